@@ -156,6 +156,8 @@ CREATE INDEX peptide_length_idx ON peptides (length);
 CREATE TABLE peptides_proteins (
     peptide_id BIGINT NOT NULL,
     protein_id BIGINT NOT NULL,
+    is_protein_n_terminus BOOLEAN DEFAULT FALSE NOT NULL,
+    is_protein_c_terminus BOOLEAN DEFAULT FALSE NOT NULL,
     PRIMARY KEY (peptide_id, protein_id)
 );
 
@@ -297,4 +299,29 @@ CREATE TABLE decoys_1 PARTITION OF decoys FOR VALUES FROM (2014015051) TO (12000
 -- CREATE TABLE decoys_96 PARTITION OF decoys FOR VALUES FROM ('10678198071') TO ('10832740213');
 -- CREATE TABLE decoys_97 PARTITION OF decoys FOR VALUES FROM ('10832740214') TO ('10994798992');
 -- CREATE TABLE decoys_98 PARTITION OF decoys FOR VALUES FROM ('10994798993') TO ('11180931067');
--- CREATE TABLE decoys_99 PARTITION OF decoys FOR VALUES FROM ('11180931068') TO ('12000000000');
\ No newline at end of file
+-- CREATE TABLE decoys_99 PARTITION OF decoys FOR VALUES FROM ('11180931068') TO ('12000000000');
+
+-- ids of decoy generation runs, all provenances of one run share the same run id
+CREATE SEQUENCE decoy_generation_run_ids;
+
+-- decoy provenances, records how a decoy was generated (a decoy may be generated several times for different precursors)
+CREATE TABLE decoy_provenances (
+    id BIGSERIAL PRIMARY KEY,
+    decoy_id BIGINT NOT NULL,
+    decoy_weight BIGINT NOT NULL,
+    modification_summary TEXT NOT NULL,
+    generation_strategy VARCHAR(20) NOT NULL,
+    seed VARCHAR(20) NOT NULL,
+    thread_count INTEGER NOT NULL,
+    precursor_mass BIGINT NOT NULL,
+    lower_precursor_tolerance_limit BIGINT NOT NULL,
+    upper_precursor_tolerance_limit BIGINT NOT NULL,
+    generated_at BIGINT NOT NULL,
+    proforma TEXT NOT NULL DEFAULT '',
+    run_id BIGINT NOT NULL,
+    FOREIGN KEY (decoy_id, decoy_weight) REFERENCES decoys (id, weight) ON DELETE CASCADE
+);
+
+-- decoy provenance indices
+CREATE INDEX decoy_provenance_decoy_idx ON decoy_provenances (decoy_id, decoy_weight);
+CREATE INDEX decoy_provenance_run_idx ON decoy_provenances (run_id);
@@ -0,0 +1,10 @@
+-- Upgrades a database created with an earlier schema.sql for protein terminal modifications.
+ALTER TABLE peptides_proteins ADD COLUMN IF NOT EXISTS is_protein_n_terminus BOOLEAN DEFAULT FALSE NOT NULL;
+ALTER TABLE peptides_proteins ADD COLUMN IF NOT EXISTS is_protein_c_terminus BOOLEAN DEFAULT FALSE NOT NULL;
+
+-- a peptide is at a protein terminus if the protein starts or ends with the peptide, peptides are stored with I and L replaced by J
+UPDATE peptides_proteins SET
+    is_protein_n_terminus = starts_with(translate(proteins.aa_sequence, 'IL', 'JJ'), trim(peptides.aa_sequence)),
+    is_protein_c_terminus = right(translate(proteins.aa_sequence, 'IL', 'JJ'), length(trim(peptides.aa_sequence))) = trim(peptides.aa_sequence)
+FROM peptides, proteins
+WHERE peptides_proteins.peptide_id = peptides.id AND peptides_proteins.protein_id = proteins.id;
@@ -11,8 +11,12 @@ extern crate time;
 extern crate csv;
 extern crate dotenv;
 extern crate url;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate toml;
 
-use std::collections::HashMap;
 use std::path::Path;
 
 use clap::{Arg, App, SubCommand};
@@ -25,116 +29,13 @@ use proteomic::utility::mz_ml::spectrum::Spectrum;
 use proteomic::tasks::identification::{identification_task, IdentificationArguments};
 use proteomic::tasks::digestion::{digest_to_database_task, DigestionArguments};
 use proteomic::tasks::sequence_mass::{sequence_mass_task, SequenceMassArguments};
+use proteomic::tasks::target_index::{build_target_index_task, TargetIndexArguments};
+use proteomic::tasks::decoy_pool::{build_decoy_pool_task, DecoyPoolArguments};
+use proteomic::tasks::decoy_generation::{decoy_generation_task, DecoyGenerationArguments};
 
-use proteomic::models::amino_acids::modification::Modification;
+use proteomic::models::amino_acids::modification_registry::ModificationRegistry;
 use proteomic::models::mass;
-
-
-fn run_decoy_generation(decoy_generation_cli_args: &clap::ArgMatches) {
-    let modification_csv_file: String = match decoy_generation_cli_args.value_of("MODIFICATION_FILE") {
-        Some(modification_csv_file) => modification_csv_file.to_owned(),
-        None => String::new()
-    };
-    let max_modifications_per_decoy: u8 = match decoy_generation_cli_args.value_of("MAX_MODIFICATION_PER_DECOY") {
-        Some(number_string) => {
-            match number_string.to_owned().parse::<u8>() {
-                Ok(number) => number,
-                Err(_) => panic!("ERROR [decoy-generation]: could not cast max-modification-per-decoy to (unsigned) integer (8 bit)")
-            }
-        },
-        None => 0
-    };
-    let number_of_decoys: usize = match decoy_generation_cli_args.value_of("NUMBER_OF_DECOYS") {
-        Some(number_string) => {
-            match number_string.to_owned().parse::<usize>() {
-                Ok(number) => number,
-                Err(_) => panic!("ERROR [decoy-generation]: could not cast number-of-decoys to unsigned integer")
-            }
-        },
-        None => 1000
-    };
-    let lower_mass_tolerance: i64 = match decoy_generation_cli_args.value_of("LOWER_MASS_TOLERANCE") {
-        Some(number_string) => {
-            match number_string.to_owned().parse::<i64>() {
-                Ok(number) => number,
-                Err(_) => panic!("ERROR [decoy-generation]: could not cast lower-mass-tolerance to integer")
-            }
-        },
-        None => 5
-    };
-    let upper_mass_tolerance: i64 = match decoy_generation_cli_args.value_of("UPPER_MASS_TOLERANCE") {
-        Some(number_string) => {
-            match number_string.to_owned().parse::<i64>() {
-                Ok(number) => number,
-                Err(_) => panic!("ERROR [decoy-generation]: could not cast upper-mass-tolerance to integer")
-            }
-        },
-        None => 5
-    };
-    let precursor_mass: i64 = match decoy_generation_cli_args.value_of("PRECURSOR_MASS") {
-        Some(number_string) => {
-            match number_string.to_owned().parse::<f64>() {
-                Ok(number) => mass::convert_mass_to_int(number),
-                Err(_) => panic!("ERROR [decoy-generation]: could not cast upper-mass-tolerance to integer")
-            }
-        },
-        None => 5
-    };
-    let cpu_thread_count: usize = num_cpus::get();
-    let thread_count: usize = match decoy_generation_cli_args.value_of("THREAD_COUNT") {
-        Some(count) => {
-            match count.to_lowercase().as_str() {
-                "max" => cpu_thread_count,
-                _ => match count.to_owned().parse::<usize>() {
-                    Ok(mut parsed_count) => {
-                        let cpu_thread_count: usize = num_cpus::get();
-                        if (1 > parsed_count) | (parsed_count > num_cpus::get()) {
-                            panic!("ERROR [decoy-generation]: threadcount must be between {} and {}.", 1, cpu_thread_count);
-                        }
-                        parsed_count
-                    } ,
-                    Err(_err) => {
-                        panic!("ERROR [decoy-generation]: cannot parse thread-count to (unsigned) integer");
-                    }
-                }
-            }
-        },
-        None => {
-            println!("WARNING [decoy-generation]: no thread-count , set it to 1.");
-            1
-        }
-    };
-    let max_time_for_decoy_generation: i64 = match decoy_generation_cli_args.value_of("MAX_TIME_FOR_DECOY_GENERATION") {
-            Some(number_string) => match number_string.to_owned().parse::<i64>() {
-                Ok(number) => number,
-                Err(_) => panic!("proteomic::tasks::identification::parse_identification_cli_arguments(): could not cast max-time-for-decoy-generation to integer")
-            },
-            None => 60
-        };
-    // prepare modifications
-    let mods = Modification::create_from_csv_file(&modification_csv_file);
-    let mut fixed_modifications_map: HashMap<char, Modification> = HashMap::new();
-    let mut variable_modifications_map: HashMap<char, Modification> = HashMap::new();
-    for modification in mods.iter() {
-        if modification.is_fix() {
-            fixed_modifications_map.insert(modification.get_amino_acid_one_letter_code(), modification.clone());
-        } else {
-            variable_modifications_map.insert(modification.get_amino_acid_one_letter_code(), modification.clone());
-        }
-    }
-    let start_time: f64 = time::precise_time_s();
-    let generator: DecoyGenerator = DecoyGenerator::new(
-        precursor_mass, upper_mass_tolerance,
-        lower_mass_tolerance, thread_count,
-        max_modifications_per_decoy,
-        &fixed_modifications_map,
-        &variable_modifications_map,
-        max_time_for_decoy_generation
-    );
-    generator.generate_decoys(number_of_decoys);
-    let stop_time: f64 = time::precise_time_s();
-    println!("generate {} decoys in {} s", number_of_decoys, stop_time - start_time);
-}
+use proteomic::models::mass::mass_type::MassType;
 
 
 fn run_amino_acid_substitution(substitution_cli_args: &clap::ArgMatches) {
@@ -161,17 +62,11 @@ fn run_amino_acid_substitution(substitution_cli_args: &clap::ArgMatches) {
         None => '_'
     };
     // prepare modifications
-    let mods = Modification::create_from_csv_file(&modification_csv_file);
-    let mut fixed_modifications_map: HashMap<char, Modification> = HashMap::new();
-    let mut variable_modifications_map: HashMap<char, Modification> = HashMap::new();
-    for modification in mods.iter() {
-        if modification.is_fix() {
-            fixed_modifications_map.insert(modification.get_amino_acid_one_letter_code(), modification.clone());
-        } else {
-            variable_modifications_map.insert(modification.get_amino_acid_one_letter_code(), modification.clone());
-        }
-    }
-    let substitution_map = *DecoyGenerator::get_one_amino_acid_substitute_map(&fixed_modifications_map);
+    let modification_registry = match ModificationRegistry::from_file(&modification_csv_file) {
+        Ok(modification_registry) => modification_registry,
+        Err(err) => panic!("ERROR [amino-acid-substitution]: {}", err)
+    };
+    let substitution_map = *DecoyGenerator::get_one_amino_acid_substitute_map(modification_registry.get_fixed_modification_map(), MassType::Monoisotopic);
     if let Some(swaps) = substitution_map.get(&source_amino_acid) {
         if let Some(weight_change) = swaps.get(&destination_amino_acid) {
             println!("{} => {} = {}", source_amino_acid, destination_amino_acid, mass::convert_mass_to_float(*weight_change));
@@ -269,6 +164,117 @@ fn main() {
             .default_value("Trypsin")
             .help("Trypsin")
         )
+        .arg(
+            Arg::with_name("TARGET_INDEX_FILE")
+            .long("target-index-file")
+            .value_name("TARGET_INDEX_FILE")
+            .takes_value(true)
+            .help("If set, an index of all targets is written to this file after the digestion (see target-index)")
+        )
+    )
+    .subcommand(
+        SubCommand::with_name("target-index")
+        .about("Writes an index (Bloom filter) of all targets to a file, which is used by the decoy generation to avoid database queries")
+        .arg(
+            Arg::with_name("TARGET_INDEX_FILE")
+            .long("target-index-file")
+            .value_name("TARGET_INDEX_FILE")
+            .required(true)
+            .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("FALSE_POSITIVE_RATE")
+            .long("false-positive-rate")
+            .value_name("FALSE_POSITIVE_RATE")
+            .takes_value(true)
+            .default_value("0.01")
+            .help("Float, rate of decoys which need to be checked in the database although they are no targets")
+        )
+    )
+    .subcommand(
+        SubCommand::with_name("decoy-pool")
+        .subcommand(
+            SubCommand::with_name("build")
+            .about("Fills the decoys-table with a number of unmodified decoys per mass bin, can be interrupted and run again to continue")
+            .arg(
+                Arg::with_name("MIN_MASS")
+                .long("min-mass")
+                .value_name("MIN_MASS")
+                .takes_value(true)
+                .default_value("500.0")
+                .help("Float, Unit: Dalton")
+            )
+            .arg(
+                Arg::with_name("MAX_MASS")
+                .long("max-mass")
+                .value_name("MAX_MASS")
+                .takes_value(true)
+                .default_value("6000.0")
+                .help("Float, Unit: Dalton")
+            )
+            .arg(
+                Arg::with_name("BIN_WIDTH")
+                .long("bin-width")
+                .value_name("BIN_WIDTH")
+                .takes_value(true)
+                .default_value("1.0")
+                .help("Float, Unit: Dalton")
+            )
+            .arg(
+                Arg::with_name("DECOYS_PER_BIN")
+                .short("n")
+                .long("decoys-per-bin")
+                .value_name("DECOYS_PER_BIN")
+                .takes_value(true)
+                .default_value("1000")
+            )
+            .arg(
+                Arg::with_name("THREAD_COUNT")
+                .short("t")
+                .long("thread-count")
+                .value_name("THREAD_COUNT")
+                .takes_value(true)
+                .default_value("2")
+            )
+            .arg(
+                Arg::with_name("MAX_TIME_PER_BIN")
+                .long("max-time-per-bin")
+                .value_name("MAX_TIME_PER_BIN")
+                .takes_value(true)
+                .default_value("60")
+                .help("Integer, Unit: seconds")
+            )
+            .arg(
+                Arg::with_name("SEED")
+                .long("seed")
+                .value_name("SEED")
+                .takes_value(true)
+                .help("Unsigned integer (64 bit), the seed for each bin is derived from this seed and the bin start. A random seed is used if omitted.")
+            )
+            .arg(
+                Arg::with_name("GENERATION_STRATEGY")
+                .long("generation-strategy")
+                .value_name("GENERATION_STRATEGY")
+                .takes_value(true)
+                .default_value("random-walk")
+                .possible_values(&["random-walk", "composition"])
+            )
+            .arg(
+                Arg::with_name("TARGET_INDEX_FILE")
+                .long("target-index-file")
+                .value_name("TARGET_INDEX_FILE")
+                .takes_value(true)
+                .help("Target index (see target-index), decoys are only checked in the database if they might be a target")
+            )
+            .arg(
+                Arg::with_name("MASS_TYPE")
+                .long("mass-type")
+                .value_name("MASS_TYPE")
+                .takes_value(true)
+                .possible_values(&["mono", "average"])
+                .help("Mass type of the bins, 'average' for an identification of low-resolution data (default: mono). The weights in the database stay monoisotopic.")
+            )
+        )
     )
     .subcommand(
         SubCommand::with_name("decoy-generation")
@@ -294,6 +300,33 @@ fn main() {
             .long("precursor-mass")
             .value_name("PRECURSOR_MASS")
             .takes_value(true)
+            .required_unless("PRECURSOR_FILE")
+            .help("Float, neutral mass in Dalton")
+        )
+        .arg(
+            Arg::with_name("PRECURSOR_FILE")
+            .long("precursor-file")
+            .value_name("PRECURSOR_FILE")
+            .takes_value(true)
+            .conflicts_with("PRECURSOR_MASS")
+            .help("CSV- or TSV-file (extension .tsv) with a header and either the column 'mass' (neutral mass in Dalton) or the columns 'mz' and 'charge', the column 'id' is optional")
+        )
+        .arg(
+            Arg::with_name("OUTPUT_DIRECTORY")
+            .short("o")
+            .long("output-directory")
+            .value_name("OUTPUT_DIRECTORY")
+            .takes_value(true)
+            .default_value(".")
+            .help("Directory for the decoy files, one file per precursor named by the precursor id")
+        )
+        .arg(
+            Arg::with_name("OUTPUT_FORMAT")
+            .long("output-format")
+            .value_name("OUTPUT_FORMAT")
+            .takes_value(true)
+            .default_value("fasta")
+            .possible_values(&["fasta", "tsv"])
         )
         .arg(
             Arg::with_name("NUMBER_OF_DECOYS")
@@ -337,6 +370,80 @@ fn main() {
             .default_value("60")
             .help("Integer, Unit: seconds")
         )
+        .arg(
+            Arg::with_name("SEED")
+            .long("seed")
+            .value_name("SEED")
+            .takes_value(true)
+            .help("Unsigned integer (64 bit), makes the decoy generation reproducible for the same thread count. A random seed is used if omitted.")
+        )
+        .arg(
+            Arg::with_name("GENERATION_STRATEGY")
+            .long("generation-strategy")
+            .value_name("GENERATION_STRATEGY")
+            .takes_value(true)
+            .default_value("random-walk")
+            .possible_values(&["random-walk", "composition"])
+            .help("random-walk: adds random amino acids and swaps them to hit the precursor tolerance, composition: draws amino acid compositions which hit the precursor tolerance (bounded time)")
+        )
+        .arg(
+            Arg::with_name("TARGET_INDEX_FILE")
+            .long("target-index-file")
+            .value_name("TARGET_INDEX_FILE")
+            .takes_value(true)
+            .help("Target index (see target-index), decoys are only checked in the database if they might be a target")
+        )
+        .arg(
+            Arg::with_name("DECOY_LENGTH_DISTRIBUTION")
+            .long("decoy-length-distribution")
+            .value_name("DECOY_LENGTH_DISTRIBUTION")
+            .takes_value(true)
+            .default_value("none")
+            .possible_values(&["none", "range"])
+            .help("none: decoy lengths result from the generation strategy, range: decoy lengths are distributed uniformly between minimum and maximum peptide length")
+        )
+        .arg(
+            Arg::with_name("MIN_PEPTIDE_LENGTH")
+            .long("minimum-peptide_length")
+            .value_name("MIN_PEPTIDE_LENGTH")
+            .takes_value(true)
+            .default_value("5")
+            .help("Minimal decoy length, used by the length distribution (should match the digestion)")
+        )
+        .arg(
+            Arg::with_name("MAX_PEPTIDE_LENGTH")
+            .long("maximum-peptide_length")
+            .value_name("MAX_PEPTIDE_LENGTH")
+            .takes_value(true)
+            .default_value("50")
+            .help("Maximal decoy length, used by the length distribution (should match the digestion), maximal: 60")
+        )
+        .arg(
+            Arg::with_name("MASS_FITTING_ITERATIONS")
+            .long("mass-fitting-iterations")
+            .value_name("MASS_FITTING_ITERATIONS")
+            .takes_value(true)
+            .default_value("100")
+            .help("Unsigned integer, maximal number of substitution rounds for fitting a random walk decoy into the precursor tolerance")
+        )
+        .arg(
+            Arg::with_name("PAIR_SUBSTITUTIONS")
+            .long("pair-substitutions")
+            .help("Substitutes two amino acids at once while fitting a random walk decoy into the precursor tolerance, which converges faster for narrow tolerances")
+        )
+        .arg(
+            Arg::with_name("LENGTH_CHANGES")
+            .long("length-changes")
+            .help("Inserts and removes amino acids (within minimum and maximum peptide length) while fitting a random walk decoy into the precursor tolerance. Ignored if a decoy length distribution is used.")
+        )
+        .arg(
+            Arg::with_name("MASS_TYPE")
+            .long("mass-type")
+            .value_name("MASS_TYPE")
+            .takes_value(true)
+            .possible_values(&["mono", "average"])
+            .help("Mass type of the precursor masses and of the decoys, 'average' for low-resolution data (default: mono). With 'average' every modification needs an average mass, which CSV-files do not provide.")
+        )
     )
     .subcommand(
         SubCommand::with_name("spectrum-splitup")
@@ -400,8 +507,26 @@ fn main() {
             .short("m")
             .long("modification-file")
             .value_name("INPUT_FILE")
-            .required(true)
             .takes_value(true)
+            .required_unless("UNIMOD_MODIFICATION")
+            .help("CSV-, TOML- or JSON-file with modifications")
+        )
+        .arg(
+            Arg::with_name("UNIMOD_FILE")
+            .long("unimod-file")
+            .value_name("UNIMOD_FILE")
+            .takes_value(true)
+            .help("Local copy of unimod.xml")
+        )
+        .arg(
+            Arg::with_name("UNIMOD_MODIFICATION")
+            .long("unimod-modification")
+            .value_name("UNIMOD_MODIFICATION")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .requires("UNIMOD_FILE")
+            .help("Modification from the unimod-file, e.g. \"UNIMOD:35@M variable\" or \"UNIMOD:1@Protein N-term variable\". Can be used multiple times.")
         )
         .arg(
             Arg::with_name("SPECTRUM_FILE")
@@ -482,6 +607,154 @@ fn main() {
             .takes_value(true)
             .help("Revision string from first line of comet params file (must match your comet version), e.g.: # comet_version 2019.01 rev. 4")
         )
+        .arg(
+            Arg::with_name("SEED")
+            .long("seed")
+            .value_name("SEED")
+            .takes_value(true)
+            .help("Unsigned integer (64 bit), makes the decoy generation reproducible for the same thread count. The seed for each spectrum is derived from this seed and the spectrum id. A random seed is used if omitted.")
+        )
+        .arg(
+            Arg::with_name("GENERATION_STRATEGY")
+            .long("generation-strategy")
+            .value_name("GENERATION_STRATEGY")
+            .takes_value(true)
+            .default_value("random-walk")
+            .possible_values(&["random-walk", "composition"])
+            .help("random-walk: adds random amino acids and swaps them to hit the precursor tolerance, composition: draws amino acid compositions which hit the precursor tolerance (bounded time)")
+        )
+        .arg(
+            Arg::with_name("TARGET_INDEX_FILE")
+            .long("target-index-file")
+            .value_name("TARGET_INDEX_FILE")
+            .takes_value(true)
+            .help("Target index (see target-index), decoys are only checked in the database if they might be a target")
+        )
+        .arg(
+            Arg::with_name("DECOY_LENGTH_DISTRIBUTION")
+            .long("decoy-length-distribution")
+            .value_name("DECOY_LENGTH_DISTRIBUTION")
+            .takes_value(true)
+            .default_value("none")
+            .possible_values(&["none", "targets", "range"])
+            .help("none: decoy lengths result from the generation strategy, targets: decoy lengths follow the lengths of the spectrum's targets (falls back to range if there are no targets), range: decoy lengths are distributed uniformly between minimum and maximum peptide length")
+        )
+        .arg(
+            Arg::with_name("MIN_PEPTIDE_LENGTH")
+            .long("minimum-peptide_length")
+            .value_name("MIN_PEPTIDE_LENGTH")
+            .takes_value(true)
+            .default_value("5")
+            .help("Minimal decoy length, used by the length distribution (should match the digestion)")
+        )
+        .arg(
+            Arg::with_name("MAX_PEPTIDE_LENGTH")
+            .long("maximum-peptide_length")
+            .value_name("MAX_PEPTIDE_LENGTH")
+            .takes_value(true)
+            .default_value("50")
+            .help("Maximal decoy length, used by the length distribution (should match the digestion), maximal: 60")
+        )
+        .arg(
+            Arg::with_name("SIMILARITY_MAX_EDIT_DISTANCE")
+            .long("similarity-max-edit-distance")
+            .value_name("SIMILARITY_MAX_EDIT_DISTANCE")
+            .takes_value(true)
+            .help("Unsigned integer, decoys with an edit distance less or equals to this value to one of the spectrum's targets are rejected. Disabled if omitted.")
+        )
+        .arg(
+            Arg::with_name("SIMILARITY_MAX_SHARED_FRAGMENT_RATIO")
+            .long("similarity-max-shared-fragment-ratio")
+            .value_name("SIMILARITY_MAX_SHARED_FRAGMENT_RATIO")
+            .takes_value(true)
+            .help("Float between 0 and 1, decoys which share at least this ratio of their b- and y-ions (within the fragmentation tolerance) with one of the spectrum's targets are rejected. Disabled if omitted.")
+        )
+        .arg(
+            Arg::with_name("SIMILARITY_KMER_LENGTH")
+            .long("similarity-kmer-length")
+            .value_name("SIMILARITY_KMER_LENGTH")
+            .takes_value(true)
+            .help("Unsigned integer, decoys which share a subsequence of this length with one of the spectrum's targets are rejected. Disabled if omitted.")
+        )
+        .arg(
+            Arg::with_name("MATCH_TARGET_COMPOSITION")
+            .long("match-target-composition")
+            .help("Biases the amino acids of generated decoys towards the amino acid composition of the spectrum's targets")
+        )
+        .arg(
+            Arg::with_name("MATCH_TARGET_PROPERTIES")
+            .long("match-target-properties")
+            .value_name("MATCH_TARGET_PROPERTIES")
+            .takes_value(true)
+            .help("Comma separated list of gravy (hydropathy), pi (isoelectric point) and rt (retention index), decoys whose properties are outside the spread of the spectrum's targets are rejected. Disabled if omitted.")
+        )
+        .arg(
+            Arg::with_name("PROPERTY_MARGIN")
+            .long("property-margin")
+            .value_name("PROPERTY_MARGIN")
+            .takes_value(true)
+            .default_value("0.1")
+            .help("Float, widens the spread of the targets' properties on both sides by this fraction of its width")
+        )
+        .arg(
+            Arg::with_name("LOOKUP_ONLY")
+            .long("lookup-only")
+            .help("Uses only decoys which are already stored (e.g. by decoy-pool build) and does not generate new decoys")
+        )
+        .arg(
+            Arg::with_name("PEFF_FORMAT")
+            .long("peff-format")
+            .value_name("PEFF_FORMAT")
+            .takes_value(true)
+            .possible_values(&["unimod", "psi-mod"])
+            .requires("PEFF_OBO")
+            .help("Writes a PEFF-file with the variable modifications of each target and decoy at explicit positions instead of a FASTA-file, annotated with the key of the given controlled vocabulary (\\ModResUnimod or \\ModResPsi). Every variable modification needs an accession of this vocabulary. Comet searches the annotated modifications as optional variants, so the unmodified forms are searched too. With --enumerate-isoforms each isoform is written as an own entry.")
+        )
+        .arg(
+            Arg::with_name("PEFF_OBO")
+            .long("peff-obo")
+            .value_name("PEFF_OBO")
+            .takes_value(true)
+            .help("OBO-file of the PEFF format's controlled vocabulary (unimod.obo or PSI-MOD.obo), passed to Comet")
+        )
+        .arg(
+            Arg::with_name("MASS_TYPE")
+            .long("mass-type")
+            .value_name("MASS_TYPE")
+            .takes_value(true)
+            .possible_values(&["mono", "average"])
+            .help("Mass type of the precursor and fragment masses, 'average' for low-resolution data (default: mono). Used for the precursor tolerance, the fitting of the decoys and Comet's mass_type_parent and mass_type_fragment. With 'average' every modification needs an average mass, which CSV-files do not provide.")
+        )
+        .arg(
+            Arg::with_name("ENUMERATE_ISOFORMS")
+            .long("enumerate-isoforms")
+            .help("Lists every isoform of a target which fits the precursor tolerance in ProForma notation in the target's header (Isoforms=PM[UNIMOD:35]EPMK,PMEPM[UNIMOD:35]K)")
+        )
+        .arg(
+            Arg::with_name("MAX_DECOY_REUSE")
+            .long("max-decoy-reuse")
+            .value_name("MAX_DECOY_REUSE")
+            .takes_value(true)
+            .help("Unsigned integer, enables the run-level decoy cache: decoys of previous spectra are reused by spectra whose precursor tolerance contains them, each decoy at most this many times. Disabled if omitted.")
+        )
+        .arg(
+            Arg::with_name("MASS_FITTING_ITERATIONS")
+            .long("mass-fitting-iterations")
+            .value_name("MASS_FITTING_ITERATIONS")
+            .takes_value(true)
+            .default_value("100")
+            .help("Unsigned integer, maximal number of substitution rounds for fitting a random walk decoy into the precursor tolerance")
+        )
+        .arg(
+            Arg::with_name("PAIR_SUBSTITUTIONS")
+            .long("pair-substitutions")
+            .help("Substitutes two amino acids at once while fitting a random walk decoy into the precursor tolerance, which converges faster for narrow tolerances")
+        )
+        .arg(
+            Arg::with_name("LENGTH_CHANGES")
+            .long("length-changes")
+            .help("Inserts and removes amino acids (within minimum and maximum peptide length) while fitting a random walk decoy into the precursor tolerance. Ignored if a decoy length distribution is used.")
+        )
     )
     .subcommand(
         SubCommand::with_name("sequence-mass")
@@ -492,6 +765,15 @@ fn main() {
             .value_name("SEQUENCE")
             .required(true)
             .takes_value(true)
+            .help("Plain sequence or ProForma 2.0 string, e.g. [UNIMOD:1]-PEPM[UNIMOD:35]K or [+15.995]?PEPMK")
+        )
+        .arg(
+            Arg::with_name("MODIFICATION_FILE")
+            .short("m")
+            .long("modification-file")
+            .value_name("MODIFICATION_FILE")
+            .takes_value(true)
+            .help("Modification file (CSV, TOML or JSON) to resolve modification names and accessions of the ProForma string")
         )
     )
     .get_matches();
@@ -501,8 +783,18 @@ fn main() {
         let digestion_args = DigestionArguments::from_cli_args(cli_args);
         digest_to_database_task(&digestion_args);
     }
+    if let Some(cli_args) = matches.subcommand_matches("target-index") {
+        let target_index_args = TargetIndexArguments::from_cli_args(cli_args);
+        build_target_index_task(&target_index_args);
+    }
+    if let Some(decoy_pool_cli_args) = matches.subcommand_matches("decoy-pool") {
+        if let Some(cli_args) = decoy_pool_cli_args.subcommand_matches("build") {
+            let decoy_pool_args = DecoyPoolArguments::from_cli_args(cli_args);
+            build_decoy_pool_task(&decoy_pool_args);
+        }
+    }
     if let Some(cli_args) = matches.subcommand_matches("decoy-generation") {
-        run_decoy_generation(cli_args);
+        decoy_generation_task(&DecoyGenerationArguments::from_cli_args(cli_args));
     }
     if let Some(cli_args) = matches.subcommand_matches("spectrum-splitup") {
         run_spectrum_splitup(cli_args)
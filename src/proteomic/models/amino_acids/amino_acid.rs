@@ -1,5 +1,6 @@
 use proteomic::models::mass;
 use proteomic::models::mass::neutral_loss::NeutralLoss;
+use proteomic::models::mass::mass_type::MassType;
 
 pub const AMINO_ACIDS_FOR_DECOY_GENERATION: &'static [char] = &['A', 'R', 'N', 'D', 'C', 'E', 'Q', 'G', 'H', 'J', 'K', 'M', 'F', 'P', 'O', 'S', 'T', 'U', 'V', 'W', 'Y'];
 pub const AMINO_ACIDS_FOR_COUNTING: &'static [char] = &['A', 'R', 'N', 'D', 'C', 'E', 'Q', 'G', 'H', 'J', 'K', 'M', 'F', 'P', 'O', 'S', 'T', 'U', 'V', 'W', 'Y'];
@@ -80,6 +81,13 @@ impl AminoAcid {
         return self.average_mass;
     }
 
+    pub fn get_mass(&self, mass_type: MassType) -> i64 {
+        return match mass_type {
+            MassType::Monoisotopic => self.mono_mass,
+            MassType::Average => self.average_mass
+        };
+    }
+
     pub fn get_distribution(&self) -> i8 {
         return self.distribution;
     }
@@ -128,9 +136,14 @@ impl AminoAcid {
 
     /// Calculats the weight of a sequence
     pub fn get_sequence_weight(sequence: &str) -> i64 {
-        let mut weight: i64 = NeutralLoss::get("H2O").get_mono_mass();
+        return Self::get_sequence_mass(sequence, MassType::Monoisotopic);
+    }
+
+    /// Calculats the monoisotopic or average mass of a sequence
+    pub fn get_sequence_mass(sequence: &str, mass_type: MassType) -> i64 {
+        let mut weight: i64 = NeutralLoss::get("H2O").get_mass(mass_type);
         for amino_acid_one_letter_code in sequence.chars() {
-            weight += AminoAcid::get(amino_acid_one_letter_code).get_mono_mass();
+            weight += AminoAcid::get(amino_acid_one_letter_code).get_mass(mass_type);
         }
         return weight;
     }
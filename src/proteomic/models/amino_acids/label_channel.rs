@@ -0,0 +1,45 @@
+use proteomic::models::amino_acids::modification::Modification;
+
+/// Channel of an isotope label set, e.g. the heavy channel of a SILAC experiment with 13C(6)15N(2)-K and 13C(6)15N(4)-R.
+/// A peptide matches a channel only if all its labelable residues carry the label of this channel.
+/// The light channel has no labels.
+pub struct LabelChannel {
+    name: String,
+    labels: Vec<Modification>
+}
+
+impl LabelChannel {
+    pub fn new(name: &str) -> Self {
+        return Self {
+            name: name.trim().to_owned(),
+            labels: Vec::new()
+        };
+    }
+
+    pub fn get_name(&self) -> &str {
+        return self.name.as_str();
+    }
+
+    pub fn get_labels(&self) -> &Vec<Modification> {
+        return &self.labels;
+    }
+
+    /// Adds the label, which is applied like a fixed modification.
+    /// Returns an error message if the channel has already a label for the amino acid.
+    pub fn add_label(&mut self, label: Modification) -> Result<(), String> {
+        if let Some(existing_label) = self.labels.iter().find(|existing_label| existing_label.get_amino_acid_one_letter_code() == label.get_amino_acid_one_letter_code()) {
+            return Err(format!("channel '{}' has already the label '{}' for '{}', can not add '{}'", self.name, existing_label.get_accession(), label.get_amino_acid_one_letter_code(), label.get_accession()));
+        }
+        self.labels.push(label);
+        return Ok(());
+    }
+}
+
+impl Clone for LabelChannel {
+    fn clone(&self) -> LabelChannel {
+        return Self {
+            name: self.name.clone(),
+            labels: self.labels.clone()
+        };
+    }
+}
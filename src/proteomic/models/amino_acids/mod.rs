@@ -1,2 +1,5 @@
 pub mod amino_acid;
-pub mod modification;
\ No newline at end of file
+pub mod label_channel;
+pub mod modification;
+pub mod modification_config;
+pub mod modification_registry;
\ No newline at end of file
@@ -1,16 +1,22 @@
 use std::hash::{Hash, Hasher};
-use std::path::Path;
 
 use proteomic::models::mass;
+use proteomic::models::mass::neutral_loss::NeutralLoss;
+use proteomic::models::mass::mass_type::MassType;
 use proteomic::utility::database_connection::DatabaseConnection;
 use proteomic::models::amino_acids::amino_acid::AminoAcid;
+use proteomic::models::amino_acids::modification_config;
 
+/// Amino acid one letter code of terminal modifications which apply to any amino acid at the terminus, e.g. TMT/iTRAQ labels or carbamylation
+pub const ANY_AMINO_ACID: char = '*';
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum ModificationPosition {
     Anywhere,
-    NTerminus,
-    CTerminus
+    NTerminus,          // n-terminus of the peptide
+    CTerminus,          // c-terminus of the peptide
+    ProteinNTerminus,   // n-terminus of a peptide which is the n-terminus of a protein
+    ProteinCTerminus    // c-terminus of a peptide which is the c-terminus of a protein
 }
 
 impl ModificationPosition {
@@ -19,18 +25,36 @@ impl ModificationPosition {
         return match self {
             ModificationPosition::Anywhere => "Anywhere".to_string(),
             ModificationPosition::CTerminus => "C-Terminus".to_string(),
-            ModificationPosition::NTerminus => "N-Terminus".to_string()
+            ModificationPosition::NTerminus => "N-Terminus".to_string(),
+            ModificationPosition::ProteinCTerminus => "Protein-C-Terminus".to_string(),
+            ModificationPosition::ProteinNTerminus => "Protein-N-Terminus".to_string()
         }
     }
 
-    fn from_char(position_char: char) -> ModificationPosition {
-        return match position_char.to_ascii_uppercase() {
-            'A' => ModificationPosition::Anywhere,
-            'C' => ModificationPosition::CTerminus,
-            'N' => ModificationPosition::NTerminus,
-            _ => panic!("proteomic::models::amino_acids::modification::ModificationPosition::from_char(): Could not parse char to ModificationPosititon")
+    /// Parses the position of modification files: A (anywhere), N and C (peptide termini), PN and PC (protein termini).
+    /// Returns None for unknown positions.
+    pub fn from_str(position_str: &str) -> Option<ModificationPosition> {
+        return match position_str.trim().to_ascii_uppercase().as_str() {
+            "A" => Some(ModificationPosition::Anywhere),
+            "C" => Some(ModificationPosition::CTerminus),
+            "N" => Some(ModificationPosition::NTerminus),
+            "PC" => Some(ModificationPosition::ProteinCTerminus),
+            "PN" => Some(ModificationPosition::ProteinNTerminus),
+            _ => None
         }
     }
+
+    pub fn is_n_terminal(&self) -> bool {
+        return (*self == ModificationPosition::NTerminus) | (*self == ModificationPosition::ProteinNTerminus);
+    }
+
+    pub fn is_c_terminal(&self) -> bool {
+        return (*self == ModificationPosition::CTerminus) | (*self == ModificationPosition::ProteinCTerminus);
+    }
+
+    pub fn is_protein_terminal(&self) -> bool {
+        return (*self == ModificationPosition::ProteinNTerminus) | (*self == ModificationPosition::ProteinCTerminus);
+    }
 }
 
 pub struct Modification {
@@ -39,11 +63,18 @@ pub struct Modification {
     position: ModificationPosition,
     is_fix: bool,
     amino_acid_one_letter_code: char,
-    mono_mass: i64
+    mono_mass: i64,
+    average_mass: i64,
+    has_average_mass: bool,
+    composition: String,
+    neutral_losses: Vec<NeutralLoss>
 }
 
 impl Modification {
     pub fn new(accession: &str, name: &str, position: ModificationPosition, is_fix: bool, amino_acid_one_letter_code: char, mono_mass: f64) -> Self {
+        if (amino_acid_one_letter_code == ANY_AMINO_ACID) & (position == ModificationPosition::Anywhere) {
+            panic!("proteomic::models::amino_acids::modification::Modification::new(): modification '{}' for any amino acid must be terminal", accession);
+        }
         return Self{
             accession: accession.to_owned().to_lowercase().trim().to_string(),
             name: name.to_owned().trim().to_string(),
@@ -51,34 +82,58 @@ impl Modification {
             is_fix: is_fix,
             amino_acid_one_letter_code: amino_acid_one_letter_code.to_ascii_uppercase(),
             mono_mass: mass::convert_mass_to_int(mono_mass),
+            // without further information the average mass is approximated by the mono mass
+            average_mass: mass::convert_mass_to_int(mono_mass),
+            has_average_mass: false,
+            composition: String::new(),
+            neutral_losses: Vec::new()
         };
     }
 
-    fn new_from_csv_row(row: &csv::StringRecord) -> Modification {
-        // check if row has correct length
-        if row.len() != 6 {
-            panic!("row has wrong length");
-        }
-        // type casts
-        let position_char: char = row[2].trim().chars().next().expect("can not get position char");
-        let is_fix_int: i8 = row[3].trim().parse::<i8>().expect("cannot parse is fix to integer");
-        let amino_acid_one_letter_code: char = row[4].trim().chars().next().expect("can not get amino acid one letter code");
-        let mono_mass: f64 = row[5].trim().parse::<f64>().expect("can not parse mono mass to float");
-        // return modification
-        return Self::new(
-            &row[0],
-            &row[1],
-            ModificationPosition::from_char(position_char),
-            is_fix_int > 0,
-            amino_acid_one_letter_code,
-            mono_mass
-        )
-    }
-
     pub fn get_mono_mass(&self) -> i64 {
         return self.mono_mass;
     }
 
+    pub fn get_average_mass(&self) -> i64 {
+        return self.average_mass;
+    }
+
+    pub fn get_mass(&self, mass_type: MassType) -> i64 {
+        return match mass_type {
+            MassType::Monoisotopic => self.mono_mass,
+            MassType::Average => self.average_mass
+        };
+    }
+
+    pub fn set_average_mass(&mut self, average_mass: f64) {
+        self.average_mass = mass::convert_mass_to_int(average_mass);
+        self.has_average_mass = true;
+    }
+
+    /// Returns false if the average mass is approximated by the mono mass, because it was not given
+    pub fn has_average_mass(&self) -> bool {
+        return self.has_average_mass;
+    }
+
+    /// Returns the elemental composition in Unimod notation, e.g. "H(3) O(3) P", which is empty if unknown
+    pub fn get_composition(&self) -> &str {
+        return &self.composition;
+    }
+
+    pub fn set_composition(&mut self, composition: &str) {
+        self.composition = composition.trim().to_owned();
+    }
+
+    pub fn get_neutral_losses(&self) -> &Vec<NeutralLoss> {
+        return &self.neutral_losses;
+    }
+
+    pub fn add_neutral_loss(&mut self, neutral_loss: NeutralLoss) {
+        if !self.neutral_losses.contains(&neutral_loss) {
+            self.neutral_losses.push(neutral_loss);
+        }
+    }
+
     pub fn get_accession(&self) -> &str {
         return &self.accession;
     }
@@ -99,6 +154,11 @@ impl Modification {
         return self.amino_acid_one_letter_code;
     }
 
+    /// Returns true if the modification applies to the given amino acid
+    pub fn matches_amino_acid(&self, amino_acid_one_letter_code: char) -> bool {
+        return (self.amino_acid_one_letter_code == ANY_AMINO_ACID) | (self.amino_acid_one_letter_code == amino_acid_one_letter_code);
+    }
+
     #[allow(dead_code)]
     pub fn to_string(&self) -> String {
         return format!(
@@ -113,62 +173,77 @@ impl Modification {
     }
 
     pub fn create_from_csv_file(modification_csv_file_path: &str) -> Box<Vec<Modification>> {
-        let mut modifications: Vec<Modification> = Vec::new();
-        let csv_path = Path::new(modification_csv_file_path);
-        let mut reader = match csv::Reader::from_path(&csv_path) {
-            Ok(reader) => reader,
-            Err(_) => panic!("something went wrong when reading the modifiication csv file. is the file existing and do you have permission to read it?")
+        return match modification_config::read_csv_file(modification_csv_file_path) {
+            Ok(modifications) => Box::new(modifications),
+            Err(err) => panic!("proteomic::models::amino_acids::modification::Modification::create_from_csv_file(): {}", err)
         };
-        for row_result in reader.records() {
-            let row = match row_result {
-                Ok(row) => row,
-                Err(err) => panic!("proteomic::models::amino_acids::modification::Modification::create_from_csv_file(): Error reading csv-line, see: {}", err)
-            };
-            modifications.push(Self::new_from_csv_row(&row));
-        }
-        return Box::new(modifications);
     }
 
-    pub fn to_comet_static_modification_param(&self) -> String {;
+    pub fn to_comet_static_modification_param(&self, mass_type: MassType) -> String {
+        if self.amino_acid_one_letter_code == ANY_AMINO_ACID {
+            return format!(
+                "add_{}term_{} = {}",
+                if self.position.is_n_terminal() { "N" } else { "C" },
+                if self.position.is_protein_terminal() { "protein" } else { "peptide" },
+                mass::convert_mass_to_float(self.get_mass(mass_type))
+            );
+        }
         let amino_acid = AminoAcid::get(self.amino_acid_one_letter_code);
         if self.amino_acid_one_letter_code.to_ascii_uppercase() != 'J' {
             return  format!(
                 "add_{}_{} = {}",
                 self.amino_acid_one_letter_code.to_ascii_uppercase(),
                 amino_acid.get_name().to_lowercase(),
-                mass::convert_mass_to_float(self.mono_mass)
+                mass::convert_mass_to_float(self.get_mass(mass_type))
             );
         } else {
             return format!(
                 "add_{}_user_amino_acid = {}",
                 self.amino_acid_one_letter_code.to_ascii_uppercase(),
-                mass::convert_mass_to_float(amino_acid.get_mono_mass() + self.mono_mass)
+                mass::convert_mass_to_float(amino_acid.get_mass(mass_type) + self.get_mass(mass_type))
             );
         }
     }
 
-    pub fn to_comet_variable_modification_param(&self, modification_number: u8, max_number_of_variable_modification_per_peptide: u8) -> String {
+    /// Returns the variable modification parameter for the given amino acids, which share the mass and position of this modification
+    pub fn to_comet_variable_modification_param(&self, modification_number: u8, amino_acid_one_letter_codes: &str, max_number_of_variable_modification_per_peptide: u8, mass_type: MassType) -> String {
         if modification_number > 9 { panic!("proteomic::models::amino_acids::modification::Modification.to_comet_variable_modification_param(): modification_number is not a number from 0 to 9") }
         let distant_to_terminus: i8 = match self.position {
             ModificationPosition::Anywhere => -1,
-            ModificationPosition::CTerminus => 0,
-            ModificationPosition::NTerminus => 0
+            _ => 0
         };
         let distant_refere_to_terminus: u8 = match self.position {
             ModificationPosition::Anywhere => 0,
-            ModificationPosition::CTerminus => 3,
-            ModificationPosition::NTerminus => 2
+            ModificationPosition::ProteinNTerminus => 0,
+            ModificationPosition::ProteinCTerminus => 1,
+            ModificationPosition::NTerminus => 2,
+            ModificationPosition::CTerminus => 3
         };
+        // Comet uses 'n' and 'c' for any amino acid at the n- and c-terminus
+        let residues: String = amino_acid_one_letter_codes.chars().map(|amino_acid_one_letter_code| match amino_acid_one_letter_code {
+            ANY_AMINO_ACID if self.position.is_n_terminal() => 'n',
+            ANY_AMINO_ACID => 'c',
+            _ => amino_acid_one_letter_code.to_ascii_uppercase()
+        }).collect();
         return format!(
-            "variable_mod0{} = {} {} 0 {} {} {} 0",
+            "variable_mod0{} = {} {} 0 {} {} {} 0 {}",
             modification_number,
-            mass::convert_mass_to_float(self.mono_mass),
-            self.get_amino_acid_one_letter_code().to_uppercase(),
+            mass::convert_mass_to_float(self.get_mass(mass_type)),
+            residues,
             max_number_of_variable_modification_per_peptide,
             distant_to_terminus,
-            distant_refere_to_terminus
+            distant_refere_to_terminus,
+            mass::convert_mass_to_float(self.get_comet_neutral_loss(mass_type))
         );
     }
+
+    /// Returns the mass of the neutral loss which is passed to Comet. Comet supports only one neutral loss per variable modification, so the first one is used.
+    pub fn get_comet_neutral_loss(&self, mass_type: MassType) -> i64 {
+        return match self.neutral_losses.first() {
+            Some(neutral_loss) => neutral_loss.get_mass(mass_type),
+            None => 0
+        };
+    }
 }
 
 impl Clone for Modification {
@@ -179,7 +254,11 @@ impl Clone for Modification {
             position: self.position.clone(),
             is_fix: self.is_fix,
             amino_acid_one_letter_code: self.amino_acid_one_letter_code.clone(),
-            mono_mass: self.mono_mass
+            mono_mass: self.mono_mass,
+            average_mass: self.average_mass,
+            has_average_mass: self.has_average_mass,
+            composition: self.composition.clone(),
+            neutral_losses: self.neutral_losses.clone()
         };
     }
 }
@@ -0,0 +1,384 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use proteomic::models::amino_acids::amino_acid::AMINO_ACIDS_FOR_COUNTING;
+use proteomic::models::amino_acids::label_channel::LabelChannel;
+use proteomic::models::amino_acids::modification::{Modification, ModificationPosition, ANY_AMINO_ACID};
+use proteomic::models::mass::neutral_loss::NeutralLoss;
+use proteomic::utility::unimod_reader::{UnimodReader, UnimodDefinition};
+
+/// Location of an error in a modification file.
+/// The parsers do not provide the positions of valid TOML- and JSON-values, so errors in their entries refer to the entry instead of a line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigLocation {
+    Unknown,
+    Line(usize),                // starting at 1
+    Entry(String, usize)        // array of the entry, e.g. 'modification' or 'label_channel 2 label', and its number starting at 1
+}
+
+impl ConfigLocation {
+    fn format(&self, file: &str) -> String {
+        return match self {
+            ConfigLocation::Unknown => file.to_owned(),
+            ConfigLocation::Line(line) => format!("{}:{}", file, line),
+            ConfigLocation::Entry(array, number) => format!("{}, {} {}", file, array, number)
+        };
+    }
+}
+
+/// Errors of modification files
+pub enum ModificationConfigError {
+    IOError(String, String),                                    // file, message
+    ParseError(String, ConfigLocation, String),                 // file, location, message
+    InvalidField(String, ConfigLocation, String, String),       // file, location, field, message
+    Conflict(String, String)                                    // file, message
+}
+
+impl ModificationConfigError {
+    pub fn to_string(&self) -> String {
+        return match self {
+            ModificationConfigError::IOError(file, err) => format!("ModificationConfigError::IOError({}: {})", file, err),
+            ModificationConfigError::ParseError(file, location, err) => format!("ModificationConfigError::ParseError({}: {})", location.format(file), err),
+            ModificationConfigError::InvalidField(file, location, field, err) => format!("ModificationConfigError::InvalidField({}: field '{}': {})", location.format(file), field, err),
+            ModificationConfigError::Conflict(file, err) => format!("ModificationConfigError::Conflict({}: {})", file, err)
+        };
+    }
+}
+
+impl fmt::Display for ModificationConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", self.to_string());
+    }
+}
+
+/// Modifications and neutral losses of a modification file
+pub struct ModificationFileContent {
+    modifications: Vec<Modification>,
+    general_neutral_losses: Vec<NeutralLoss>,
+    label_channels: Vec<LabelChannel>
+}
+
+impl ModificationFileContent {
+    pub fn get_modifications(&self) -> &Vec<Modification> {
+        return &self.modifications;
+    }
+
+    /// Returns the neutral losses which apply to all fragments, e.g. NH3
+    pub fn get_general_neutral_losses(&self) -> &Vec<NeutralLoss> {
+        return &self.general_neutral_losses;
+    }
+
+    pub fn get_label_channels(&self) -> &Vec<LabelChannel> {
+        return &self.label_channels;
+    }
+}
+
+/// Modification file in TOML or JSON, e.g.
+/// ```toml
+/// unimod_file = "unimod.xml"
+/// neutral_losses = ["NH3"]
+///
+/// [[modification]]
+/// accession = "unimod:4"
+/// name = "Carbamidomethyl"
+/// position = "A"
+/// fixed = true
+/// amino_acid = "C"
+/// mono_mass = 57.021464
+///
+/// [[modification]]
+/// accession = "unimod:21"
+/// name = "Phospho"
+/// position = "A"
+/// fixed = false
+/// amino_acid = "S"
+/// mono_mass = 79.966331
+/// neutral_losses = ["H3PO4"]
+///
+/// [[modification]]
+/// unimod = "UNIMOD:35@M variable"
+///
+/// [[label_channel]]
+/// name = "light"
+///
+/// [[label_channel]]
+/// name = "heavy"
+///
+/// [[label_channel.label]]
+/// unimod = "UNIMOD:259@K fixed"
+///
+/// [[label_channel.label]]
+/// accession = "unimod:267"
+/// name = "Label:13C(6)15N(4)"
+/// amino_acid = "R"
+/// mono_mass = 10.008269
+/// ```
+#[derive(Deserialize)]
+struct ModificationConfig {
+    #[serde(default)]
+    unimod_file: Option<String>,
+    #[serde(default)]
+    neutral_losses: Vec<NeutralLossConfig>,
+    #[serde(default, rename = "modification")]
+    modifications: Vec<ModificationConfigEntry>,
+    #[serde(default, rename = "label_channel")]
+    label_channels: Vec<LabelChannelConfig>
+}
+
+#[derive(Deserialize)]
+struct LabelChannelConfig {
+    name: String,
+    #[serde(default, rename = "label")]
+    labels: Vec<ModificationConfigEntry>
+}
+
+#[derive(Deserialize)]
+struct ModificationConfigEntry {
+    unimod: Option<String>,
+    accession: Option<String>,
+    name: Option<String>,
+    position: Option<String>,
+    fixed: Option<bool>,
+    amino_acid: Option<String>,
+    mono_mass: Option<f64>,
+    average_mass: Option<f64>,
+    composition: Option<String>,
+    #[serde(default)]
+    neutral_losses: Vec<NeutralLossConfig>
+}
+
+/// Neutral loss given by the formula of a predefined loss (H2O, NH3, H3PO4, CH4OS) or by masses
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NeutralLossConfig {
+    Predefined(String),
+    Custom {
+        name: String,
+        mono_mass: f64,
+        average_mass: Option<f64>
+    }
+}
+
+impl NeutralLossConfig {
+    fn to_neutral_loss(&self) -> Result<NeutralLoss, String> {
+        return match self {
+            NeutralLossConfig::Predefined(name) => match NeutralLoss::try_get(name) {
+                Some(neutral_loss) => Ok(neutral_loss),
+                None => Err(format!("unknown neutral loss '{}', use H2O, NH3, H3PO4, CH4OS or define name and mono_mass", name))
+            },
+            NeutralLossConfig::Custom { name, mono_mass, average_mass } => Ok(NeutralLoss::new_custom(name, *mono_mass, average_mass.unwrap_or(*mono_mass)))
+        };
+    }
+}
+
+/// Reads the modifications from a CSV-, TOML- or JSON-file, depending on the file extension
+pub fn read_modification_file(file_path: &str) -> Result<ModificationFileContent, ModificationConfigError> {
+    let extension: String = match Path::new(file_path).extension() {
+        Some(extension) => extension.to_string_lossy().to_lowercase(),
+        None => String::new()
+    };
+    return match extension.as_str() {
+        "toml" | "json" => read_config_file(file_path),
+        _ => Ok(ModificationFileContent {
+            modifications: read_csv_file(file_path)?,
+            general_neutral_losses: Vec::new(),
+            label_channels: Vec::new()
+        })
+    };
+}
+
+/// Reads the modification CSV-file with the columns accession, name, position, is_fix, amino_acid, mono_mass
+/// and optional neutral_losses, which is a semicolon separated list of predefined neutral losses (H2O, NH3, H3PO4, CH4OS)
+pub fn read_csv_file(file_path: &str) -> Result<Vec<Modification>, ModificationConfigError> {
+    let mut reader = match csv::Reader::from_path(Path::new(file_path)) {
+        Ok(reader) => reader,
+        Err(err) => return Err(ModificationConfigError::IOError(file_path.to_owned(), format!("{}", err)))
+    };
+    let mut modifications: Vec<Modification> = Vec::new();
+    for row_result in reader.records() {
+        let row = match row_result {
+            Ok(row) => row,
+            Err(err) => {
+                let location: ConfigLocation = err.position().map_or(ConfigLocation::Unknown, |position| ConfigLocation::Line(position.line() as usize));
+                return Err(ModificationConfigError::ParseError(file_path.to_owned(), location, format!("{}", err)));
+            }
+        };
+        let location: ConfigLocation = row.position().map_or(ConfigLocation::Unknown, |position| ConfigLocation::Line(position.line() as usize));
+        if (row.len() != 6) & (row.len() != 7) {
+            return Err(ModificationConfigError::ParseError(file_path.to_owned(), location.clone(), format!("expected 6 or 7 columns (accession, name, position, is_fix, amino_acid, mono_mass, neutral_losses), found {}", row.len())));
+        }
+        let is_fix: bool = match row[3].trim().parse::<i8>() {
+            Ok(is_fix) => is_fix > 0,
+            Err(_) => return Err(ModificationConfigError::InvalidField(file_path.to_owned(), location.clone(), "is_fix".to_owned(), format!("can not parse '{}' to integer", &row[3])))
+        };
+        let mono_mass: f64 = match row[5].trim().parse::<f64>() {
+            Ok(mono_mass) => mono_mass,
+            Err(_) => return Err(ModificationConfigError::InvalidField(file_path.to_owned(), location.clone(), "mono_mass".to_owned(), format!("can not parse '{}' to float", &row[5])))
+        };
+        let mut modification = build_modification(file_path, &location, &row[0], &row[1], &row[2], is_fix, &row[4], mono_mass)?;
+        if row.len() == 7 {
+            for name in row[6].split(';').filter(|name| !name.trim().is_empty()) {
+                match NeutralLoss::try_get(name) {
+                    Some(neutral_loss) => modification.add_neutral_loss(neutral_loss),
+                    None => return Err(ModificationConfigError::InvalidField(file_path.to_owned(), location.clone(), "neutral_losses".to_owned(), format!("unknown neutral loss '{}', use H2O, NH3, H3PO4 or CH4OS", name)))
+                }
+            }
+        }
+        modifications.push(modification);
+    }
+    return Ok(modifications);
+}
+
+/// Reads a TOML- or JSON-file, see ModificationConfig
+pub fn read_config_file(file_path: &str) -> Result<ModificationFileContent, ModificationConfigError> {
+    let content: String = match fs::read_to_string(file_path) {
+        Ok(content) => content,
+        Err(err) => return Err(ModificationConfigError::IOError(file_path.to_owned(), format!("{}", err)))
+    };
+    let is_json: bool = file_path.to_lowercase().ends_with(".json");
+    let config: ModificationConfig = if is_json {
+        match serde_json::from_str(content.as_str()) {
+            Ok(config) => config,
+            // serde_json reports line 0 if the error has no position
+            Err(err) => return Err(ModificationConfigError::ParseError(file_path.to_owned(), if err.line() > 0 { ConfigLocation::Line(err.line()) } else { ConfigLocation::Unknown }, format!("{}", err)))
+        }
+    } else {
+        match toml::from_str(content.as_str()) {
+            Ok(config) => config,
+            // toml counts lines from 0
+            Err(err) => return Err(ModificationConfigError::ParseError(file_path.to_owned(), err.line_col().map_or(ConfigLocation::Unknown, |(line, _)| ConfigLocation::Line(line + 1)), format!("{}", err)))
+        }
+    };
+    // Unimod definitions are only loaded if they are referenced
+    let has_unimod_references: bool = config.modifications.iter().chain(config.label_channels.iter().flat_map(|channel| channel.labels.iter())).any(|entry| entry.unimod.is_some());
+    let unimod_definitions: Option<Box<HashMap<u32, UnimodDefinition>>> = if has_unimod_references {
+        // relative paths are relative to the modification file
+        let unimod_file: PathBuf = match config.unimod_file {
+            Some(ref unimod_file) => match Path::new(file_path).parent() {
+                Some(directory) => directory.join(unimod_file),
+                None => PathBuf::from(unimod_file)
+            },
+            None => return Err(ModificationConfigError::InvalidField(file_path.to_owned(), ConfigLocation::Unknown, "unimod_file".to_owned(), "is required to use Unimod references".to_owned()))
+        };
+        if !unimod_file.is_file() {
+            return Err(ModificationConfigError::IOError(unimod_file.to_string_lossy().into_owned(), "Unimod-file does not exist".to_owned()));
+        }
+        Some(UnimodReader::new(unimod_file.to_string_lossy().as_ref()).get_definitions())
+    } else {
+        None
+    };
+    let mut modifications: Vec<Modification> = Vec::new();
+    for (entry_idx, entry) in config.modifications.iter().enumerate() {
+        let location = ConfigLocation::Entry("modification".to_owned(), entry_idx + 1);
+        modifications.push(entry_to_modification(file_path, &location, entry, &unimod_definitions, false)?);
+    }
+    let mut general_neutral_losses: Vec<NeutralLoss> = Vec::new();
+    for neutral_loss in config.neutral_losses.iter() {
+        match neutral_loss.to_neutral_loss() {
+            Ok(neutral_loss) => general_neutral_losses.push(neutral_loss),
+            Err(err) => return Err(ModificationConfigError::InvalidField(file_path.to_owned(), ConfigLocation::Unknown, "neutral_losses".to_owned(), err))
+        }
+    }
+    let mut label_channels: Vec<LabelChannel> = Vec::new();
+    for (channel_idx, channel_config) in config.label_channels.iter().enumerate() {
+        let mut label_channel = LabelChannel::new(channel_config.name.as_str());
+        for (label_idx, entry) in channel_config.labels.iter().enumerate() {
+            let location = ConfigLocation::Entry(format!("label_channel {} label", channel_idx + 1), label_idx + 1);
+            if let Err(err) = label_channel.add_label(entry_to_modification(file_path, &location, entry, &unimod_definitions, true)?) {
+                return Err(ModificationConfigError::InvalidField(file_path.to_owned(), location, "amino_acid".to_owned(), err));
+            }
+        }
+        if label_channels.iter().any(|existing_channel| existing_channel.get_name() == label_channel.get_name()) {
+            return Err(ModificationConfigError::InvalidField(file_path.to_owned(), ConfigLocation::Entry("label_channel".to_owned(), channel_idx + 1), "name".to_owned(), format!("label channel '{}' is defined twice", label_channel.get_name())));
+        }
+        label_channels.push(label_channel);
+    }
+    return Ok(ModificationFileContent {
+        modifications: modifications,
+        general_neutral_losses: general_neutral_losses,
+        label_channels: label_channels
+    });
+}
+
+/// Creates the modification from a config entry, which is either a Unimod reference or a full definition.
+/// Labels are always fixed and located anywhere, so these fields are optional for them.
+fn entry_to_modification(file_path: &str, location: &ConfigLocation, entry: &ModificationConfigEntry, unimod_definitions: &Option<Box<HashMap<u32, UnimodDefinition>>>, is_label: bool) -> Result<Modification, ModificationConfigError> {
+    let mut modification = if let Some(ref reference) = entry.unimod {
+        let definitions = match unimod_definitions {
+            Some(definitions) => definitions,
+            None => return Err(ModificationConfigError::InvalidField(file_path.to_owned(), location.clone(), "unimod".to_owned(), "unimod_file is required to use Unimod references".to_owned()))
+        };
+        match UnimodReader::try_create_modification(definitions, reference) {
+            Ok(modification) => modification,
+            Err(err) => return Err(ModificationConfigError::InvalidField(file_path.to_owned(), location.clone(), "unimod".to_owned(), err))
+        }
+    } else {
+        let default_position: Option<String> = if is_label { Some("A".to_owned()) } else { None };
+        let default_is_fix: Option<bool> = if is_label { Some(true) } else { None };
+        build_modification(
+            file_path,
+            location,
+            get_required_field(file_path, location, "accession", &entry.accession)?,
+            get_required_field(file_path, location, "name", &entry.name)?,
+            get_required_field(file_path, location, "position", if entry.position.is_some() { &entry.position } else { &default_position })?,
+            *get_required_field(file_path, location, "fixed", if entry.fixed.is_some() { &entry.fixed } else { &default_is_fix })?,
+            get_required_field(file_path, location, "amino_acid", &entry.amino_acid)?,
+            *get_required_field(file_path, location, "mono_mass", &entry.mono_mass)?
+        )?
+    };
+    if is_label & !modification.is_fix() {
+        return Err(ModificationConfigError::InvalidField(file_path.to_owned(), location.clone(), "fixed".to_owned(), "labels must be fixed".to_owned()));
+    }
+    if let Some(average_mass) = entry.average_mass {
+        modification.set_average_mass(average_mass);
+    }
+    if let Some(ref composition) = entry.composition {
+        modification.set_composition(composition);
+    }
+    for neutral_loss in entry.neutral_losses.iter() {
+        match neutral_loss.to_neutral_loss() {
+            Ok(neutral_loss) => modification.add_neutral_loss(neutral_loss),
+            Err(err) => return Err(ModificationConfigError::InvalidField(file_path.to_owned(), location.clone(), "neutral_losses".to_owned(), err))
+        }
+    }
+    return Ok(modification);
+}
+
+/// Creates the modification and validates position and amino acid
+fn build_modification(file_path: &str, location: &ConfigLocation, accession: &str, name: &str, position: &str, is_fix: bool, amino_acid: &str, mono_mass: f64) -> Result<Modification, ModificationConfigError> {
+    if accession.trim().is_empty() {
+        return Err(ModificationConfigError::InvalidField(file_path.to_owned(), location.clone(), "accession".to_owned(), "must not be empty".to_owned()));
+    }
+    let position: ModificationPosition = match ModificationPosition::from_str(position) {
+        Some(position) => position,
+        None => return Err(ModificationConfigError::InvalidField(file_path.to_owned(), location.clone(), "position".to_owned(), format!("unknown position '{}', use A, N, C, PN or PC", position)))
+    };
+    let mut amino_acid_chars = amino_acid.trim().chars();
+    let amino_acid_one_letter_code: char = match (amino_acid_chars.next(), amino_acid_chars.next()) {
+        // sequences are generalized, so I and L are modified as J
+        (Some(amino_acid_one_letter_code), None) => match amino_acid_one_letter_code.to_ascii_uppercase() {
+            'I' | 'L' => 'J',
+            amino_acid_one_letter_code => amino_acid_one_letter_code
+        },
+        _ => return Err(ModificationConfigError::InvalidField(file_path.to_owned(), location.clone(), "amino_acid".to_owned(), format!("'{}' is not a single amino acid one letter code", amino_acid)))
+    };
+    if (amino_acid_one_letter_code != ANY_AMINO_ACID) & !AMINO_ACIDS_FOR_COUNTING.contains(&amino_acid_one_letter_code) {
+        return Err(ModificationConfigError::InvalidField(file_path.to_owned(), location.clone(), "amino_acid".to_owned(), format!("unknown amino acid '{}'", amino_acid)));
+    }
+    if (amino_acid_one_letter_code == ANY_AMINO_ACID) & (position == ModificationPosition::Anywhere) {
+        return Err(ModificationConfigError::InvalidField(file_path.to_owned(), location.clone(), "position".to_owned(), format!("modification for any amino acid ('{}') must be terminal", ANY_AMINO_ACID)));
+    }
+    if !mono_mass.is_finite() {
+        return Err(ModificationConfigError::InvalidField(file_path.to_owned(), location.clone(), "mono_mass".to_owned(), format!("'{}' is not a valid mass", mono_mass)));
+    }
+    return Ok(Modification::new(accession, name, position, is_fix, amino_acid_one_letter_code, mono_mass));
+}
+
+fn get_required_field<'f, T>(file_path: &str, location: &ConfigLocation, field: &str, value: &'f Option<T>) -> Result<&'f T, ModificationConfigError> {
+    return match value {
+        Some(value) => Ok(value),
+        None => Err(ModificationConfigError::InvalidField(file_path.to_owned(), location.clone(), field.to_owned(), "is missing".to_owned()))
+    };
+}
@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+
+use proteomic::models::amino_acids::label_channel::LabelChannel;
+use proteomic::models::amino_acids::modification::{Modification, ModificationPosition, ANY_AMINO_ACID};
+use proteomic::models::amino_acids::modification_config::{self, ModificationConfigError};
+use proteomic::models::mass::mass_type::MassType;
+use proteomic::models::mass::neutral_loss::NeutralLoss;
+use proteomic::utility::unimod_reader::UnimodReader;
+
+/// Fixed and variable modifications of a search.
+/// An amino acid has at most one fixed modification, but may have several variable modifications,
+/// e.g. phosphorylation and O-GlcNAc on S and T or oxidation and dioxidation on M.
+pub struct ModificationRegistry {
+    fixed_modification_map: HashMap<char, Modification>,
+    variable_modification_map: HashMap<char, Vec<Modification>>,
+    general_neutral_losses: Vec<NeutralLoss>,
+    label_channels: Vec<LabelChannel>
+}
+
+impl ModificationRegistry {
+    pub fn new() -> Self {
+        return Self {
+            fixed_modification_map: HashMap::new(),
+            variable_modification_map: HashMap::new(),
+            general_neutral_losses: Vec::new(),
+            label_channels: Vec::new()
+        };
+    }
+
+    pub fn from_modifications(modifications: &Vec<Modification>) -> Self {
+        let mut registry = Self::new();
+        for modification in modifications.iter() {
+            registry.add(modification.clone());
+        }
+        return registry;
+    }
+
+    pub fn from_csv_file(modification_csv_file_path: &str) -> Self {
+        return Self::from_modifications(Modification::create_from_csv_file(modification_csv_file_path).as_ref());
+    }
+
+    /// Reads a CSV-, TOML- or JSON-file and rejects conflicting modifications, so errors are reported before any search starts
+    pub fn from_file(modification_file_path: &str) -> Result<Self, ModificationConfigError> {
+        let mut registry = Self::new();
+        let file_content = modification_config::read_modification_file(modification_file_path)?;
+        for modification in file_content.get_modifications().iter() {
+            if let Err(err) = registry.try_add(modification.clone()) {
+                return Err(ModificationConfigError::Conflict(modification_file_path.to_owned(), err));
+            }
+        }
+        for neutral_loss in file_content.get_general_neutral_losses().iter() {
+            registry.add_general_neutral_loss(neutral_loss.clone());
+        }
+        for label_channel in file_content.get_label_channels().iter() {
+            if let Err(err) = registry.add_label_channel(label_channel.clone()) {
+                return Err(ModificationConfigError::Conflict(modification_file_path.to_owned(), err));
+            }
+        }
+        return Ok(registry);
+    }
+
+    /// Adds the modifications referenced like `UNIMOD:35@M variable`, which are looked up in the given unimod.xml
+    pub fn add_unimod_modifications(&mut self, unimod_file_path: &str, references: &Vec<String>) {
+        for modification in UnimodReader::new(unimod_file_path).create_modifications(references).into_iter() {
+            self.add(modification);
+        }
+    }
+
+    /// Adds the modification. Variable modifications are kept in the order they are added, duplicates are ignored.
+    /// Panics if the modification conflicts with the registered ones, see try_add().
+    pub fn add(&mut self, modification: Modification) {
+        if let Err(err) = self.try_add(modification) {
+            panic!("proteomic::models::amino_acids::modification_registry::ModificationRegistry.add(): {}", err);
+        }
+    }
+
+    /// Adds the modification or returns a message if it conflicts with the registered ones:
+    /// an amino acid has at most one fixed modification and a site with a fixed modification can not be modified variably.
+    pub fn try_add(&mut self, modification: Modification) -> Result<(), String> {
+        let amino_acid_one_letter_code: char = modification.get_amino_acid_one_letter_code();
+        if modification.is_fix() {
+            if let Some(fixed_modification) = self.fixed_modification_map.get(&amino_acid_one_letter_code) {
+                if *fixed_modification != modification {
+                    return Err(format!("'{}' has already the fixed modification '{}', can not add '{}'", amino_acid_one_letter_code, fixed_modification.get_accession(), modification.get_accession()));
+                }
+                return Ok(());
+            }
+            if let Some(variable_modification) = self.get_variable_modifications(amino_acid_one_letter_code).iter().find(|variable_modification| Self::share_site(&modification, variable_modification)) {
+                return Err(format!("'{}' has already the variable modification '{}', can not add the fixed modification '{}'", amino_acid_one_letter_code, variable_modification.get_accession(), modification.get_accession()));
+            }
+            self.fixed_modification_map.insert(amino_acid_one_letter_code, modification);
+        } else {
+            if let Some(fixed_modification) = self.fixed_modification_map.get(&amino_acid_one_letter_code) {
+                if Self::share_site(fixed_modification, &modification) {
+                    return Err(format!("'{}' has already the fixed modification '{}', can not add the variable modification '{}'", amino_acid_one_letter_code, fixed_modification.get_accession(), modification.get_accession()));
+                }
+            }
+            let variable_modifications = self.variable_modification_map.entry(amino_acid_one_letter_code).or_insert(Vec::new());
+            if !variable_modifications.contains(&modification) {
+                variable_modifications.push(modification);
+            }
+        }
+        return Ok(());
+    }
+
+    /// Returns true if both modifications may occure at the same site, e.g. a modification anywhere and one at the n-terminus.
+    /// Terminal modifications share a site only if their positions are identical, so a fixed peptide n-terminal modification (e.g. TMT)
+    /// can be combined with a variable protein n-terminal modification (e.g. Acetyl).
+    fn share_site(modification: &Modification, other: &Modification) -> bool {
+        let position = modification.get_position();
+        let other_position = other.get_position();
+        return (position == ModificationPosition::Anywhere)
+            | (other_position == ModificationPosition::Anywhere)
+            | (position == other_position);
+    }
+
+    /// Adds a neutral loss which applies to all fragments, e.g. NH3
+    pub fn add_general_neutral_loss(&mut self, neutral_loss: NeutralLoss) {
+        if !self.general_neutral_losses.contains(&neutral_loss) {
+            self.general_neutral_losses.push(neutral_loss);
+        }
+    }
+
+    pub fn get_general_neutral_losses(&self) -> &Vec<NeutralLoss> {
+        return &self.general_neutral_losses;
+    }
+
+    /// Returns true if NH3 or H2O is a general neutral loss. Comet's use_NL_ions adds both of them to all b- and y-ions
+    /// and does not support other general neutral losses, so these are only considered by the similarity filter.
+    /// Neutral losses of variable modifications are independent of use_NL_ions.
+    pub fn uses_comet_neutral_loss_ions(&self) -> bool {
+        return self.general_neutral_losses.iter().any(|neutral_loss| (neutral_loss.get_name() == "NH3") | (neutral_loss.get_name() == "H2O"));
+    }
+
+    /// Adds a channel of the label set. Returns an error message if a channel with the same name exists
+    /// or a label conflicts with a registered modification.
+    pub fn add_label_channel(&mut self, label_channel: LabelChannel) -> Result<(), String> {
+        if self.label_channels.iter().any(|existing_channel| existing_channel.get_name() == label_channel.get_name()) {
+            return Err(format!("label channel '{}' is defined twice", label_channel.get_name()));
+        }
+        // the registry of the channel validates the labels against the modifications
+        let mut channel_registry = self.clone();
+        for label in label_channel.get_labels().iter() {
+            if let Err(err) = channel_registry.try_add(label.clone()) {
+                return Err(format!("label channel '{}': {}", label_channel.get_name(), err));
+            }
+        }
+        self.label_channels.push(label_channel);
+        return Ok(());
+    }
+
+    pub fn get_label_channels(&self) -> &Vec<LabelChannel> {
+        return &self.label_channels;
+    }
+
+    pub fn has_label_channels(&self) -> bool {
+        return !self.label_channels.is_empty();
+    }
+
+    /// Returns the registry of the given channel, which contains the labels of the channel as fixed modifications
+    pub fn get_channel_registry(&self, label_channel: &LabelChannel) -> ModificationRegistry {
+        let mut channel_registry = self.clone();
+        channel_registry.label_channels.clear();
+        for label in label_channel.get_labels().iter() {
+            channel_registry.add(label.clone());
+        }
+        return channel_registry;
+    }
+
+    pub fn get_fixed_modification_map(&self) -> &HashMap<char, Modification> {
+        return &self.fixed_modification_map;
+    }
+
+    pub fn get_variable_modification_map(&self) -> &HashMap<char, Vec<Modification>> {
+        return &self.variable_modification_map;
+    }
+
+    /// Returns the variable modifications of the amino acid, which is empty if there are none
+    pub fn get_variable_modifications(&self, amino_acid_one_letter_code: char) -> &[Modification] {
+        return match self.variable_modification_map.get(&amino_acid_one_letter_code) {
+            Some(modifications) => modifications.as_slice(),
+            None => &[]
+        };
+    }
+
+    pub fn has_variable_modifications(&self) -> bool {
+        return !self.variable_modification_map.is_empty();
+    }
+
+    /// Returns the fixed modifications, sorted by amino acid, followed by the variable modifications
+    pub fn get_modifications(&self) -> Vec<&Modification> {
+        let mut amino_acid_one_letter_codes: Vec<&char> = self.fixed_modification_map.keys().collect();
+        amino_acid_one_letter_codes.sort();
+        let mut modifications: Vec<&Modification> = amino_acid_one_letter_codes.iter().map(|amino_acid_one_letter_code| &self.fixed_modification_map[*amino_acid_one_letter_code]).collect();
+        modifications.extend(self.get_sorted_variable_modifications());
+        return modifications;
+    }
+
+    /// Returns an error message if a modification or label has no average mass,
+    /// because its mono mass would be used silently for average masses.
+    pub fn check_average_masses(&self) -> Result<(), String> {
+        let labels = self.label_channels.iter().flat_map(|label_channel| label_channel.get_labels().iter());
+        for modification in self.get_modifications().into_iter().chain(labels) {
+            if !modification.has_average_mass() {
+                return Err(format!("modification '{}' ({}) has no average mass, define average_mass in a TOML- or JSON-modification file or use a Unimod reference", modification.get_accession(), modification.get_name()));
+            }
+        }
+        return Ok(());
+    }
+
+    /// Returns all variable modifications, sorted by amino acid and in the order they were added
+    pub fn get_sorted_variable_modifications(&self) -> Vec<&Modification> {
+        let mut amino_acid_one_letter_codes: Vec<&char> = self.variable_modification_map.keys().collect();
+        amino_acid_one_letter_codes.sort();
+        let mut modifications: Vec<&Modification> = Vec::new();
+        for amino_acid_one_letter_code in amino_acid_one_letter_codes {
+            modifications.extend(self.variable_modification_map[amino_acid_one_letter_code].iter());
+        }
+        return modifications;
+    }
+
+    /// Returns true if any modification is restricted to protein termini
+    pub fn has_protein_terminal_modifications(&self) -> bool {
+        return self.fixed_modification_map.values().any(|modification| modification.get_position().is_protein_terminal())
+            || self.variable_modification_map.values().flat_map(|modifications| modifications.iter()).any(|modification| modification.get_position().is_protein_terminal());
+    }
+
+    /// Returns the sorted amino acids which have a fixed or variable modification, without modifications for any amino acid
+    pub fn get_modifiable_amino_acids(&self) -> Vec<char> {
+        let mut amino_acid_one_letter_codes: Vec<char> = self.fixed_modification_map.keys().cloned().collect();
+        for amino_acid_one_letter_code in self.variable_modification_map.keys() {
+            if !self.fixed_modification_map.contains_key(amino_acid_one_letter_code) {
+                amino_acid_one_letter_codes.push(*amino_acid_one_letter_code);
+            }
+        }
+        amino_acid_one_letter_codes.retain(|amino_acid_one_letter_code| *amino_acid_one_letter_code != ANY_AMINO_ACID);
+        amino_acid_one_letter_codes.sort();
+        return amino_acid_one_letter_codes;
+    }
+
+    /// Returns the lightest and heaviest mass shift of a single residue of the amino acid,
+    /// which is the fixed modification (if any) plus none or one of the variable modifications.
+    /// Returns None if the amino acid has no modification.
+    pub fn get_mass_shift_range(&self, amino_acid_one_letter_code: char, mass_type: MassType) -> Option<(i64, i64)> {
+        let fixed_mass_shift: i64 = match self.fixed_modification_map.get(&amino_acid_one_letter_code) {
+            Some(modification) => modification.get_mass(mass_type),
+            None => 0
+        };
+        let variable_modifications = self.get_variable_modifications(amino_acid_one_letter_code);
+        if !self.fixed_modification_map.contains_key(&amino_acid_one_letter_code) && variable_modifications.is_empty() {
+            return None;
+        }
+        let mut mass_shift_range: (i64, i64) = (fixed_mass_shift, fixed_mass_shift);
+        for modification in variable_modifications.iter() {
+            mass_shift_range.0 = std::cmp::min(mass_shift_range.0, fixed_mass_shift + modification.get_mass(mass_type));
+            mass_shift_range.1 = std::cmp::max(mass_shift_range.1, fixed_mass_shift + modification.get_mass(mass_type));
+        }
+        return Some(mass_shift_range);
+    }
+
+    /// Returns the lightest and heaviest mass shift of a peptide by the modifications for any amino acid.
+    /// Each terminus carries the fixed peptide terminal modification, or none or one of the variable modifications.
+    /// A fixed protein terminal modification is only carried by peptides at the protein terminus, so it is optional like the variable ones.
+    pub fn get_any_amino_acid_mass_shift_range(&self, mass_type: MassType) -> (i64, i64) {
+        let mut mass_shift_range: (i64, i64) = (0, 0);
+        for is_n_terminal in [true, false].iter() {
+            let is_at_terminus = |modification: &Modification| if *is_n_terminal { modification.get_position().is_n_terminal() } else { modification.get_position().is_c_terminal() };
+            let mut terminus_mass_shift_range: (i64, i64) = (0, 0);
+            match self.fixed_modification_map.get(&ANY_AMINO_ACID) {
+                Some(modification) if is_at_terminus(modification) && !modification.get_position().is_protein_terminal() => {
+                    terminus_mass_shift_range = (modification.get_mass(mass_type), modification.get_mass(mass_type));
+                },
+                fixed_modification => {
+                    let optional_modifications = self.get_variable_modifications(ANY_AMINO_ACID).into_iter().chain(fixed_modification.into_iter()).filter(|modification| is_at_terminus(*modification));
+                    for modification in optional_modifications {
+                        terminus_mass_shift_range.0 = std::cmp::min(terminus_mass_shift_range.0, modification.get_mass(mass_type));
+                        terminus_mass_shift_range.1 = std::cmp::max(terminus_mass_shift_range.1, modification.get_mass(mass_type));
+                    }
+                }
+            }
+            mass_shift_range.0 += terminus_mass_shift_range.0;
+            mass_shift_range.1 += terminus_mass_shift_range.1;
+        }
+        return mass_shift_range;
+    }
+}
+
+impl Clone for ModificationRegistry {
+    fn clone(&self) -> ModificationRegistry {
+        return Self {
+            fixed_modification_map: self.fixed_modification_map.clone(),
+            variable_modification_map: self.variable_modification_map.clone(),
+            general_neutral_losses: self.general_neutral_losses.clone(),
+            label_channels: self.label_channels.clone()
+        };
+    }
+}
@@ -1,9 +1,10 @@
 use std::{thread, time};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use proteomic::models::persistable::{handle_postgres_error, Persistable, QueryOk, QueryError};
 use proteomic::models::protein::Protein;
 use proteomic::models::peptides::peptide::Peptide;
+use proteomic::models::peptides::peptide_interface::PeptideInterface;
 use proteomic::models::peptide_protein_association::PeptideProteinAssociation;
 use proteomic::models::amino_acids::amino_acid::AminoAcid;
 use proteomic::models::enzyms::digest_summary::DigestSummary;
@@ -59,6 +60,7 @@ pub trait DigestEnzym<'e> {
          * collect the results as String-vector
          */
         let peptides_without_missed_cleavages: Vec<String> = self.get_digest_regex().split(protein.get_aa_sequence()).map(|peptide| peptide.to_owned()).collect::<Vec<String>>();
+        let protein_termini: HashMap<String, (bool, bool)> = self.get_protein_termini(&peptides_without_missed_cleavages);
         // let mut peptide_position: usize = 0;
         // calculate peptides for missed_cleavages 1 to n + 1 (+1 because explicit boundary)
         for peptide_idx in 0..peptides_without_missed_cleavages.len() {
@@ -78,7 +80,7 @@ pub trait DigestEnzym<'e> {
                     // the next line will move all Peptides from HashSet to a new Vec, left HashSet empty,
                     // which is pretty good because we have to empty anyway for next iterations
                     let mut peptides_for_transaction: Vec<Peptide> = peptides_for_transaction_set.drain().collect();
-                    self.do_peptide_transaction(protein, &mut peptides_for_transaction, &mut summary);
+                    self.do_peptide_transaction(protein, &mut peptides_for_transaction, &protein_termini, &mut summary);
 
                 }
                 if break_missed_cleavage_loop_before_next_iteration { break 'missed_cleavage_loop; }
@@ -94,10 +96,32 @@ pub trait DigestEnzym<'e> {
         return summary;
     }
 
-    fn do_peptide_transaction(&mut self, protein: &Protein, peptides: &mut Vec<Peptide>, summary: &mut DigestSummary) {
+    /// Returns for each generalized peptide sequence which starts at the protein N-terminus or ends at the protein C-terminus
+    /// a tuple (is_protein_n_terminus, is_protein_c_terminus). A sequence may occure at several positions in the protein, so the flags are combined.
+    fn get_protein_termini(&self, peptides_without_missed_cleavages: &Vec<String>) -> HashMap<String, (bool, bool)> {
+        let mut protein_termini: HashMap<String, (bool, bool)> = HashMap::new();
+        for peptide_idx in 0..peptides_without_missed_cleavages.len() {
+            let mut new_peptide_aa_sequence: String = String::new();
+            for number_of_missed_cleavages in 0..(self.get_max_number_of_missed_cleavages() + 1) {
+                let temp_idx: usize = peptide_idx + number_of_missed_cleavages as usize;
+                if temp_idx >= peptides_without_missed_cleavages.len() { break; }
+                new_peptide_aa_sequence.push_str(peptides_without_missed_cleavages.get(temp_idx).unwrap());
+                let is_protein_n_terminus: bool = peptide_idx == 0;
+                let is_protein_c_terminus: bool = temp_idx + 1 == peptides_without_missed_cleavages.len();
+                if is_protein_n_terminus || is_protein_c_terminus {
+                    let termini = protein_termini.entry(AminoAcid::gerneralize_sequence(&new_peptide_aa_sequence)).or_insert((false, false));
+                    termini.0 |= is_protein_n_terminus;
+                    termini.1 |= is_protein_c_terminus;
+                }
+            }
+        }
+        return protein_termini;
+    }
+
+    fn do_peptide_transaction(&mut self, protein: &Protein, peptides: &mut Vec<Peptide>, protein_termini: &HashMap<String, (bool, bool)>, summary: &mut DigestSummary) {
         let mut local_log: Vec<String> = Vec::new();
         for try in 1..=3 {
-            match self.try_peptide_transaction(protein, peptides) {
+            match self.try_peptide_transaction(protein, peptides, protein_termini) {
                 Ok(transaction_summary) => {
                     match try {
                         2 | 3 => {
@@ -128,7 +152,7 @@ pub trait DigestEnzym<'e> {
         }
     }
 
-    fn try_peptide_transaction(&mut self, protein: &Protein, peptides: &mut Vec<Peptide>) -> Result<TransactionSummary, QueryError> {
+    fn try_peptide_transaction(&mut self, protein: &Protein, peptides: &mut Vec<Peptide>, protein_termini: &HashMap<String, (bool, bool)>) -> Result<TransactionSummary, QueryError> {
         let mut summary = TransactionSummary::new();
         // create transaction for peptide and association
         let transaction = match self.get_database_connection().transaction() {
@@ -145,7 +169,11 @@ pub trait DigestEnzym<'e> {
                 },
                 Err(err) => return Err(err)
             };
-            let mut association = PeptideProteinAssociation::new(peptide, protein);
+            let (is_protein_n_terminus, is_protein_c_terminus) = match protein_termini.get(peptide.get_aa_sequence()) {
+                Some(termini) => *termini,
+                None => (false, false)
+            };
+            let mut association = PeptideProteinAssociation::new(peptide, protein, is_protein_n_terminus, is_protein_c_terminus);
             match association.prepared_create(self.get_pp_association_create_statement(), self.get_pp_association_exists_statement()) {
                 Ok(query_ok) => match query_ok {
                     QueryOk::Created => summary.increase_peptide_protein_association_counter(true),
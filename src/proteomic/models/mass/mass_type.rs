@@ -0,0 +1,32 @@
+/// Kind of mass used for precursor and fragment calculations.
+/// Average masses suit low-resolution data, where the isotopes are not resolved.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MassType {
+    Monoisotopic,
+    Average
+}
+
+impl MassType {
+    pub fn to_string(&self) -> String {
+        return match self {
+            MassType::Monoisotopic => "mono".to_string(),
+            MassType::Average => "average".to_string()
+        }
+    }
+
+    pub fn from_str(mass_type_name: &str) -> MassType {
+        return match mass_type_name.to_lowercase().as_str() {
+            "mono" | "monoisotopic" => MassType::Monoisotopic,
+            "average" => MassType::Average,
+            _ => panic!("proteomic::models::mass::mass_type::MassType::from_str(): Unknown mass type '{}', use 'mono' or 'average'", mass_type_name)
+        }
+    }
+
+    /// Value of Comet's parameters `mass_type_parent` and `mass_type_fragment`
+    pub fn get_comet_mass_type(&self) -> u8 {
+        return match self {
+            MassType::Average => 0,
+            MassType::Monoisotopic => 1
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub mod neutral_loss;
+pub mod mass_type;
 
 const MASS_CONVERT_FACTOR: f64 = 1000000.0;
 const HYDROGEN_MONO_MASS: f64 = 1.007276;
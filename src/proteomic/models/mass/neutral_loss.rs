@@ -1,10 +1,15 @@
 use proteomic::models::mass::convert_mass_to_int;
+use proteomic::models::mass::mass_type::MassType;
 
 const WATER_LOSS: (&'static str, f64, f64) = ("H2O", 18.010565, 18.015);
+const AMMONIA_LOSS: (&'static str, f64, f64) = ("NH3", 17.026549, 17.0305);
+const PHOSPHORIC_ACID_LOSS: (&'static str, f64, f64) = ("H3PO4", 97.976896, 97.9952);      // phosphorylated S and T
+const METHANESULFENIC_ACID_LOSS: (&'static str, f64, f64) = ("CH4OS", 63.998285, 64.1069);  // oxidized M
 const NONE_LOSS: (&'static str, f64, f64) = ("NONE", 0.0, 0.0);
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct NeutralLoss {
-    name: &'static str,
+    name: String,
     mono_mass: i64,
     average_mass: i64
 }
@@ -13,14 +18,23 @@ impl NeutralLoss {
     // (name, mono_mass. average_mass)
     pub fn new(mass_tupel: (&'static str, f64, f64)) -> NeutralLoss {
         return NeutralLoss {
-            name: mass_tupel.0,
+            name: mass_tupel.0.to_owned(),
             mono_mass: convert_mass_to_int(mass_tupel.1),
             average_mass: convert_mass_to_int(mass_tupel.2)
         }
     }
 
-    pub fn get_name (&self) -> &'static str {
-        return self.name;
+    /// Creates a neutral loss which is not predefined, e.g. from a Unimod definition
+    pub fn new_custom(name: &str, mono_mass: f64, average_mass: f64) -> NeutralLoss {
+        return NeutralLoss {
+            name: name.trim().to_owned(),
+            mono_mass: convert_mass_to_int(mono_mass),
+            average_mass: convert_mass_to_int(average_mass)
+        }
+    }
+
+    pub fn get_name (&self) -> &str {
+        return self.name.as_str();
     }
 
     pub fn get_mono_mass (&self) -> i64 {
@@ -31,11 +45,29 @@ impl NeutralLoss {
         return self.average_mass;
     }
 
+    pub fn get_mass(&self, mass_type: MassType) -> i64 {
+        return match mass_type {
+            MassType::Monoisotopic => self.mono_mass,
+            MassType::Average => self.average_mass
+        };
+    }
+
     pub fn get(name: &str) -> NeutralLoss {
-        match name {
-            "H2O" => NeutralLoss::new(WATER_LOSS),
-            _ => return NeutralLoss::new(NONE_LOSS)
-        }
+        return match Self::try_get(name) {
+            Some(neutral_loss) => neutral_loss,
+            None => NeutralLoss::new(NONE_LOSS)
+        };
+    }
+
+    /// Returns the predefined neutral loss with the given formula, or None if it is unknown
+    pub fn try_get(name: &str) -> Option<NeutralLoss> {
+        return match name.trim().to_ascii_uppercase().as_str() {
+            "H2O" => Some(NeutralLoss::new(WATER_LOSS)),
+            "NH3" => Some(NeutralLoss::new(AMMONIA_LOSS)),
+            "H3PO4" => Some(NeutralLoss::new(PHOSPHORIC_ACID_LOSS)),
+            "CH4OS" => Some(NeutralLoss::new(METHANESULFENIC_ACID_LOSS)),
+            _ => None
+        };
     }
 }
 
@@ -4,14 +4,18 @@ use proteomic::models::protein::Protein;
 
 pub struct PeptideProteinAssociation {
     peptide_id: i64,
-    protein_id: i64
+    protein_id: i64,
+    is_protein_n_terminus: bool,
+    is_protein_c_terminus: bool
 }
 
 impl PeptideProteinAssociation {
-    pub fn new(peptide: &Peptide, protein: &Protein) -> PeptideProteinAssociation {
+    pub fn new(peptide: &Peptide, protein: &Protein, is_protein_n_terminus: bool, is_protein_c_terminus: bool) -> PeptideProteinAssociation {
         return PeptideProteinAssociation {
             peptide_id: peptide.get_primary_key(),
-            protein_id: protein.get_primary_key()
+            protein_id: protein.get_primary_key(),
+            is_protein_n_terminus: is_protein_n_terminus,
+            is_protein_c_terminus: is_protein_c_terminus
         }
     }
 
@@ -22,6 +26,16 @@ impl PeptideProteinAssociation {
     pub fn get_protein_id(&self) -> i64 {
         return self.protein_id;
     }
+
+    /// Returns true if the peptide is located at the N-terminus of the protein
+    pub fn is_protein_n_terminus(&self) -> bool {
+        return self.is_protein_n_terminus;
+    }
+
+    /// Returns true if the peptide is located at the C-terminus of the protein
+    pub fn is_protein_c_terminus(&self) -> bool {
+        return self.is_protein_c_terminus;
+    }
 }
 
 impl Persistable<PeptideProteinAssociation, (i64, i64), (i64, i64)> for PeptideProteinAssociation {
@@ -29,7 +43,9 @@ impl Persistable<PeptideProteinAssociation, (i64, i64), (i64, i64)> for PeptideP
         return Ok(
             Self {
                 peptide_id: row.get(0),
-                protein_id: row.get(1)
+                protein_id: row.get(1),
+                is_protein_n_terminus: row.get(2),
+                is_protein_c_terminus: row.get(3)
             }
         )
     }
@@ -61,11 +77,11 @@ impl Persistable<PeptideProteinAssociation, (i64, i64), (i64, i64)> for PeptideP
     }
 
     fn create_query() -> &'static str {
-        return "INSERT INTO peptides_proteins (peptide_id, protein_id) VALUES ($1, $2) ON CONFLICT (peptide_id, protein_id) DO NOTHING RETURNING *;";
+        return "INSERT INTO peptides_proteins (peptide_id, protein_id, is_protein_n_terminus, is_protein_c_terminus) VALUES ($1, $2, $3, $4) ON CONFLICT (peptide_id, protein_id) DO NOTHING RETURNING *;";
     }
 
     fn create_attributes(&self) -> Box<Vec<&postgres::types::ToSql>>{
-        return Box::new(vec![&self.peptide_id, &self.protein_id]);
+        return Box::new(vec![&self.peptide_id, &self.protein_id, &self.is_protein_n_terminus, &self.is_protein_c_terminus]);
     }
 
     fn update_query() -> &'static str{
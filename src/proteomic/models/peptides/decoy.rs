@@ -18,20 +18,25 @@ pub struct Decoy {
     number_of_missed_cleavages: i16,        // SMALLINT
     weight: i64,                            // BIGINT
     amino_acids_counts: HashMap<char, i16>,  // columns <one_letter_code>_count, type SMALLINT
-    modification_summary: String
+    modification_summary: String,
+    proforma: String,
+    modified_weight: i64                    // weight including modifications, not persisted
 }
 
 impl Decoy {
     pub fn new(aa_sequence: &str, number_of_missed_cleavages: i16) -> Self {
         let generalized_aa_sequence: String = AminoAcid::gerneralize_sequence(aa_sequence);
+        let weight: i64 = AminoAcid::get_sequence_weight(generalized_aa_sequence.as_str());
         return Self {
             id: 0,
             length: generalized_aa_sequence.len() as i32,
-            weight: AminoAcid::get_sequence_weight(generalized_aa_sequence.as_str()),
+            weight: weight,
             amino_acids_counts: *Self::count_amino_acids(generalized_aa_sequence.as_str()),
             aa_sequence: generalized_aa_sequence,
             number_of_missed_cleavages: number_of_missed_cleavages,
-            modification_summary: String::new()
+            modification_summary: String::new(),
+            proforma: String::new(),
+            modified_weight: weight
         }
     }
 
@@ -46,6 +51,27 @@ impl Decoy {
         self.modification_summary = modification_summary.to_owned();
     }
 
+    pub fn get_modification_summary(&self) -> &str {
+        return self.modification_summary.as_str();
+    }
+
+    pub fn set_proforma(&mut self, proforma: &str) {
+        self.proforma = proforma.to_owned();
+    }
+
+    pub fn get_proforma(&self) -> &str {
+        return self.proforma.as_str();
+    }
+
+    pub fn set_modified_weight(&mut self, modified_weight: i64) {
+        self.modified_weight = modified_weight;
+    }
+
+    /// Returns the weight including modifications, which equals the weight if the decoy was not built from a modified peptide.
+    pub fn get_modified_weight(&self) -> i64 {
+        return self.modified_weight;
+    }
+
     pub fn is_peptide(&self, conn: &postgres::Connection) -> bool {
         match Peptide::exists_where(conn, "aa_sequence = $1", &[&self.aa_sequence]) {
             Ok(query_ok) => match query_ok {
@@ -78,6 +104,9 @@ impl PeptideInterface for Decoy {
         if self.modification_summary.len() > 0 {
             header.push_str(format!(" ModRes={}", self.modification_summary).as_str());
         }
+        if self.proforma.len() > 0 {
+            header.push_str(format!(" ProForma={}", self.proforma).as_str());
+        }
         return header;
     }
 
@@ -147,7 +176,9 @@ impl Persistable<Decoy, i64, String> for Decoy {
                     ('y', row.get(24)),
                     ('a', row.get(24))
                 ].iter().cloned().collect(),
-                modification_summary: String::new()
+                modification_summary: String::new(),
+                proforma: String::new(),
+                modified_weight: row.get(4)
             }
         )
     }
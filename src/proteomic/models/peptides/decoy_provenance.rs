@@ -0,0 +1,261 @@
+use proteomic::models::persistable::{Persistable, QueryOk, QueryError, FromSqlRowError, handle_postgres_error};
+use proteomic::models::peptides::peptide_interface::PeptideInterface;
+use proteomic::models::peptides::decoy::Decoy;
+
+/// Records how a decoy was generated, so a decoy set can be audited and reproduced.
+/// A decoy may have several provenances, because it can be generated again for another precursor.
+/// All provenances of one generation run share the same run id.
+pub struct DecoyProvenance {
+    id: i64,                                    // BIGSERIAL
+    decoy_id: i64,                              // BIGINT
+    decoy_weight: i64,                          // BIGINT, primary key of decoys is (id, weight)
+    modification_summary: String,               // TEXT
+    generation_strategy: String,                // VARCHAR(20)
+    seed: String,                               // VARCHAR(20), u64 does not fit into BIGINT
+    thread_count: i32,                          // INTEGER, the generated decoys depend on seed and thread count
+    precursor_mass: i64,                        // BIGINT
+    lower_precursor_tolerance_limit: i64,       // BIGINT
+    upper_precursor_tolerance_limit: i64,       // BIGINT
+    generated_at: i64,                          // BIGINT, seconds since epoch
+    proforma: String,                           // TEXT
+    run_id: i64                                 // BIGINT, from sequence decoy_generation_run_ids
+}
+
+impl DecoyProvenance {
+    /// Creates a provenance which is not assigned to a decoy yet. Use `for_decoy()` to create the provenance of a generated decoy.
+    pub fn new(run_id: i64, generation_strategy: &str, seed: u64, thread_count: usize, precursor_mass: i64, lower_precursor_tolerance_limit: i64, upper_precursor_tolerance_limit: i64) -> Self {
+        return Self {
+            id: 0,
+            decoy_id: 0,
+            decoy_weight: 0,
+            modification_summary: String::new(),
+            generation_strategy: generation_strategy.to_owned(),
+            seed: seed.to_string(),
+            thread_count: thread_count as i32,
+            precursor_mass: precursor_mass,
+            lower_precursor_tolerance_limit: lower_precursor_tolerance_limit,
+            upper_precursor_tolerance_limit: upper_precursor_tolerance_limit,
+            generated_at: 0,
+            proforma: String::new(),
+            run_id: run_id
+        };
+    }
+
+    /// Returns a new run id for a generation run
+    pub fn next_run_id(conn: &postgres::Connection) -> Result<i64, QueryError> {
+        return match conn.query("SELECT nextval('decoy_generation_run_ids');", &[]) {
+            Ok(rows) => Ok(rows.get(0).get(0)),
+            Err(err) => Err(handle_postgres_error(&err))
+        };
+    }
+
+    /// Returns a copy of this provenance for the given (persisted) decoy, generated now
+    pub fn for_decoy(&self, decoy: &Decoy) -> Self {
+        return Self {
+            id: 0,
+            decoy_id: decoy.get_primary_key(),
+            decoy_weight: decoy.get_weight(),
+            modification_summary: decoy.get_modification_summary().to_owned(),
+            generation_strategy: self.generation_strategy.clone(),
+            seed: self.seed.clone(),
+            thread_count: self.thread_count,
+            precursor_mass: self.precursor_mass,
+            lower_precursor_tolerance_limit: self.lower_precursor_tolerance_limit,
+            upper_precursor_tolerance_limit: self.upper_precursor_tolerance_limit,
+            generated_at: time::now().to_timespec().sec,
+            proforma: decoy.get_proforma().to_owned(),
+            run_id: self.run_id
+        };
+    }
+
+    /// Creates the decoy, if it does not exist yet, and its provenance in one transaction, so there is no decoy without provenance.
+    /// Returns the result of the decoy creation.
+    pub fn create_with_decoy(&self, conn: &postgres::Connection, decoy: &mut Decoy) -> Result<QueryOk, QueryError> {
+        let transaction = match conn.transaction() {
+            Ok(transaction) => transaction,
+            Err(err) => return Err(handle_postgres_error(&err))
+        };
+        transaction.set_rollback(); // set only to commit if no errors occured
+        let decoy_query_ok: QueryOk = match decoy.create(conn) {
+            Ok(query_ok) => query_ok,
+            Err(err) => {
+                decoy.invalidate_primary_key();
+                return Err(err);
+            }
+        };
+        match self.for_decoy(decoy).create(conn) {
+            Ok(_) => (),
+            Err(err) => {
+                decoy.invalidate_primary_key();
+                return Err(err);
+            }
+        }
+        transaction.set_commit();
+        match transaction.finish() {
+            Ok(_) => (),
+            Err(err) => {
+                decoy.invalidate_primary_key();
+                return Err(handle_postgres_error(&err));
+            }
+        }
+        return Ok(decoy_query_ok);
+    }
+
+    pub fn get_run_id(&self) -> i64 {
+        return self.run_id;
+    }
+
+    pub fn get_decoy_id(&self) -> i64 {
+        return self.decoy_id;
+    }
+
+    pub fn get_modification_summary(&self) -> &str {
+        return self.modification_summary.as_str();
+    }
+
+    pub fn get_proforma(&self) -> &str {
+        return self.proforma.as_str();
+    }
+
+    pub fn get_generation_strategy(&self) -> &str {
+        return self.generation_strategy.as_str();
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        return match self.seed.parse::<u64>() {
+            Ok(seed) => seed,
+            Err(_) => panic!("proteomic::models::peptides::decoy_provenance::DecoyProvenance.get_seed(): seed '{}' is not an unsigned integer (64 bit)", self.seed)
+        };
+    }
+
+    pub fn get_thread_count(&self) -> usize {
+        return self.thread_count as usize;
+    }
+
+    pub fn get_precursor_mass(&self) -> i64 {
+        return self.precursor_mass;
+    }
+
+    pub fn get_lower_precursor_tolerance_limit(&self) -> i64 {
+        return self.lower_precursor_tolerance_limit;
+    }
+
+    pub fn get_upper_precursor_tolerance_limit(&self) -> i64 {
+        return self.upper_precursor_tolerance_limit;
+    }
+
+    pub fn get_generated_at(&self) -> i64 {
+        return self.generated_at;
+    }
+}
+
+impl Persistable<DecoyProvenance, i64, i64> for DecoyProvenance {
+    fn from_sql_row(row: &postgres::rows::Row) -> Result<Self, FromSqlRowError> {
+        return Ok(
+            Self {
+                id: row.get(0),
+                decoy_id: row.get(1),
+                decoy_weight: row.get(2),
+                modification_summary: row.get(3),
+                generation_strategy: row.get(4),
+                seed: row.get(5),
+                thread_count: row.get(6),
+                precursor_mass: row.get(7),
+                lower_precursor_tolerance_limit: row.get(8),
+                upper_precursor_tolerance_limit: row.get(9),
+                generated_at: row.get(10),
+                proforma: row.get(11),
+                run_id: row.get(12)
+            }
+        )
+    }
+
+    fn set_primary_key_from_sql_row(&mut self, row: &postgres::rows::Row) {
+        self.id = row.get(0);
+    }
+
+    fn invalidate_primary_key(&mut self) {
+        self.id = 0;
+    }
+
+    fn get_primary_key(&self) -> i64 {
+        return self.id;
+    }
+
+    fn get_table_name() -> &'static str {
+        return "decoy_provenances";
+    }
+
+    fn is_persisted(&self) -> bool {
+        return self.id > 0;
+    }
+
+    fn find_query() -> &'static str {
+        return "SELECT * FROM decoy_provenances WHERE id = $1 LIMIT 1;";
+    }
+
+    fn create_query() -> &'static str {
+        return "INSERT INTO decoy_provenances (decoy_id, decoy_weight, modification_summary, generation_strategy, seed, thread_count, precursor_mass, lower_precursor_tolerance_limit, upper_precursor_tolerance_limit, generated_at, proforma, run_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) RETURNING id;";
+    }
+
+    fn create_attributes(&self) -> Box<Vec<&postgres::types::ToSql>>{
+        return Box::new(vec![
+            &self.decoy_id,
+            &self.decoy_weight,
+            &self.modification_summary,
+            &self.generation_strategy,
+            &self.seed,
+            &self.thread_count,
+            &self.precursor_mass,
+            &self.lower_precursor_tolerance_limit,
+            &self.upper_precursor_tolerance_limit,
+            &self.generated_at,
+            &self.proforma,
+            &self.run_id
+        ]);
+    }
+
+    fn update_query() -> &'static str{
+        return "UPDATE decoy_provenances SET decoy_id = $2, decoy_weight = $3, modification_summary = $4, generation_strategy = $5, seed = $6, thread_count = $7, precursor_mass = $8, lower_precursor_tolerance_limit = $9, upper_precursor_tolerance_limit = $10, generated_at = $11, proforma = $12, run_id = $13 WHERE id = $1;";
+    }
+
+    fn update_attributes(&self) -> Box<Vec<&postgres::types::ToSql>>{
+        return Box::new(vec![
+            &self.id,
+            &self.decoy_id,
+            &self.decoy_weight,
+            &self.modification_summary,
+            &self.generation_strategy,
+            &self.seed,
+            &self.thread_count,
+            &self.precursor_mass,
+            &self.lower_precursor_tolerance_limit,
+            &self.upper_precursor_tolerance_limit,
+            &self.generated_at,
+            &self.proforma,
+            &self.run_id
+        ]);
+    }
+
+    fn delete_query() -> &'static str {
+        return "DELETE FROM decoy_provenances WHERE id = $1;";
+    }
+
+    fn delete_attributes(&self) -> Box<Vec<&postgres::types::ToSql>> {
+        return Box::new(vec![&self.id]);
+    }
+
+    fn delete_all_query() -> &'static str {
+        return "DELETE FROM decoy_provenances WHERE id IS NOT NULL;";
+    }
+
+    fn exists_query() -> &'static str {
+        return "SELECT id FROM decoy_provenances WHERE id = $1 LIMIT 1;";
+    }
+
+    fn exists_attributes(&self) -> Box<Vec<&postgres::types::ToSql>> {
+        return Box::new(vec![&self.id]);
+    }
+
+    fn before_delete_hook(&self) -> Result<(), QueryError> {return Ok(());}
+}
@@ -1,7 +1,10 @@
 pub mod peptide;
 pub mod decoy;
+pub mod decoy_provenance;
 pub mod modified_peptide;
 pub mod peptide_interface;
+pub mod peptide_properties;
+pub mod proforma;
 
 #[cfg(test)]
 mod tests;
\ No newline at end of file
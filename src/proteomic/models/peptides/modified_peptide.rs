@@ -4,17 +4,23 @@ use std::collections::hash_map::Entry::{Occupied, Vacant};
 
 use std::fmt;
 
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use rand::seq::SliceRandom;
 
-use proteomic::models::amino_acids::modification::{Modification, ModificationPosition};
+use proteomic::models::amino_acids::modification::{Modification, ModificationPosition, ANY_AMINO_ACID};
 use proteomic::models::amino_acids::amino_acid::AminoAcid;
 use proteomic::models::mass;
 use proteomic::models::mass::neutral_loss::NeutralLoss;
+use proteomic::models::mass::mass_type::MassType;
 use proteomic::utility::combinations::n_choose_k::NChooseK;
+use proteomic::utility::mass_fitting::{MassFittingOptions, TwoAminoAcidSubstitutionTable};
+use proteomic::utility::amino_acid_composition::AminoAcidComposition;
 use proteomic::models::peptides::peptide_interface::PeptideInterface;
 use proteomic::models::peptides::peptide::{Peptide, PEPTIDE_HEADER_START};
 use proteomic::models::peptides::decoy::{Decoy, DECOY_HEADER_START};
+use proteomic::models::peptides::peptide_properties;
+use proteomic::models::peptides::proforma::{self, ProFormaSequence, ProFormaError};
+use proteomic::models::amino_acids::modification_registry::ModificationRegistry;
 use proteomic::models::amino_acids::amino_acid::AMINO_ACIDS_FOR_DECOY_GENERATION;
 
 pub enum PushAminoAcidOk {
@@ -48,7 +54,8 @@ pub enum ModifiedPeptideError {
     OutrangeMassTolerance,
     ModificationIsNotFix,
     ModificationIsNotVariable,
-    AlreadyFixModificationInPlace
+    AlreadyFixModificationInPlace,
+    ModificationDoesNotMatchToTerminus         // protein terminal modification on a peptide which is not at the protein terminus
 }
 
 impl ModifiedPeptideError {
@@ -58,7 +65,8 @@ impl ModifiedPeptideError {
             ModifiedPeptideError::OutrangeMassTolerance => format!("ModifiedPeptideError::OutrangeMassTolerance"),
             ModifiedPeptideError::ModificationIsNotFix => format!("ModifiedPeptideError::ModificationIsNotFix"),
             ModifiedPeptideError::ModificationIsNotVariable => format!("ModifiedPeptideError::ModificationIsNotVariable"),
-            ModifiedPeptideError::AlreadyFixModificationInPlace => format!("ModifiedPeptideError::AlreadyFixModificationInPlace")
+            ModifiedPeptideError::AlreadyFixModificationInPlace => format!("ModifiedPeptideError::AlreadyFixModificationInPlace"),
+            ModifiedPeptideError::ModificationDoesNotMatchToTerminus => format!("ModifiedPeptideError::ModificationDoesNotMatchToTerminus")
         }
     }
 }
@@ -80,7 +88,11 @@ pub struct ModifiedPeptide {
     upper_weight_limit: i64,
     n_terminus_modification: Option<Modification>,
     c_terminus_modification: Option<Modification>,
-    number_of_modifications: i32
+    unlocalised_modifications: Vec<Modification>,      // modifications with unknown site, only from ProForma strings
+    number_of_modifications: i32,
+    is_protein_n_terminus: bool,
+    is_protein_c_terminus: bool,
+    mass_type: MassType
 }
 
 impl ModifiedPeptide {
@@ -96,7 +108,11 @@ impl ModifiedPeptide {
             upper_weight_limit: upper_weight_limit,
             n_terminus_modification: None,
             c_terminus_modification: None,
-            number_of_modifications: 0
+            unlocalised_modifications: Vec::new(),
+            number_of_modifications: 0,
+            is_protein_n_terminus: false,
+            is_protein_c_terminus: false,
+            mass_type: MassType::Monoisotopic
         }
     }
 
@@ -131,6 +147,7 @@ impl ModifiedPeptide {
                 }
             }
         }
+        new_modified_peptide.apply_fixed_terminal_modifications(fix_modifications);
         return new_modified_peptide;
     }
 
@@ -146,10 +163,188 @@ impl ModifiedPeptide {
         return Self::from_string(DECOY_HEADER_START, decoy.get_aa_sequence(), precursor_mass, lower_weight_limit, upper_weight_limit, fix_modifications);
     }
 
+    /// Creates a new ModifiedPeptide from a ProForma 2.0 string, e.g. `[UNIMOD:1]-PEPM[UNIMOD:35]K` or `[Oxidation]?PEPMMK`.
+    /// Modification tags are resolved to the modifications of the registry, mass deltas without matching modification result in new variable modifications.
+    /// Fixed modifications of the registry are not applied implicitly, only the modifications in the string.
+    pub fn from_proforma(header: &str, proforma: &str, precursor_mass: i64, lower_weight_limit: i64, upper_weight_limit: i64, modification_registry: &ModificationRegistry) -> Result<Self, ProFormaError> {
+        let proforma_sequence = ProFormaSequence::parse(proforma)?;
+        let candidates: Vec<&Modification> = modification_registry.get_modifications();
+        let mut new_modified_peptide = Self::new(header, precursor_mass, lower_weight_limit, upper_weight_limit);
+        // I and L are kept, so the ProForma string is written back with the residues of the input
+        for amino_acid_one_letter_code in proforma_sequence.get_aa_sequence().iter() {
+            new_modified_peptide.push_amino_acid(&AminoAcid::get(*amino_acid_one_letter_code));
+            new_modified_peptide.modifications.push(None);
+        }
+        if let Some(ref tag) = *proforma_sequence.get_n_terminus_modification() {
+            let n_terminus_amino_acid: char = new_modified_peptide.get_n_terminus_amino_acid();
+            let modification = proforma::resolve_modification(
+                tag,
+                "N-term",
+                &candidates,
+                |modification| modification.get_position().is_n_terminal() && modification.matches_amino_acid(n_terminus_amino_acid),
+                &|mass_delta| Modification::new("", format!("{:+}", mass_delta).as_str(), ModificationPosition::NTerminus, false, ANY_AMINO_ACID, mass_delta)
+            )?;
+            // a protein n-terminal modification implies that the peptide is the n-terminus of a protein
+            new_modified_peptide.is_protein_n_terminus = modification.get_position() == ModificationPosition::ProteinNTerminus;
+            new_modified_peptide.weight += modification.get_mass(new_modified_peptide.mass_type);
+            new_modified_peptide.number_of_modifications += 1;
+            new_modified_peptide.n_terminus_modification = Some(modification);
+        }
+        if let Some(ref tag) = *proforma_sequence.get_c_terminus_modification() {
+            let c_terminus_amino_acid: char = new_modified_peptide.get_c_terminus_amino_acid();
+            let modification = proforma::resolve_modification(
+                tag,
+                "C-term",
+                &candidates,
+                |modification| modification.get_position().is_c_terminal() && modification.matches_amino_acid(c_terminus_amino_acid),
+                &|mass_delta| Modification::new("", format!("{:+}", mass_delta).as_str(), ModificationPosition::CTerminus, false, ANY_AMINO_ACID, mass_delta)
+            )?;
+            new_modified_peptide.is_protein_c_terminus = modification.get_position() == ModificationPosition::ProteinCTerminus;
+            new_modified_peptide.weight += modification.get_mass(new_modified_peptide.mass_type);
+            new_modified_peptide.number_of_modifications += 1;
+            new_modified_peptide.c_terminus_modification = Some(modification);
+        }
+        for (idx, tag_option) in proforma_sequence.get_residue_modifications().iter().enumerate() {
+            if let Some(ref tag) = *tag_option {
+                let amino_acid_one_letter_code: char = new_modified_peptide.get_amino_acid_at(idx);
+                let modification = proforma::resolve_modification(
+                    tag,
+                    format!("{}{}", amino_acid_one_letter_code, idx + 1).as_str(),
+                    &candidates,
+                    |modification| (modification.get_position() == ModificationPosition::Anywhere) && modification.matches_amino_acid(amino_acid_one_letter_code),
+                    &|mass_delta| Modification::new("", format!("{:+}", mass_delta).as_str(), ModificationPosition::Anywhere, false, amino_acid_one_letter_code, mass_delta)
+                )?;
+                new_modified_peptide.weight += modification.get_mass(new_modified_peptide.mass_type);
+                new_modified_peptide.number_of_modifications += 1;
+                new_modified_peptide.modifications[idx] = Some(modification);
+            }
+        }
+        for tag in proforma_sequence.get_unlocalised_modifications().iter() {
+            let aa_sequence: String = new_modified_peptide.get_aa_sequence();
+            let modification = proforma::resolve_modification(
+                tag,
+                "unknown position",
+                &candidates,
+                |modification| (modification.get_amino_acid_one_letter_code() == ANY_AMINO_ACID) || aa_sequence.contains(modification.get_amino_acid_one_letter_code()),
+                &|mass_delta| Modification::new("", format!("{:+}", mass_delta).as_str(), ModificationPosition::Anywhere, false, ANY_AMINO_ACID, mass_delta)
+            )?;
+            new_modified_peptide.weight += modification.get_mass(new_modified_peptide.mass_type);
+            new_modified_peptide.number_of_modifications += 1;
+            new_modified_peptide.unlocalised_modifications.push(modification);
+        }
+        return Ok(new_modified_peptide);
+    }
+
+    /// Returns the ProForma 2.0 string of the peptide, e.g. `[UNIMOD:1]-PEPM[UNIMOD:35]K`, including fixed modifications.
+    /// Modifications are written as Unimod accession if available, otherwise as mass delta.
+    pub fn to_proforma(&self) -> String {
+        let mut proforma_string: String = String::new();
+        // unlocalised modifications, grouped by tag in order of their first occurrence
+        let mut unlocalised_tags: Vec<(String, usize)> = Vec::new();
+        for modification in self.unlocalised_modifications.iter() {
+            let tag = proforma::get_modification_tag(modification);
+            match unlocalised_tags.iter().position(|unlocalised_tag| unlocalised_tag.0 == tag) {
+                Some(tag_idx) => unlocalised_tags[tag_idx].1 += 1,
+                None => unlocalised_tags.push((tag, 1))
+            }
+        }
+        for &(ref tag, count) in unlocalised_tags.iter() {
+            proforma_string.push_str(format!("[{}]", tag).as_str());
+            if count > 1 {
+                proforma_string.push_str(format!("^{}", count).as_str());
+            }
+        }
+        if !unlocalised_tags.is_empty() {
+            proforma_string.push('?');
+        }
+        if let Some(ref modification) = self.n_terminus_modification {
+            proforma_string.push_str(format!("[{}]-", proforma::get_modification_tag(modification)).as_str());
+        }
+        for (idx, amino_acid_one_letter_code) in self.aa_sequence.iter().enumerate() {
+            proforma_string.push(*amino_acid_one_letter_code);
+            if let Some(Some(modification)) = self.modifications.get(idx) {
+                proforma_string.push_str(format!("[{}]", proforma::get_modification_tag(modification)).as_str());
+            }
+        }
+        if let Some(ref modification) = self.c_terminus_modification {
+            proforma_string.push_str(format!("-[{}]", proforma::get_modification_tag(modification)).as_str());
+        }
+        return proforma_string;
+    }
+
     pub fn get_unmodified_weight(&self) -> i64 {
         return AminoAcid::get_sequence_weight(self.get_aa_sequence().as_str());
     }
 
+    /// Returns true if any variable modification is applied
+    pub fn has_variable_modifications(&self) -> bool {
+        let is_variable = |modification_option: &Option<Modification>| match modification_option {
+            Some(ref modification) => !modification.is_fix(),
+            None => false
+        };
+        return self.modifications.iter().any(|modification_option| is_variable(modification_option))
+            || is_variable(&self.n_terminus_modification)
+            || is_variable(&self.c_terminus_modification);
+    }
+
+    /// Marks the peptide as n- and/or c-terminus of a protein and applies the fixed protein terminal modifications
+    pub fn set_protein_termini(&mut self, is_protein_n_terminus: bool, is_protein_c_terminus: bool, fix_modifications: &HashMap<char, Modification>) {
+        self.is_protein_n_terminus = is_protein_n_terminus;
+        self.is_protein_c_terminus = is_protein_c_terminus;
+        self.apply_fixed_terminal_modifications(fix_modifications);
+    }
+
+    pub fn is_protein_n_terminus(&self) -> bool {
+        return self.is_protein_n_terminus;
+    }
+
+    pub fn is_protein_c_terminus(&self) -> bool {
+        return self.is_protein_c_terminus;
+    }
+
+    /// Returns true if the modification fits the amino acid at the given index and the position of the modification
+    pub fn is_modification_applicable_at(&self, idx: usize, modification: &Modification) -> bool {
+        if (idx >= self.aa_sequence.len()) || !modification.matches_amino_acid(self.get_amino_acid_at(idx)) {
+            return false;
+        }
+        let is_n_terminus: bool = idx == 0;
+        let is_c_terminus: bool = idx == self.aa_sequence.len() - 1;
+        return match modification.get_position() {
+            ModificationPosition::Anywhere => true,
+            ModificationPosition::NTerminus => is_n_terminus,
+            ModificationPosition::CTerminus => is_c_terminus,
+            ModificationPosition::ProteinNTerminus => is_n_terminus & self.is_protein_n_terminus,
+            ModificationPosition::ProteinCTerminus => is_c_terminus & self.is_protein_c_terminus
+        };
+    }
+
+    /// Applies the fixed modifications for any amino acid and the fixed protein terminal modifications to the termini, if the termini are not modified yet.
+    /// Fixed peptide terminal modifications of specific amino acids are already applied when the amino acids are pushed.
+    pub fn apply_fixed_terminal_modifications(&mut self, fix_modifications: &HashMap<char, Modification>) {
+        if self.aa_sequence.is_empty() {
+            return;
+        }
+        let last_idx: usize = self.aa_sequence.len() - 1;
+        for amino_acid_one_letter_code in [ANY_AMINO_ACID, self.get_n_terminus_amino_acid()].iter() {
+            if let Some(modification) = fix_modifications.get(amino_acid_one_letter_code) {
+                if self.n_terminus_modification.is_none() && modification.get_position().is_n_terminal() && self.is_modification_applicable_at(0, modification) {
+                    self.weight += modification.get_mass(self.mass_type);
+                    self.number_of_modifications += 1;
+                    self.n_terminus_modification = Some(modification.clone());
+                }
+            }
+        }
+        for amino_acid_one_letter_code in [ANY_AMINO_ACID, self.get_c_terminus_amino_acid()].iter() {
+            if let Some(modification) = fix_modifications.get(amino_acid_one_letter_code) {
+                if self.c_terminus_modification.is_none() && modification.get_position().is_c_terminal() && self.is_modification_applicable_at(last_idx, modification) {
+                    self.weight += modification.get_mass(self.mass_type);
+                    self.number_of_modifications += 1;
+                    self.c_terminus_modification = Some(modification.clone());
+                }
+            }
+        }
+    }
+
     pub fn get_number_of_modifications(&self) -> i32 {
         return self.number_of_modifications;
     }
@@ -163,15 +358,77 @@ impl ModifiedPeptide {
         return (self.precursor_mass - self.weight).abs();
     }
 
+    /// Returns the sorted masses of the b- and y-ions (without charge), including the modifications.
+    /// Fragments which contain a modification with neutral losses are added once more for each of its losses,
+    /// and every fragment is added once more for each of the given general neutral losses, e.g. NH3.
+    pub fn get_fragment_masses(&self, general_neutral_losses: &Vec<NeutralLoss>) -> Vec<i64> {
+        let length: usize = self.aa_sequence.len();
+        if length < 2 {
+            return Vec::new();
+        }
+        let general_neutral_loss_masses: Vec<i64> = general_neutral_losses.iter().map(|neutral_loss| neutral_loss.get_mass(self.mass_type)).collect();
+        // residue masses and neutral losses, including the terminal modifications
+        let mut residue_masses: Vec<i64> = Vec::with_capacity(length);
+        let mut residue_neutral_loss_masses: Vec<Vec<i64>> = Vec::with_capacity(length);
+        for idx in 0..length {
+            let mut residue_modifications: Vec<&Modification> = Vec::new();
+            if let Some(Some(modification)) = self.modifications.get(idx) { residue_modifications.push(modification); }
+            if idx == 0 { if let Some(ref modification) = self.n_terminus_modification { residue_modifications.push(modification); } }
+            if idx == length - 1 { if let Some(ref modification) = self.c_terminus_modification { residue_modifications.push(modification); } }
+            let mut residue_mass: i64 = AminoAcid::get(self.aa_sequence[idx]).get_mass(self.mass_type);
+            let mut neutral_loss_masses: Vec<i64> = Vec::new();
+            for modification in residue_modifications.iter() {
+                residue_mass += modification.get_mass(self.mass_type);
+                neutral_loss_masses.extend(modification.get_neutral_losses().iter().map(|neutral_loss| neutral_loss.get_mass(self.mass_type)));
+            }
+            residue_masses.push(residue_mass);
+            residue_neutral_loss_masses.push(neutral_loss_masses);
+        }
+        let mut fragment_masses: Vec<i64> = Vec::new();
+        // b-ions
+        let mut fragment_mass: i64 = 0;
+        let mut fragment_neutral_loss_masses: Vec<i64> = Vec::new();
+        for idx in 0..(length - 1) {
+            fragment_mass += residue_masses[idx];
+            Self::merge_neutral_loss_masses(&mut fragment_neutral_loss_masses, &residue_neutral_loss_masses[idx]);
+            Self::push_fragment_mass_with_neutral_losses(fragment_mass, &fragment_neutral_loss_masses, &general_neutral_loss_masses, &mut fragment_masses);
+        }
+        // y-ions
+        fragment_mass = NeutralLoss::get("H2O").get_mass(self.mass_type);
+        fragment_neutral_loss_masses.clear();
+        for idx in (1..length).rev() {
+            fragment_mass += residue_masses[idx];
+            Self::merge_neutral_loss_masses(&mut fragment_neutral_loss_masses, &residue_neutral_loss_masses[idx]);
+            Self::push_fragment_mass_with_neutral_losses(fragment_mass, &fragment_neutral_loss_masses, &general_neutral_loss_masses, &mut fragment_masses);
+        }
+        fragment_masses.sort();
+        return fragment_masses;
+    }
+
+    fn merge_neutral_loss_masses(neutral_loss_masses: &mut Vec<i64>, additional_neutral_loss_masses: &Vec<i64>) {
+        for neutral_loss_mass in additional_neutral_loss_masses.iter() {
+            if !neutral_loss_masses.contains(neutral_loss_mass) {
+                neutral_loss_masses.push(*neutral_loss_mass);
+            }
+        }
+    }
+
+    fn push_fragment_mass_with_neutral_losses(fragment_mass: i64, modification_neutral_loss_masses: &Vec<i64>, general_neutral_loss_masses: &Vec<i64>, fragment_masses: &mut Vec<i64>) {
+        fragment_masses.push(fragment_mass);
+        for neutral_loss_mass in modification_neutral_loss_masses.iter().chain(general_neutral_loss_masses.iter()) {
+            fragment_masses.push(fragment_mass - neutral_loss_mass);
+        }
+    }
+
     /// Pushs a new amino acid to sequence
     fn push_amino_acid(&mut self, amino_acid: &AminoAcid) {
-        self.weight += amino_acid.get_mono_mass();
+        self.weight += amino_acid.get_mass(self.mass_type);
         self.aa_sequence.push(amino_acid.get_one_letter_code());
     }
 
     /// Removes the last amino acid.
     fn undo_push_amino_acid(&mut self, amino_acid: &AminoAcid) {
-        self.weight -= amino_acid.get_mono_mass();
+        self.weight -= amino_acid.get_mass(self.mass_type);
         let last_idx = self.aa_sequence.len() - 1;
         self.aa_sequence.remove(last_idx);
     }
@@ -184,20 +441,23 @@ impl ModifiedPeptide {
         if let Some(modification) = modification_option {
             match modification.get_position() {
                 ModificationPosition::Anywhere => {
-                    self.weight += modification.get_mono_mass();
+                    self.weight += modification.get_mass(self.mass_type);
                     self.number_of_modifications += 1;
                     self.modifications.push(Some(modification.clone()));
                 },
+                // terminus modifications are stored separately, but `self.modifications` keeps one entry per amino acid
                 ModificationPosition::CTerminus => {
-                    self.weight += modification.get_mono_mass();
+                    self.weight += modification.get_mass(self.mass_type);
                     self.number_of_modifications += 1;
                     self.c_terminus_modification = Some(modification.clone());
+                    self.modifications.push(None);
                 },
                 // only if this is the first amino acid and modification
                 ModificationPosition::NTerminus if self.aa_sequence.len() == 1 => {
-                    self.weight += modification.get_mono_mass();
+                    self.weight += modification.get_mass(self.mass_type);
                     self.number_of_modifications += 1;
                     self.n_terminus_modification = Some(modification.clone());
+                    self.modifications.push(None);
                 },
                 _ => self.modifications.push(None)
             }
@@ -221,7 +481,7 @@ impl ModifiedPeptide {
         match self.modifications.get(last_idx) {
             Some(modification_option) => match modification_option {
                 Some(modification) => {
-                    self.weight -= modification.get_mono_mass();
+                    self.weight -= modification.get_mass(self.mass_type);
                     self.number_of_modifications -= 1;
                 },
                 None => ()
@@ -234,7 +494,7 @@ impl ModifiedPeptide {
         if self.aa_sequence.len() > 0 {
             match old_c_terminus_modification_option {
                 Some(modification) => {
-                    self.weight += modification.get_mono_mass();
+                    self.weight += modification.get_mass(self.mass_type);
                     self.number_of_modifications += 1;
                     self.c_terminus_modification = Some(modification.clone());
                 },
@@ -244,13 +504,15 @@ impl ModifiedPeptide {
     }
 
     pub fn to_decoy(&self) -> Decoy {
-        return Decoy::new(self.get_aa_sequence().as_str(), 0);
+        let mut decoy = Decoy::new(self.get_aa_sequence().as_str(), 0);
+        decoy.set_modified_weight(self.get_weight());
+        return decoy;
     }
 
     fn remove_c_terminus_modification(&mut self) -> Option<Modification> {
         match self.c_terminus_modification {
             Some(ref modification) => {
-                self.weight -= modification.get_mono_mass();
+                self.weight -= modification.get_mass(self.mass_type);
                 self.number_of_modifications -= 1;
             },
             None => ()
@@ -263,7 +525,7 @@ impl ModifiedPeptide {
     fn remove_n_terminus_modification(&mut self) {
         match self.n_terminus_modification {
             Some(ref modification) => {
-                self.weight -= modification.get_mono_mass();
+                self.weight -= modification.get_mass(self.mass_type);
                 self.number_of_modifications -= 1;
             },
             None => ()
@@ -304,13 +566,16 @@ impl ModifiedPeptide {
         if modification.is_fix() {
             return Err(ModifiedPeptideError::ModificationIsNotVariable);
         }
-        if modification.get_amino_acid_one_letter_code() != self.get_c_terminus_amino_acid() {
+        if !modification.matches_amino_acid(self.get_c_terminus_amino_acid()) {
             return Err(ModifiedPeptideError::ModificationDoesNotMatchToAminoAcid);
         }
+        if (modification.get_position() == ModificationPosition::ProteinCTerminus) & !self.is_protein_c_terminus {
+            return Err(ModifiedPeptideError::ModificationDoesNotMatchToTerminus);
+        }
         if self.c_terminus_modification.is_some() {
             return Err(ModifiedPeptideError::AlreadyFixModificationInPlace);
         }
-        self.weight += modification.get_mono_mass();
+        self.weight += modification.get_mass(self.mass_type);
         self.number_of_modifications += 1;
         self.c_terminus_modification = Some(modification.clone());
         // if self.get_distance_to_mass_tolerance() < 0 {
@@ -323,13 +588,16 @@ impl ModifiedPeptide {
         if modification.is_fix() {
             return Err(ModifiedPeptideError::ModificationIsNotVariable);
         }
-        if modification.get_amino_acid_one_letter_code() != self.get_n_terminus_amino_acid() {
+        if !modification.matches_amino_acid(self.get_n_terminus_amino_acid()) {
             return Err(ModifiedPeptideError::ModificationDoesNotMatchToAminoAcid);
         }
+        if (modification.get_position() == ModificationPosition::ProteinNTerminus) & !self.is_protein_n_terminus {
+            return Err(ModifiedPeptideError::ModificationDoesNotMatchToTerminus);
+        }
         if self.n_terminus_modification.is_some() {
             return Err(ModifiedPeptideError::AlreadyFixModificationInPlace);
         }
-        self.weight += modification.get_mono_mass();
+        self.weight += modification.get_mass(self.mass_type);
         self.number_of_modifications += 1;
         self.n_terminus_modification = Some(modification.clone());
         // if self.get_distance_to_mass_tolerance() < 0 {
@@ -342,12 +610,12 @@ impl ModifiedPeptide {
         if modification.is_fix() {
             return Err(ModifiedPeptideError::ModificationIsNotVariable);
         }
-        if modification.get_amino_acid_one_letter_code() != self.get_amino_acid_at(idx) {
+        if !modification.matches_amino_acid(self.get_amino_acid_at(idx)) {
             return Err(ModifiedPeptideError::ModificationDoesNotMatchToAminoAcid);
         }
-        if (idx == 0) & (modification.get_position() == ModificationPosition::NTerminus) {
+        if (idx == 0) & modification.get_position().is_n_terminal() {
             return self.set_variable_n_terminus_modification(modification);
-        } else if (idx == self.aa_sequence.len() - 1) & (modification.get_position() == ModificationPosition::CTerminus) {
+        } else if (idx == self.aa_sequence.len() - 1) & modification.get_position().is_c_terminal() {
             return self.set_variable_c_terminus_modification(modification);
         } else if modification.get_position() == ModificationPosition::Anywhere {
             match self.modifications.get_mut(idx) {
@@ -357,7 +625,7 @@ impl ModifiedPeptide {
                 },
                 None => ()
             }
-            self.weight += modification.get_mono_mass();
+            self.weight += modification.get_mass(self.mass_type);
             self.number_of_modifications += 1;
             // if self.get_distance_to_mass_tolerance() < 0 {
             //     return Err(ModifiedPeptideError::OutrangeMassTolerance);
@@ -369,7 +637,7 @@ impl ModifiedPeptide {
     pub fn remove_all_variable_modifications(&mut self) {
         let mut set_current_modification_to_none = match self.n_terminus_modification {
             Some(ref mut modification) if !modification.is_fix() => {
-                self.weight -= modification.get_mono_mass();
+                self.weight -= modification.get_mass(self.mass_type);
                 self.number_of_modifications -= 1;
                 true
             },
@@ -379,7 +647,7 @@ impl ModifiedPeptide {
 
         set_current_modification_to_none = match self.c_terminus_modification {
             Some(ref mut modification) if !modification.is_fix() => {
-                self.weight -= modification.get_mono_mass();
+                self.weight -= modification.get_mass(self.mass_type);
                 self.number_of_modifications -= 1;
                 true
             },
@@ -390,7 +658,7 @@ impl ModifiedPeptide {
         for modification_option in self.modifications.iter_mut() {
             set_current_modification_to_none = match modification_option {
                 Some(modification) if !modification.is_fix() => {
-                    self.weight -= modification.get_mono_mass();
+                    self.weight -= modification.get_mass(self.mass_type);
                     self.number_of_modifications -= 1;
                     true
                 },
@@ -407,7 +675,7 @@ impl ModifiedPeptide {
             Some(modification_options) => {
                 match modification_options {
                     Some(ref modification) => {
-                        self.weight -= modification.get_mono_mass();
+                        self.weight -= modification.get_mass(self.mass_type);
                         self.number_of_modifications -= 1;
                     },
                     None => ()
@@ -421,22 +689,22 @@ impl ModifiedPeptide {
     fn add_modification_at(&mut self, idx: usize, modification: &Modification) -> Result<(), ModifiedPeptideError> {
         match self.aa_sequence.get(idx) {
             Some(one_letter_code) => {
-                if *one_letter_code != modification.get_amino_acid_one_letter_code() {
+                if !modification.matches_amino_acid(*one_letter_code) {
                     return Err(ModifiedPeptideError::ModificationDoesNotMatchToAminoAcid);
                 }
             },
             None => return Err(ModifiedPeptideError::ModificationDoesNotMatchToAminoAcid)
         }
-        if (idx == 0) & (modification.get_position() == ModificationPosition::NTerminus) {
-            self.weight += modification.get_mono_mass();
+        if (idx == 0) & modification.get_position().is_n_terminal() & self.is_modification_applicable_at(idx, modification) {
+            self.weight += modification.get_mass(self.mass_type);
             self.number_of_modifications += 1;
             self.n_terminus_modification = Some(modification.clone());
-        } else if (idx == self.aa_sequence.len() - 1) & (modification.get_position() == ModificationPosition::CTerminus) {
-            self.weight += modification.get_mono_mass();
+        } else if (idx == self.aa_sequence.len() - 1) & modification.get_position().is_c_terminal() & self.is_modification_applicable_at(idx, modification) {
+            self.weight += modification.get_mass(self.mass_type);
             self.number_of_modifications += 1;
             self.c_terminus_modification = Some(modification.clone());
         } else if modification.get_position() == ModificationPosition::Anywhere {
-            self.weight += modification.get_mono_mass();
+            self.weight += modification.get_mass(self.mass_type);
             self.number_of_modifications += 1;
             match self.modifications.get_mut(idx) {
                 Some(current_modification) => *current_modification = Some(modification.clone()),
@@ -446,100 +714,275 @@ impl ModifiedPeptide {
         return Ok(());
     }
 
-    /// Swaps amino acid until a minimum is reached.
-    /// If only a local minimum is reached, respectively the no futher swaps can applied but mass tolerance is not hit, swap one random amino acid
-    pub fn swap_amino_acids_to_hit_mass_tolerance(&mut self, amino_acid_substitute_map: &HashMap<char, HashMap<char, i64>>, fix_modifications_map: &HashMap<char, Modification>, max_number_of_modifications: u8, varibale_modification_map: &HashMap<char, Modification>) -> bool {
-        // try 100 times
-        'tries: for _ in 0..100 {
-            'sequence: for idx in 0..self.aa_sequence.len() {
-                let aa_one_letter_code = self.get_amino_acid_at(idx);
-                if let Some(ref swaps) = amino_acid_substitute_map.get(&aa_one_letter_code) {
-                    let mut do_swap = false;
-                    let mut best_swap: (i64, char) = (self.get_distance_to_precursor_mass(), aa_one_letter_code);
-                    'swaps: for (amino_acid_swap, weight_change) in swaps.iter() {
-                        self.weight += weight_change;   // temporarily increase weight
-                        let distance = self.get_distance_to_precursor_mass();
-                        if distance < best_swap.0 {
-                            best_swap = (distance, *amino_acid_swap);
-                            do_swap = true;
+    /// Replaces the amino acid at the given index and applies the fixed modification of the replacement
+    fn replace_amino_acid_at(&mut self, idx: usize, replacement: char, fix_modifications_map: &HashMap<char, Modification>) {
+        let aa_one_letter_code = self.get_amino_acid_at(idx);
+        self.remove_modification_at(idx);
+        self.weight -= AminoAcid::get(aa_one_letter_code).get_mass(self.mass_type);
+        self.weight += AminoAcid::get(replacement).get_mass(self.mass_type);
+        match self.aa_sequence.get_mut(idx) {
+            Some(item) => *item = replacement,
+            None => panic!("proteomic::models::decoys::new_decoy::ModifiedPeptide.replace_amino_acid_at(): expected char insted of None at self.aa_sequence.get_mut(idx)")
+        }
+        if let Some(ref modification) = fix_modifications_map.get(&replacement) {
+            match self.add_modification_at(idx, modification) {
+                Ok(_) => (),
+                Err(err) => panic!("proteomic::models::decoys::new_decoy::ModifiedPeptide.replace_amino_acid_at(): {}", err)
+            };
+        }
+        // removing the modification of a terminus removes the terminal modifications too
+        if (idx == 0) | (idx == self.aa_sequence.len() - 1) {
+            self.apply_fixed_terminal_modifications(fix_modifications_map);
+        }
+    }
 
-                        }
-                        self.weight -= weight_change; // do not forget to reduce weight
+    /// Inserts an amino acid before the given index. The index must not be 0, so the n-terminus is not changed.
+    fn insert_amino_acid_at(&mut self, idx: usize, amino_acid_one_letter_code: char, fix_modifications_map: &HashMap<char, Modification>) {
+        if (idx == 0) | (idx > self.aa_sequence.len() - 1) {
+            panic!("proteomic::models::decoys::new_decoy::ModifiedPeptide.insert_amino_acid_at(): only insertions between the termini are allowed");
+        }
+        self.weight += AminoAcid::get(amino_acid_one_letter_code).get_mass(self.mass_type);
+        self.aa_sequence.insert(idx, amino_acid_one_letter_code);
+        self.modifications.insert(idx, None);
+        if let Some(ref modification) = fix_modifications_map.get(&amino_acid_one_letter_code) {
+            match self.add_modification_at(idx, modification) {
+                Ok(_) => (),
+                Err(err) => panic!("proteomic::models::decoys::new_decoy::ModifiedPeptide.insert_amino_acid_at(): {}", err)
+            };
+        }
+    }
+
+    /// Removes the amino acid at the given index, which must be between the termini.
+    fn remove_amino_acid_at(&mut self, idx: usize) {
+        if (idx == 0) | (idx >= self.aa_sequence.len() - 1) {
+            panic!("proteomic::models::decoys::new_decoy::ModifiedPeptide.remove_amino_acid_at(): only removals between the termini are allowed");
+        }
+        self.remove_modification_at(idx);
+        self.weight -= AminoAcid::get(self.get_amino_acid_at(idx)).get_mass(self.mass_type);
+        self.aa_sequence.remove(idx);
+        self.modifications.remove(idx);
+    }
+
+    /// Applies the single amino acid substitution which brings the weight closest to the precursor mass, for each position one after another.
+    /// Returns true if the mass tolerance is hit.
+    fn apply_single_substitutions(&mut self, amino_acid_substitute_map: &HashMap<char, HashMap<char, i64>>, fix_modifications_map: &HashMap<char, Modification>, max_number_of_modifications: u8, varibale_modification_map: &HashMap<char, Vec<Modification>>) -> bool {
+        for idx in 0..self.aa_sequence.len() {
+            let aa_one_letter_code = self.get_amino_acid_at(idx);
+            if let Some(ref swaps) = amino_acid_substitute_map.get(&aa_one_letter_code) {
+                let mut do_swap = false;
+                let mut best_swap: (i64, char) = (self.get_distance_to_precursor_mass(), aa_one_letter_code);
+                // iterate in a fixed order instead of the HashMap's order, so ties are always resolved the same way
+                'swaps: for amino_acid_swap in AMINO_ACIDS_FOR_DECOY_GENERATION.iter() {
+                    let weight_change = match swaps.get(amino_acid_swap) {
+                        Some(weight_change) => weight_change,
+                        None => continue 'swaps
+                    };
+                    let distance = (self.precursor_mass - (self.weight + weight_change)).abs();
+                    if distance < best_swap.0 {
+                        best_swap = (distance, *amino_acid_swap);
+                        do_swap = true;
                     }
-                    if do_swap {
-                        self.remove_modification_at(idx);
-                        self.weight -= AminoAcid::get(aa_one_letter_code).get_mono_mass();
-                        self.weight += AminoAcid::get(best_swap.1).get_mono_mass();
-                        match self.aa_sequence.get_mut(idx) {
-                            Some(item) => *item = best_swap.1,
-                            None => panic!("proteomic::models::decoys::new_decoy::ModifiedPeptide.swap_amino_acids_to_hit_mass_tolerance(): expected char insted of None at self.aa_sequence.get_mut(idx)")
-                        }
-                        if let Some(ref modification) = fix_modifications_map.get(&best_swap.1) {
-                            match self.add_modification_at(idx, modification) {
-                                Ok(_) => (),
-                                Err(err) => panic!("proteomic::models::decoys::new_decoy::ModifiedPeptide.swap_amino_acids_to_hit_mass_tolerance(): {}", err)
-                            };
-                        }
-                        if self.hits_mass_tolerance() { return true; }
-                        if self.try_variable_modifications(max_number_of_modifications, varibale_modification_map) { return true; }
+                }
+                if do_swap {
+                    self.replace_amino_acid_at(idx, best_swap.1, fix_modifications_map);
+                    if self.hits_mass_tolerance() { return true; }
+                    if self.try_variable_modifications(max_number_of_modifications, varibale_modification_map) { return true; }
+                }
+            }
+        }
+        return false;
+    }
+
+    /// Substitutes the two amino acids which can be replaced by a pair from the table with the smallest resulting distance to the precursor mass.
+    /// Returns None if no pair substitution reduces the distance, otherwise if the mass tolerance is hit.
+    fn apply_pair_substitution(&mut self, substitution_table: &TwoAminoAcidSubstitutionTable, fix_modifications_map: &HashMap<char, Modification>, max_number_of_modifications: u8, varibale_modification_map: &HashMap<char, Vec<Modification>>) -> Option<bool> {
+        let amino_acid_weights: Vec<i64> = self.aa_sequence.iter().map(|aa_one_letter_code| TwoAminoAcidSubstitutionTable::get_amino_acid_weight(*aa_one_letter_code, fix_modifications_map, self.mass_type)).collect();
+        let missing_weight: i64 = self.precursor_mass - self.weight;
+        // (distance, first index, second index, first replacement, second replacement)
+        let mut best_substitution: Option<(i64, usize, usize, char, char)> = None;
+        let mut best_distance: i64 = missing_weight.abs();
+        for first_idx in 0..amino_acid_weights.len() {
+            for second_idx in (first_idx + 1)..amino_acid_weights.len() {
+                let current_pair_weight: i64 = amino_acid_weights[first_idx] + amino_acid_weights[second_idx];
+                if let Some(pair) = substitution_table.get_closest_pair(current_pair_weight + missing_weight) {
+                    let distance: i64 = (missing_weight + current_pair_weight - pair.0).abs();
+                    if distance < best_distance {
+                        best_distance = distance;
+                        best_substitution = Some((distance, first_idx, second_idx, pair.1, pair.2));
                     }
                 }
             }
-            // swap one random amino acid because the current sequence is at it's minimum
-            let mut rng = thread_rng();
-            let idx_to_swap: usize = rng.gen_range(0, self.aa_sequence.len());
-            let aa_one_letter_code = self.get_amino_acid_at(idx_to_swap);
-            let random_replacement = *AMINO_ACIDS_FOR_DECOY_GENERATION.choose(&mut rng).unwrap();
-            self.remove_modification_at(idx_to_swap);
-            self.weight -= AminoAcid::get(aa_one_letter_code).get_mono_mass();
-            self.weight += AminoAcid::get(random_replacement).get_mono_mass();
-            match self.aa_sequence.get_mut(idx_to_swap) {
-                Some(item) => *item = random_replacement,
-                None => panic!("proteomic::models::decoys::new_decoy::ModifiedPeptide.swap_amino_acids_to_hit_mass_tolerance(): expected char insted of None at self.aa_sequence.get_mut(idx_to_swap)")
+        }
+        return match best_substitution {
+            Some(substitution) => {
+                self.replace_amino_acid_at(substitution.1, substitution.3, fix_modifications_map);
+                self.replace_amino_acid_at(substitution.2, substitution.4, fix_modifications_map);
+                Some(self.hits_mass_tolerance() || self.try_variable_modifications(max_number_of_modifications, varibale_modification_map))
+            },
+            None => None
+        };
+    }
+
+    /// Inserts or removes one amino acid between the termini if this reduces the distance to the precursor mass and the length stays within the limits.
+    /// Returns None if no length change reduces the distance, otherwise if the mass tolerance is hit.
+    fn apply_length_change<R: Rng>(&mut self, substitution_table: &TwoAminoAcidSubstitutionTable, mass_fitting_options: &MassFittingOptions, fix_modifications_map: &HashMap<char, Modification>, max_number_of_modifications: u8, varibale_modification_map: &HashMap<char, Vec<Modification>>, rng: &mut R) -> Option<bool> {
+        let length: usize = self.aa_sequence.len();
+        // both termini must stay in place
+        if length < 2 {
+            return None;
+        }
+        let missing_weight: i64 = self.precursor_mass - self.weight;
+        let mut best_distance: i64 = missing_weight.abs();
+        let mut insertion: Option<char> = None;
+        let mut removal_idx: Option<usize> = None;
+        if length < mass_fitting_options.get_max_length() {
+            if let Some(amino_acid) = substitution_table.get_closest_amino_acid(missing_weight) {
+                let distance: i64 = (missing_weight - amino_acid.0).abs();
+                if distance < best_distance {
+                    best_distance = distance;
+                    insertion = Some(amino_acid.1);
+                }
             }
-            if let Some(ref modification) = fix_modifications_map.get(&random_replacement) {
-                match self.add_modification_at(idx_to_swap, modification) {
-                    Ok(_) => (),
-                    Err(err) => panic!("proteomic::models::decoys::new_decoy::ModifiedPeptide.swap_amino_acids_to_hit_mass_tolerance(): {}", err)
-                };
+        }
+        if (length > mass_fitting_options.get_min_length()) & (length > 2) {
+            for idx in 1..(length - 1) {
+                let distance: i64 = (missing_weight + TwoAminoAcidSubstitutionTable::get_amino_acid_weight(self.get_amino_acid_at(idx), fix_modifications_map, self.mass_type)).abs();
+                if distance < best_distance {
+                    best_distance = distance;
+                    insertion = None;
+                    removal_idx = Some(idx);
+                }
+            }
+        }
+        if let Some(idx) = removal_idx {
+            self.remove_amino_acid_at(idx);
+        } else if let Some(amino_acid_one_letter_code) = insertion {
+            let idx: usize = rng.gen_range(1, length);
+            self.insert_amino_acid_at(idx, amino_acid_one_letter_code, fix_modifications_map);
+        } else {
+            return None;
+        }
+        return Some(self.hits_mass_tolerance() || self.try_variable_modifications(max_number_of_modifications, varibale_modification_map));
+    }
+
+    /// Swaps amino acid until a minimum is reached.
+    /// Each iteration applies the best single amino acid substitution for each position, then (if enabled) the best substitution of two amino acids
+    /// and the best insertion or deletion of one amino acid.
+    /// If only a local minimum is reached, respectively the no futher swaps can applied but mass tolerance is not hit, swap one random amino acid
+    /// The random swaps are drawn from `rng`, so the result is reproducible with a seeded generator.
+    pub fn swap_amino_acids_to_hit_mass_tolerance<R: Rng>(&mut self, amino_acid_substitute_map: &HashMap<char, HashMap<char, i64>>, substitution_table: &TwoAminoAcidSubstitutionTable, mass_fitting_options: &MassFittingOptions, fix_modifications_map: &HashMap<char, Modification>, max_number_of_modifications: u8, varibale_modification_map: &HashMap<char, Vec<Modification>>, amino_acid_composition: Option<&AminoAcidComposition>, rng: &mut R) -> bool {
+        for _ in 0..mass_fitting_options.get_max_iterations() {
+            if self.apply_single_substitutions(amino_acid_substitute_map, fix_modifications_map, max_number_of_modifications, varibale_modification_map) { return true; }
+            if mass_fitting_options.get_pair_substitutions() {
+                if let Some(true) = self.apply_pair_substitution(substitution_table, fix_modifications_map, max_number_of_modifications, varibale_modification_map) { return true; }
+            }
+            if mass_fitting_options.get_length_changes() {
+                match self.apply_length_change(substitution_table, mass_fitting_options, fix_modifications_map, max_number_of_modifications, varibale_modification_map, rng) {
+                    Some(true) => return true,
+                    // the length changed, so start the next iteration with single substitutions before swapping randomly
+                    Some(false) => continue,
+                    None => ()
+                }
             }
+            // swap one random amino acid because the current sequence is at it's minimum, the replacement follows the amino acid composition if given
+            let idx_to_swap: usize = rng.gen_range(0, self.aa_sequence.len());
+            let random_replacement = match amino_acid_composition {
+                Some(amino_acid_composition) => amino_acid_composition.choose_amino_acid(rng),
+                None => *AMINO_ACIDS_FOR_DECOY_GENERATION.choose(rng).unwrap()
+            };
+            self.replace_amino_acid_at(idx_to_swap, random_replacement, fix_modifications_map);
         }
         return false;
     }
 
     /// Uses all possible combination of variable modifications until a configuration fits mass tolerance or all combinations were tried.
     /// Returns true if configuration fits mass tolerance, false if not.
-    pub fn try_variable_modifications(&mut self, max_number_of_modifications: u8, varibale_modification_map: &HashMap<char, Modification>) -> bool {
-        // get positions of amino acids to which a variable modification exists
-        let mut modification_positions: Vec<(usize, char)> = Vec::new();
-        for (idx, one_letter_code) in self.aa_sequence.iter().enumerate() {
-            if varibale_modification_map.contains_key(one_letter_code) {
-                modification_positions.push((idx, *one_letter_code));
+    pub fn try_variable_modifications(&mut self, max_number_of_modifications: u8, varibale_modification_map: &HashMap<char, Vec<Modification>>) -> bool {
+        let modification_sites = self.get_variable_modification_sites(varibale_modification_map);
+        for number_of_modifications in 1..=max_number_of_modifications {
+            if number_of_modifications as usize <= modification_sites.len() {
+                let n_choose_k = NChooseK::new(number_of_modifications as i32, modification_sites.clone());
+                // loop through all possible modification and apply them
+                for combination in n_choose_k.into_iter() {
+                    if self.apply_variable_modification_combination(&combination, varibale_modification_map) && self.hits_mass_tolerance() { return true; }
+                }
             }
         }
+        return false;
+    }
+
+    /// Uses all possible combination of variable modifications and returns each configuration which fits the mass tolerance in ProForma notation, see `to_proforma()`.
+    /// Afterwards the first fitting configuration is applied, like `try_variable_modifications()` does.
+    pub fn enumerate_variable_modification_isoforms(&mut self, max_number_of_modifications: u8, varibale_modification_map: &HashMap<char, Vec<Modification>>) -> Vec<String> {
+        let modification_sites = self.get_variable_modification_sites(varibale_modification_map);
+        let mut isoforms: Vec<String> = Vec::new();
+        let mut first_fitting_combination: Option<Vec<(usize, u8, char, usize)>> = None;
         for number_of_modifications in 1..=max_number_of_modifications {
-            if number_of_modifications as usize <= modification_positions.len() {
-                let n_choose_k = NChooseK::new(number_of_modifications as i32, modification_positions.clone());
-                // loop through all possible modification and apply them
-                'combinations: for combination in n_choose_k.into_iter() {
-                    self.remove_all_variable_modifications(); // remove previous modifications
-                    'positions: for position in combination {
-                        if let Some(modification) = varibale_modification_map.get(&position.1) {
-                            match self.set_variable_modification_at(position.0, modification) {
-                                Ok(_) => continue 'positions,
-                                Err(err) => match err {
-                                    ModifiedPeptideError::OutrangeMassTolerance => continue 'combinations,
-                                    ModifiedPeptideError::AlreadyFixModificationInPlace => continue 'positions,
-                                    _ => panic!("proteomic::models::decoys::new_decoy::ModifiedPeptide.try_variable_modifications(): {}", err)
-                                }
-                            }
+            if number_of_modifications as usize <= modification_sites.len() {
+                let n_choose_k = NChooseK::new(number_of_modifications as i32, modification_sites.clone());
+                for combination in n_choose_k.into_iter() {
+                    if self.apply_variable_modification_combination(&combination, varibale_modification_map) && self.hits_mass_tolerance() {
+                        // combinations with a site which is already fixed modified result in the same isoform as the combination without this site
+                        let isoform = self.to_proforma();
+                        if !isoforms.contains(&isoform) {
+                            isoforms.push(isoform);
+                        }
+                        if first_fitting_combination.is_none() {
+                            first_fitting_combination = Some(combination);
                         }
                     }
-                    if self.hits_mass_tolerance() { return true; }
                 }
             }
         }
-        return false;
+        match first_fitting_combination {
+            Some(combination) => { self.apply_variable_modification_combination(&combination, varibale_modification_map); },
+            None => self.remove_all_variable_modifications()
+        }
+        return isoforms;
+    }
+
+    /// Returns the sites to which a variable modification exists, as tuples of the amino acid index, the modified site (0 = residue, 1 = n-terminus, 2 = c-terminus),
+    /// the key in the modification map and the index of the modification if there are several for the key
+    fn get_variable_modification_sites(&self, varibale_modification_map: &HashMap<char, Vec<Modification>>) -> Vec<(usize, u8, char, usize)> {
+        let mut modification_sites: Vec<(usize, u8, char, usize)> = Vec::new();
+        for (idx, one_letter_code) in self.aa_sequence.iter().enumerate() {
+            for key in [*one_letter_code, ANY_AMINO_ACID].iter() {
+                if let Some(modifications) = varibale_modification_map.get(key) {
+                    for (modification_idx, modification) in modifications.iter().enumerate() {
+                        if self.is_modification_applicable_at(idx, modification) {
+                            let site: u8 = if modification.get_position().is_n_terminal() { 1 } else if modification.get_position().is_c_terminal() { 2 } else { 0 };
+                            modification_sites.push((idx, site, *key, modification_idx));
+                        }
+                    }
+                }
+            }
+        }
+        // entries of the same site must be adjacent for the check in `apply_variable_modification_combination()`, the stable sort keeps the order of the modifications
+        modification_sites.sort_by_key(|site| (site.0, site.1));
+        return modification_sites;
+    }
+
+    /// Replaces the variable modifications by the given combination of sites from `get_variable_modification_sites()`.
+    /// Returns false if the combination is not applicable.
+    fn apply_variable_modification_combination(&mut self, combination: &Vec<(usize, u8, char, usize)>, varibale_modification_map: &HashMap<char, Vec<Modification>>) -> bool {
+        // a site carries only one variable modification
+        if combination.windows(2).any(|sites| (sites[0].0 == sites[1].0) & (sites[0].1 == sites[1].1)) {
+            return false;
+        }
+        self.remove_all_variable_modifications(); // remove previous modifications
+        'sites: for site in combination.iter() {
+            if let Some(modification) = varibale_modification_map.get(&site.2).and_then(|modifications| modifications.get(site.3)) {
+                match self.set_variable_modification_at(site.0, modification) {
+                    Ok(_) => continue 'sites,
+                    Err(err) => match err {
+                        ModifiedPeptideError::OutrangeMassTolerance => return false,
+                        ModifiedPeptideError::AlreadyFixModificationInPlace => continue 'sites,
+                        _ => panic!("proteomic::models::decoys::new_decoy::ModifiedPeptide.apply_variable_modification_combination(): {}", err)
+                    }
+                }
+            }
+        }
+        return true;
     }
 
     fn to_string(&self) -> String {
@@ -576,10 +1019,41 @@ impl ModifiedPeptide {
         return self.weight;
     }
 
+    pub fn get_mass_type(&self) -> MassType {
+        return self.mass_type;
+    }
+
+    /// Sets the mass type of weight, mass fitting and fragment masses and recalculates the weight.
+    /// `precursor_mass` and the weight limits must be given in the same mass type.
+    pub fn set_mass_type(&mut self, mass_type: MassType) {
+        self.mass_type = mass_type;
+        let mut weight: i64 = AminoAcid::get_sequence_mass(self.get_aa_sequence().as_str(), mass_type);
+        let terminus_modifications = self.n_terminus_modification.iter().chain(self.c_terminus_modification.iter());
+        for modification in self.modifications.iter().flatten().chain(terminus_modifications).chain(self.unlocalised_modifications.iter()) {
+            weight += modification.get_mass(mass_type);
+        }
+        self.weight = weight;
+    }
+
     pub fn get_length(&self) -> i32 {
         return self.aa_sequence.len() as i32;
     }
 
+    /// Grand average of hydropathy, modifications are not considered
+    pub fn get_gravy(&self) -> f64 {
+        return peptide_properties::calculate_gravy(self.get_aa_sequence().as_str());
+    }
+
+    /// Isoelectric point, modifications are not considered
+    pub fn get_isoelectric_point(&self) -> f64 {
+        return peptide_properties::calculate_isoelectric_point(self.get_aa_sequence().as_str());
+    }
+
+    /// Retention index as predictor for the retention time, modifications are not considered
+    pub fn get_retention_index(&self) -> f64 {
+        return peptide_properties::calculate_retention_index(self.get_aa_sequence().as_str());
+    }
+
     pub fn get_c_terminus_amino_acid(&self) -> char {
         match self.aa_sequence.iter().last() {
             Some(amino_acids_one_letter_code) => *amino_acids_one_letter_code,
@@ -619,6 +1093,14 @@ impl ModifiedPeptide {
                 None => ()
             }
         }
+        for modification in self.unlocalised_modifications.iter() {
+            let peff_notation_of_accession_and_name = format!("{}|{}", modification.get_accession(), modification.get_name());
+            let counter = match modification_counts.entry(peff_notation_of_accession_and_name) {
+                Vacant(entry) => entry.insert(0),
+                Occupied(entry) => entry.into_mut()
+            };
+            *counter += 1;
+        }
         match self.c_terminus_modification {
             Some(ref modification) => {
                 let peff_notation_of_accession_and_name = format!("{}|{}", modification.get_accession(), modification.get_name());
@@ -658,4 +1140,24 @@ impl ModifiedPeptide {
         return mod_res;
     }
 
+    /// Returns the localised modifications with their positions, sorted by position.
+    /// Positions start at 1, terminal modifications have the position of the terminal amino acid.
+    pub fn get_modification_positions(&self) -> Vec<(usize, &Modification)> {
+        let mut positions: Vec<(usize, &Modification)> = Vec::new();
+        if let Some(ref modification) = self.n_terminus_modification {
+            positions.push((1, modification));
+        }
+        for (idx, modification_option) in self.modifications.iter().enumerate() {
+            if let Some(ref modification) = *modification_option {
+                positions.push((idx + 1, modification));
+            }
+        }
+        if let Some(ref modification) = self.c_terminus_modification {
+            positions.push((self.aa_sequence.len(), modification));
+        }
+        // stable sort, so the n-terminal modification stays in front of the residue modification at the same position
+        positions.sort_by_key(|position| position.0);
+        return positions;
+    }
+
 }
@@ -61,11 +61,40 @@ impl Peptide {
         return accessions.join(",");
     }
 
-    pub fn get_header_with_modification_summary(&self, modification_summary: &str) -> String {
+    /// Selects the peptides which match the conditions together with (is_protein_n_terminus, is_protein_c_terminus),
+    /// which are true if the peptide is located at the respective terminus of any of its proteins.
+    /// The termini are fetched in the same query, so no additional query per peptide is necessary.
+    pub fn find_where_with_protein_termini(conn: &postgres::Connection, conditions: &str, values: &[&postgres::types::ToSql]) -> Result<Vec<(Self, bool, bool)>, QueryError> {
+        let select_query: String = format!(
+            "SELECT {peptide_table}.*, COALESCE(termini.is_protein_n_terminus, FALSE), COALESCE(termini.is_protein_c_terminus, FALSE) FROM {peptide_table} LEFT JOIN LATERAL (SELECT bool_or(is_protein_n_terminus) AS is_protein_n_terminus, bool_or(is_protein_c_terminus) AS is_protein_c_terminus FROM {association_table} WHERE {association_table}.peptide_id = {peptide_table}.id) termini ON TRUE WHERE {conditions};",
+            peptide_table = Self::get_table_name(),
+            association_table = PeptideProteinAssociation::get_table_name(),
+            conditions = conditions
+        );
+        match conn.query(select_query.as_str(), values) {
+            Ok(ref rows) => {
+                let mut peptides: Vec<(Self, bool, bool)> = Vec::new();
+                for row in rows {
+                    let number_of_columns: usize = row.len();
+                    match Self::from_sql_row(&row) {
+                        Ok(peptide) => peptides.push((peptide, row.get(number_of_columns - 2), row.get(number_of_columns - 1))),
+                        Err(err) => return Err(QueryError::InnerError(format!("{}", err)))
+                    }
+                }
+                return Ok(peptides);
+            },
+            Err(err) => Err(handle_postgres_error(&err))
+        }
+    }
+
+    pub fn get_header_with_modification_summary(&self, modification_summary: &str, proforma: &str) -> String {
         let mut header = self.get_header();
         if modification_summary.len() > 0 {
             header.push_str(format!(" ModRes={}", modification_summary).as_str());
         }
+        if proforma.len() > 0 {
+            header.push_str(format!(" ProForma={}", proforma).as_str());
+        }
         return header;
     }
 }
@@ -0,0 +1,119 @@
+/// pKa of the free N-terminus and C-terminus (EMBOSS)
+const N_TERMINUS_PKA: f64 = 8.6;
+const C_TERMINUS_PKA: f64 = 3.6;
+/// Precision of the bisection for the isoelectric point
+const ISOELECTRIC_POINT_PRECISION: f64 = 0.001;
+
+/// Physicochemical properties of a peptide, which are computed from the amino acid sequence.
+/// Modifications are not considered.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PeptideProperty {
+    Gravy,              // grand average of hydropathy (Kyte & Doolittle)
+    IsoelectricPoint,   // pH at which the net charge is zero (pKa values of EMBOSS)
+    RetentionIndex      // sum of reversed phase retention coefficients (Guo et al. 1986, pH 2), a predictor for the retention time
+}
+
+impl PeptideProperty {
+    pub fn to_string(&self) -> String {
+        return match self {
+            PeptideProperty::Gravy => "gravy".to_string(),
+            PeptideProperty::IsoelectricPoint => "pi".to_string(),
+            PeptideProperty::RetentionIndex => "rt".to_string()
+        }
+    }
+
+    pub fn from_str(property_name: &str) -> PeptideProperty {
+        return match property_name.trim().to_lowercase().as_str() {
+            "gravy" => PeptideProperty::Gravy,
+            "pi" => PeptideProperty::IsoelectricPoint,
+            "rt" => PeptideProperty::RetentionIndex,
+            _ => panic!("proteomic::models::peptides::peptide_properties::PeptideProperty::from_str(): Unknown property '{}', use 'gravy', 'pi' or 'rt'", property_name)
+        }
+    }
+
+    /// Calculates the property for the given sequence
+    pub fn calculate(&self, aa_sequence: &str) -> f64 {
+        return match self {
+            PeptideProperty::Gravy => calculate_gravy(aa_sequence),
+            PeptideProperty::IsoelectricPoint => calculate_isoelectric_point(aa_sequence),
+            PeptideProperty::RetentionIndex => calculate_retention_index(aa_sequence)
+        }
+    }
+}
+
+/// Kyte & Doolittle hydropathy. J is the average of I and L, O is treated like K and U like C.
+fn get_hydropathy(aa_one_letter_code: char) -> f64 {
+    return match aa_one_letter_code {
+        'A' => 1.8, 'R' => -4.5, 'N' => -3.5, 'D' => -3.5, 'C' => 2.5, 'E' => -3.5, 'Q' => -3.5,
+        'G' => -0.4, 'H' => -3.2, 'I' => 4.5, 'L' => 3.8, 'J' => 4.15, 'K' => -3.9, 'M' => 1.9,
+        'F' => 2.8, 'P' => -1.6, 'O' => -3.9, 'S' => -0.8, 'T' => -0.7, 'U' => 2.5, 'V' => 4.2,
+        'W' => -0.9, 'Y' => -1.3,
+        _ => 0.0
+    };
+}
+
+/// Retention coefficients of Guo et al. (1986) at pH 2. J is the average of I and L, O is treated like K and U like C.
+fn get_retention_coefficient(aa_one_letter_code: char) -> f64 {
+    return match aa_one_letter_code {
+        'A' => 2.0, 'R' => -0.6, 'N' => -0.6, 'D' => 0.2, 'C' => 2.6, 'E' => 1.1, 'Q' => 0.0,
+        'G' => -0.2, 'H' => -2.1, 'I' => 7.4, 'L' => 8.1, 'J' => 7.75, 'K' => -2.1, 'M' => 5.5,
+        'F' => 8.1, 'P' => 2.0, 'O' => -2.1, 'S' => -0.2, 'T' => 0.6, 'U' => 2.6, 'V' => 5.0,
+        'W' => 8.8, 'Y' => 4.5,
+        _ => 0.0
+    };
+}
+
+/// pKa of charged side chains, the bool is true for positively charged side chains. O is treated like K and U like C.
+fn get_side_chain_pka(aa_one_letter_code: char) -> Option<(f64, bool)> {
+    return match aa_one_letter_code {
+        'K' | 'O' => Some((10.8, true)),
+        'R' => Some((12.5, true)),
+        'H' => Some((6.5, true)),
+        'D' => Some((3.9, false)),
+        'E' => Some((4.1, false)),
+        'C' | 'U' => Some((8.5, false)),
+        'Y' => Some((10.1, false)),
+        _ => None
+    };
+}
+
+pub fn calculate_gravy(aa_sequence: &str) -> f64 {
+    if aa_sequence.is_empty() {
+        return 0.0;
+    }
+    return aa_sequence.chars().map(|aa_one_letter_code| get_hydropathy(aa_one_letter_code)).sum::<f64>() / aa_sequence.chars().count() as f64;
+}
+
+pub fn calculate_retention_index(aa_sequence: &str) -> f64 {
+    return aa_sequence.chars().map(|aa_one_letter_code| get_retention_coefficient(aa_one_letter_code)).sum::<f64>();
+}
+
+/// Net charge of the sequence at the given pH (Henderson-Hasselbalch)
+pub fn calculate_net_charge(aa_sequence: &str, ph: f64) -> f64 {
+    let positive_charge = |pka: f64| 1.0 / (1.0 + 10f64.powf(ph - pka));
+    let negative_charge = |pka: f64| -1.0 / (1.0 + 10f64.powf(pka - ph));
+    let mut net_charge: f64 = positive_charge(N_TERMINUS_PKA) + negative_charge(C_TERMINUS_PKA);
+    for aa_one_letter_code in aa_sequence.chars() {
+        net_charge += match get_side_chain_pka(aa_one_letter_code) {
+            Some((pka, true)) => positive_charge(pka),
+            Some((pka, false)) => negative_charge(pka),
+            None => 0.0
+        };
+    }
+    return net_charge;
+}
+
+/// Finds the pH with a net charge of zero by bisection, the net charge decreases with increasing pH
+pub fn calculate_isoelectric_point(aa_sequence: &str) -> f64 {
+    let mut lower_ph: f64 = 0.0;
+    let mut upper_ph: f64 = 14.0;
+    while upper_ph - lower_ph > ISOELECTRIC_POINT_PRECISION {
+        let ph: f64 = (lower_ph + upper_ph) / 2.0;
+        if calculate_net_charge(aa_sequence, ph) > 0.0 {
+            lower_ph = ph;
+        } else {
+            upper_ph = ph;
+        }
+    }
+    return (lower_ph + upper_ph) / 2.0;
+}
@@ -0,0 +1,210 @@
+use std::fmt;
+
+use proteomic::models::mass;
+use proteomic::models::amino_acids::modification::Modification;
+
+/// Tolerance for matching a mass delta, e.g. `[+15.995]`, to a modification, 0.01 Da
+const MASS_DELTA_TOLERANCE: i64 = 10000;
+
+pub enum ProFormaError {
+    UnexpectedCharacter(usize, char),
+    UnclosedModification(usize),
+    EmptySequence,
+    UnknownModification(String),
+    InvalidModificationSite(String, String)
+}
+
+impl ProFormaError {
+    pub fn to_string(&self) -> String {
+        return match self {
+            ProFormaError::UnexpectedCharacter(position, character) => format!("ProFormaError::UnexpectedCharacter('{}' at position {})", character, position),
+            ProFormaError::UnclosedModification(position) => format!("ProFormaError::UnclosedModification(opened at position {})", position),
+            ProFormaError::EmptySequence => format!("ProFormaError::EmptySequence"),
+            ProFormaError::UnknownModification(tag) => format!("ProFormaError::UnknownModification({})", tag),
+            ProFormaError::InvalidModificationSite(tag, site) => format!("ProFormaError::InvalidModificationSite({} at {})", tag, site)
+        };
+    }
+}
+
+impl fmt::Display for ProFormaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", self.to_string());
+    }
+}
+
+/// Amino acids and modification tags of a ProForma 2.0 string, e.g. `[UNIMOD:35]?[UNIMOD:1]-PEPC[UNIMOD:4]K-[+0.984]`, before the tags are resolved to modifications.
+/// Supported are residue, terminal and unlocalised modifications (optionally with count, e.g. `[Phospho]^2?`).
+pub struct ProFormaSequence {
+    aa_sequence: Vec<char>,
+    residue_modifications: Vec<Option<String>>,
+    n_terminus_modification: Option<String>,
+    c_terminus_modification: Option<String>,
+    unlocalised_modifications: Vec<String>
+}
+
+impl ProFormaSequence {
+    pub fn parse(proforma: &str) -> Result<Self, ProFormaError> {
+        let characters: Vec<char> = proforma.trim().chars().collect();
+        let mut proforma_sequence = Self {
+            aa_sequence: Vec::new(),
+            residue_modifications: Vec::new(),
+            n_terminus_modification: None,
+            c_terminus_modification: None,
+            unlocalised_modifications: Vec::new()
+        };
+        let mut idx: usize = 0;
+        // unlocalised modifications (followed by '?') and n-terminal modification (followed by '-')
+        let mut pending_tags: Vec<(String, usize)> = Vec::new();
+        while idx < characters.len() && characters[idx] == '[' {
+            let (tag, next_idx) = Self::read_tag(&characters, idx)?;
+            let (count, next_idx) = Self::read_count(&characters, next_idx)?;
+            idx = next_idx;
+            pending_tags.push((tag, count));
+            match characters.get(idx) {
+                Some(&'[') => (),
+                Some(&'?') => {
+                    for (tag, count) in pending_tags.drain(..) {
+                        for _ in 0..count {
+                            proforma_sequence.unlocalised_modifications.push(tag.clone());
+                        }
+                    }
+                    idx += 1;
+                },
+                Some(&'-') if (pending_tags.len() == 1) && (pending_tags[0].1 == 1) && proforma_sequence.n_terminus_modification.is_none() => {
+                    proforma_sequence.n_terminus_modification = Some(pending_tags.remove(0).0);
+                    idx += 1;
+                },
+                Some(character) => return Err(ProFormaError::UnexpectedCharacter(idx, *character)),
+                None => return Err(ProFormaError::EmptySequence)
+            }
+        }
+        // amino acids with residue modifications and c-terminal modification
+        while idx < characters.len() {
+            let character = characters[idx];
+            if character.is_ascii_uppercase() {
+                proforma_sequence.aa_sequence.push(character);
+                proforma_sequence.residue_modifications.push(None);
+                idx += 1;
+                if idx < characters.len() && characters[idx] == '[' {
+                    let (tag, next_idx) = Self::read_tag(&characters, idx)?;
+                    if let Some(last_modification) = proforma_sequence.residue_modifications.last_mut() {
+                        *last_modification = Some(tag);
+                    }
+                    idx = next_idx;
+                }
+            } else if (character == '-') && !proforma_sequence.aa_sequence.is_empty() && (idx + 1 < characters.len()) && (characters[idx + 1] == '[') {
+                let (tag, next_idx) = Self::read_tag(&characters, idx + 1)?;
+                if next_idx < characters.len() {
+                    return Err(ProFormaError::UnexpectedCharacter(next_idx, characters[next_idx]));
+                }
+                proforma_sequence.c_terminus_modification = Some(tag);
+                idx = next_idx;
+            } else {
+                return Err(ProFormaError::UnexpectedCharacter(idx, character));
+            }
+        }
+        if proforma_sequence.aa_sequence.is_empty() {
+            return Err(ProFormaError::EmptySequence);
+        }
+        return Ok(proforma_sequence);
+    }
+
+    /// Reads the tag between the bracket at `start_idx` and its closing bracket, brackets within the tag are allowed if they are balanced.
+    /// Returns the tag and the index after the closing bracket.
+    fn read_tag(characters: &Vec<char>, start_idx: usize) -> Result<(String, usize), ProFormaError> {
+        let mut depth: usize = 0;
+        for idx in start_idx..characters.len() {
+            match characters[idx] {
+                '[' => depth += 1,
+                ']' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok((characters[(start_idx + 1)..idx].iter().collect::<String>(), idx + 1));
+                    }
+                },
+                _ => ()
+            }
+        }
+        return Err(ProFormaError::UnclosedModification(start_idx));
+    }
+
+    /// Reads the count of an unlocalised modification, e.g. `^2`. Returns 1 if there is no count.
+    fn read_count(characters: &Vec<char>, start_idx: usize) -> Result<(usize, usize), ProFormaError> {
+        if characters.get(start_idx) != Some(&'^') {
+            return Ok((1, start_idx));
+        }
+        let mut idx = start_idx + 1;
+        while idx < characters.len() && characters[idx].is_ascii_digit() {
+            idx += 1;
+        }
+        return match characters[(start_idx + 1)..idx].iter().collect::<String>().parse::<usize>() {
+            Ok(count) if count > 0 => Ok((count, idx)),
+            _ => Err(ProFormaError::UnexpectedCharacter(start_idx, '^'))
+        };
+    }
+
+    pub fn get_aa_sequence(&self) -> &Vec<char> {
+        return &self.aa_sequence;
+    }
+
+    pub fn get_residue_modifications(&self) -> &Vec<Option<String>> {
+        return &self.residue_modifications;
+    }
+
+    pub fn get_n_terminus_modification(&self) -> &Option<String> {
+        return &self.n_terminus_modification;
+    }
+
+    pub fn get_c_terminus_modification(&self) -> &Option<String> {
+        return &self.c_terminus_modification;
+    }
+
+    pub fn get_unlocalised_modifications(&self) -> &Vec<String> {
+        return &self.unlocalised_modifications;
+    }
+}
+
+/// Returns the ProForma tag of the modification, the Unimod accession (e.g. `UNIMOD:35`) if available, otherwise the mass delta (e.g. `+15.994915`)
+pub fn get_modification_tag(modification: &Modification) -> String {
+    let accession: &str = modification.get_accession();
+    if accession.to_ascii_lowercase().starts_with("unimod:") {
+        return format!("UNIMOD:{}", &accession[7..]);
+    }
+    return format!("{:+}", mass::convert_mass_to_float(modification.get_mono_mass()));
+}
+
+/// Returns the modification of `candidates` which is described by the tag and is applicable at the site.
+/// A tag is a Unimod accession (`UNIMOD:35`), a name (`Oxidation` or `U:Oxidation`) or a mass delta (`+15.995`), alternatives are separated by `|`.
+/// A mass delta without matching candidate results in the given `mass_delta_modification` with the mass of the tag.
+pub fn resolve_modification<F>(tag: &str, site: &str, candidates: &Vec<&Modification>, is_applicable: F, mass_delta_modification: &Fn(f64) -> Modification) -> Result<Modification, ProFormaError> where F: Fn(&Modification) -> bool {
+    let mut is_known: bool = false;
+    for alternative in tag.split('|') {
+        let alternative = alternative.trim();
+        let mass_delta: Option<f64> = if alternative.starts_with('+') || alternative.starts_with('-') { alternative.parse::<f64>().ok() } else { None };
+        let matches_tag = |modification: &Modification| -> bool {
+            if let Some(mass_delta) = mass_delta {
+                return (modification.get_mono_mass() - mass::convert_mass_to_int(mass_delta)).abs() <= MASS_DELTA_TOLERANCE;
+            } else if alternative.to_ascii_uppercase().starts_with("UNIMOD:") {
+                return modification.get_accession().eq_ignore_ascii_case(alternative);
+            } else if alternative.to_ascii_uppercase().starts_with("U:") {
+                return modification.get_name().eq_ignore_ascii_case(&alternative[2..]);
+            }
+            return modification.get_name().eq_ignore_ascii_case(alternative);
+        };
+        for modification in candidates.iter() {
+            if matches_tag(modification) {
+                if is_applicable(modification) {
+                    return Ok((*modification).clone());
+                }
+                is_known = true;
+            }
+        }
+        if let Some(mass_delta) = mass_delta {
+            return Ok(mass_delta_modification(mass_delta));
+        }
+    }
+    if is_known {
+        return Err(ProFormaError::InvalidModificationSite(tag.to_owned(), site.to_owned()));
+    }
+    return Err(ProFormaError::UnknownModification(tag.to_owned()));
+}
@@ -0,0 +1,50 @@
+use proteomic::models::persistable::{Persistable, QueryOk};
+use proteomic::models::peptides::decoy::Decoy;
+use proteomic::models::peptides::decoy_provenance::DecoyProvenance;
+use proteomic::utility::database_connection::DatabaseConnection;
+
+
+#[test]
+/// Creates the same decoy twice in one run and checks that the decoy is stored once and each creation recorded a provenance with the run id.
+fn test_decoy_and_provenance_are_created_together() {
+    let conn = DatabaseConnection::get_database_connection();
+    let run_id: i64 = match DecoyProvenance::next_run_id(&conn) {
+        Ok(run_id) => run_id,
+        Err(err) => panic!("could not get run id: {}", err)
+    };
+    let provenance: DecoyProvenance = DecoyProvenance::new(run_id, "composition", 42, 1, 1000000000, 999990000, 1000010000);
+    let mut decoy: Decoy = Decoy::new("WYPMHCWQDK", 0);
+    let is_created_by_test: bool = match provenance.create_with_decoy(&conn, &mut decoy) {
+        Ok(QueryOk::Created) => true,
+        Ok(_) => false,
+        Err(err) => panic!("could not create decoy with provenance: {}", err)
+    };
+    assert!(decoy.is_persisted());
+    let mut same_decoy: Decoy = Decoy::new("WYPMHCWQDK", 0);
+    match provenance.create_with_decoy(&conn, &mut same_decoy) {
+        Ok(QueryOk::AlreadyExists) => (),
+        Ok(_) => panic!("decoy was created twice"),
+        Err(err) => panic!("could not create provenance for existing decoy: {}", err)
+    }
+    assert_eq!(same_decoy.get_primary_key(), decoy.get_primary_key());
+    let provenances: Vec<DecoyProvenance> = match DecoyProvenance::find_where(&conn, "run_id = $1", &[&run_id]) {
+        Ok(provenances) => provenances,
+        Err(err) => panic!("could not find provenances: {}", err)
+    };
+    assert_eq!(provenances.len(), 2);
+    for run_provenance in provenances.iter() {
+        assert_eq!(run_provenance.get_decoy_id(), decoy.get_primary_key());
+        assert_eq!(run_provenance.get_run_id(), run_id);
+    }
+    // only remove the decoy if this test has created it, the provenances are removed by cascade
+    if is_created_by_test {
+        match decoy.delete(&conn) {
+            Ok(_) => (),
+            Err(err) => panic!("could not delete decoy: {}", err)
+        }
+        match DecoyProvenance::count_where(&conn, "run_id = $1", &[&run_id]) {
+            Ok(count) => assert_eq!(count, 0),
+            Err(err) => panic!("could not count provenances: {}", err)
+        }
+    }
+}
@@ -1 +1,5 @@
-mod peptide;
\ No newline at end of file
+mod decoy_provenance;
+mod modified_peptide;
+mod peptide;
+mod peptide_properties;
+mod proforma;
\ No newline at end of file
@@ -0,0 +1,38 @@
+use proteomic::models::mass;
+use proteomic::models::amino_acids::amino_acid::AminoAcid;
+use proteomic::models::amino_acids::modification::{Modification, ModificationPosition};
+use proteomic::models::amino_acids::modification_registry::ModificationRegistry;
+use proteomic::models::peptides::modified_peptide::ModifiedPeptide;
+use proteomic::models::peptides::peptide_interface::PeptideInterface;
+
+#[test]
+/// Checks that both positions of a single oxidation in "PMEPMK" are listed as isoforms in ProForma notation and the first one stays applied
+pub fn test_enumerate_variable_modification_isoforms() {
+    let registry = ModificationRegistry::from_modifications(&vec![
+        Modification::new("unimod:4", "Carbamidomethyl", ModificationPosition::Anywhere, true, 'C', 57.021464),
+        Modification::new("unimod:35", "Oxidation", ModificationPosition::Anywhere, false, 'M', 15.994915),
+        Modification::new("unimod:425", "Dioxidation", ModificationPosition::Anywhere, false, 'M', 31.989829)
+    ]);
+    let precursor_mass: i64 = AminoAcid::get_sequence_weight("PMEPMK") + mass::convert_mass_to_int(15.994915);
+    let precursor_tolerance = mass::calculate_precursor_tolerance(precursor_mass, 5, 5);
+    let mut peptide = ModifiedPeptide::from_string("", "PMEPMK", precursor_mass, precursor_tolerance.0, precursor_tolerance.1, registry.get_fixed_modification_map());
+    let isoforms = peptide.enumerate_variable_modification_isoforms(2, registry.get_variable_modification_map());
+    assert_eq!(vec!["PM[UNIMOD:35]EPMK".to_owned(), "PMEPM[UNIMOD:35]K".to_owned()], isoforms);
+    assert!(peptide.hits_mass_tolerance());
+    assert_eq!("PM[UNIMOD:35]EPMK", peptide.to_proforma());
+}
+
+#[test]
+/// Checks that a decoy built from an oxidized "PMEPMK" keeps the modified weight besides the unmodified sequence weight
+pub fn test_to_decoy_keeps_modified_weight() {
+    let registry = ModificationRegistry::from_modifications(&vec![
+        Modification::new("unimod:35", "Oxidation", ModificationPosition::Anywhere, false, 'M', 15.994915)
+    ]);
+    let peptide = match ModifiedPeptide::from_proforma("", "PM[UNIMOD:35]EPMK", 0, 0, 0, &registry) {
+        Ok(peptide) => peptide,
+        Err(_) => panic!("could not parse 'PM[UNIMOD:35]EPMK'")
+    };
+    let decoy = peptide.to_decoy();
+    assert_eq!(AminoAcid::get_sequence_weight("PMEPMK"), decoy.get_weight());
+    assert_eq!(AminoAcid::get_sequence_weight("PMEPMK") + mass::convert_mass_to_int(15.994915), decoy.get_modified_weight());
+}
@@ -0,0 +1,29 @@
+use proteomic::models::peptides::peptide_properties::{PeptideProperty, calculate_gravy, calculate_isoelectric_point, calculate_retention_index};
+
+#[test]
+pub fn test_gravy() {
+    // (1.8 - 4.5) / 2
+    assert!((calculate_gravy("AR") + 1.35).abs() < 0.0001);
+}
+
+#[test]
+pub fn test_isoelectric_point_order() {
+    assert!(calculate_isoelectric_point("KKRK") > calculate_isoelectric_point("GGAG"));
+    assert!(calculate_isoelectric_point("GGAG") > calculate_isoelectric_point("DDED"));
+}
+
+#[test]
+pub fn test_property_names() {
+    for property in [PeptideProperty::Gravy, PeptideProperty::IsoelectricPoint, PeptideProperty::RetentionIndex].iter() {
+        assert_eq!(*property, PeptideProperty::from_str(property.to_string().as_str()));
+    }
+}
+
+#[test]
+pub fn test_retention_index() {
+    // 2.0 + 8.1 - 2.1 (Guo et al.)
+    assert!((calculate_retention_index("ALK") - 8.0).abs() < 0.0001);
+    // J is the average of I and L
+    assert!((calculate_retention_index("J") - (calculate_retention_index("I") + calculate_retention_index("L")) / 2.0).abs() < 0.0001);
+    assert_eq!(PeptideProperty::RetentionIndex.calculate("ALK"), calculate_retention_index("ALK"));
+}
@@ -0,0 +1,49 @@
+use proteomic::models::mass;
+use proteomic::models::amino_acids::amino_acid::AminoAcid;
+use proteomic::models::amino_acids::modification::{Modification, ModificationPosition};
+use proteomic::models::amino_acids::modification_registry::ModificationRegistry;
+use proteomic::models::peptides::modified_peptide::ModifiedPeptide;
+use proteomic::models::peptides::proforma::ProFormaError;
+
+fn get_registry() -> ModificationRegistry {
+    return ModificationRegistry::from_modifications(&vec![
+        Modification::new("unimod:4", "Carbamidomethyl", ModificationPosition::Anywhere, true, 'C', 57.021464),
+        Modification::new("unimod:35", "Oxidation", ModificationPosition::Anywhere, false, 'M', 15.994915),
+        Modification::new("unimod:1", "Acetyl", ModificationPosition::NTerminus, false, '*', 42.010565)
+    ]);
+}
+
+#[test]
+/// Parses terminal, residue and unlocalised modifications given by accession, name and mass delta and writes them back
+pub fn test_proforma_round_trip() {
+    let registry = get_registry();
+    let peptide = ModifiedPeptide::from_proforma("", "[UNIMOD:1]-PEPC[UNIMOD:4]M[Oxidation]K-[+0.984016]", 0, 0, 0, &registry).ok().unwrap();
+    assert_eq!("[UNIMOD:1]-PEPC[UNIMOD:4]M[UNIMOD:35]K-[+0.984016]", peptide.to_proforma());
+    let expected_weight: i64 = AminoAcid::get_sequence_weight("PEPCMK") + mass::convert_mass_to_int(42.010565) + mass::convert_mass_to_int(57.021464) + mass::convert_mass_to_int(15.994915) + mass::convert_mass_to_int(0.984016);
+    assert_eq!(expected_weight, peptide.get_weight());
+    let peptide = ModifiedPeptide::from_proforma("", "[Oxidation]^2?PMEPMK", 0, 0, 0, &registry).ok().unwrap();
+    assert_eq!("[UNIMOD:35]^2?PMEPMK", peptide.to_proforma());
+    assert_eq!(AminoAcid::get_sequence_weight("PMEPMK") + 2 * mass::convert_mass_to_int(15.994915), peptide.get_weight());
+    // isoleucine and leucine are not generalized
+    let peptide = ModifiedPeptide::from_proforma("", "PEPTIDELM[Oxidation]K", 0, 0, 0, &registry).ok().unwrap();
+    assert_eq!("PEPTIDELM[UNIMOD:35]K", peptide.to_proforma());
+    assert_eq!(AminoAcid::get_sequence_weight("PEPTIDELMK") + mass::convert_mass_to_int(15.994915), peptide.get_weight());
+}
+
+#[test]
+/// Checks that unknown modifications, modifications at wrong sites and unclosed brackets are rejected
+pub fn test_invalid_proforma() {
+    let registry = get_registry();
+    match ModifiedPeptide::from_proforma("", "PEPS[Phospho]K", 0, 0, 0, &registry) {
+        Err(ProFormaError::UnknownModification(tag)) => assert_eq!("Phospho", tag),
+        _ => panic!("expected ProFormaError::UnknownModification")
+    }
+    match ModifiedPeptide::from_proforma("", "PEPK[UNIMOD:35]", 0, 0, 0, &registry) {
+        Err(ProFormaError::InvalidModificationSite(_, site)) => assert_eq!("K4", site),
+        _ => panic!("expected ProFormaError::InvalidModificationSite")
+    }
+    match ModifiedPeptide::from_proforma("", "PEPM[UNIMOD:35K", 0, 0, 0, &registry) {
+        Err(ProFormaError::UnclosedModification(position)) => assert_eq!(4, position),
+        _ => panic!("expected ProFormaError::UnclosedModification")
+    }
+}
@@ -1 +1,3 @@
-mod protein;
\ No newline at end of file
+mod modification_config;
+mod modification_registry;
+mod protein;
@@ -0,0 +1,87 @@
+use std::env;
+use std::fs::File;
+use std::io::prelude::*;
+
+use proteomic::models::mass;
+use proteomic::models::amino_acids::modification_config::{self, ConfigLocation, ModificationConfigError};
+use proteomic::models::amino_acids::modification_registry::ModificationRegistry;
+
+fn write_temp_file(file_name: &str, content: &[u8]) -> String {
+    let mut file_path = env::temp_dir();
+    file_path.push(file_name);
+    File::create(&file_path).unwrap().write_all(content).unwrap();
+    return file_path.to_str().unwrap().to_owned();
+}
+
+#[test]
+/// Reads modifications from a TOML-file
+pub fn test_read_toml_file() {
+    let file_path = write_temp_file("max_decoy_test_modifications.toml", b"[[modification]]\naccession = \"unimod:4\"\nname = \"Carbamidomethyl\"\nposition = \"A\"\nfixed = true\namino_acid = \"C\"\nmono_mass = 57.021464\naverage_mass = 57.0513\n");
+    let file_content = modification_config::read_modification_file(&file_path).ok().unwrap();
+    let modifications = file_content.get_modifications();
+    assert_eq!(modifications.len(), 1);
+    assert!(modifications[0].is_fix());
+    assert_eq!(modifications[0].get_average_mass(), mass::convert_mass_to_int(57.0513));
+}
+
+#[test]
+/// Checks that a missing field in a JSON-file is reported with its field and entry and an invalid mass in a CSV-file with its field and line
+pub fn test_invalid_fields() {
+    let file_path = write_temp_file("max_decoy_test_modifications.json", b"{\"modification\": [\n  {\"accession\": \"unimod:4\", \"name\": \"Carbamidomethyl\", \"position\": \"A\", \"fixed\": true, \"amino_acid\": \"C\", \"mono_mass\": 57.021464},\n  {\"accession\": \"unimod:35\", \"name\": \"Oxidation\", \"position\": \"A\", \"fixed\": false, \"amino_acid\": \"M\"}\n]}");
+    match modification_config::read_modification_file(&file_path) {
+        Err(ModificationConfigError::InvalidField(_, location, field, _)) => {
+            assert_eq!(location, ConfigLocation::Entry("modification".to_owned(), 2));
+            assert_eq!(field, "mono_mass");
+        },
+        _ => panic!("expected ModificationConfigError::InvalidField")
+    }
+    let file_path = write_temp_file("max_decoy_test_modifications.csv", b"accession,name,position,is_fix,amino_acid,mono_mass\nunimod:4,Carbamidomethyl,A,1,C,57.021464\nunimod:35,Oxidation,A,0,M,15.99a\n");
+    match modification_config::read_modification_file(&file_path) {
+        Err(ModificationConfigError::InvalidField(_, location, field, _)) => {
+            assert_eq!(location, ConfigLocation::Line(3));
+            assert_eq!(field, "mono_mass");
+        },
+        _ => panic!("expected ModificationConfigError::InvalidField")
+    }
+}
+
+#[test]
+/// Checks that a fixed and a variable modification on the same amino acid are rejected when the file is loaded
+pub fn test_conflicting_modifications_in_file() {
+    let file_path = write_temp_file("max_decoy_test_conflicting_modifications.csv", b"accession,name,position,is_fix,amino_acid,mono_mass\nunimod:4,Carbamidomethyl,A,1,C,57.021464\nunimod:39,Methylthio,A,0,C,45.987721\n");
+    match ModificationRegistry::from_file(&file_path) {
+        Err(ModificationConfigError::Conflict(_, _)) => (),
+        _ => panic!("expected ModificationConfigError::Conflict")
+    }
+}
+
+#[test]
+/// Reads a SILAC label set and checks that only the heavy channel carries the label as fixed modification
+pub fn test_label_channels() {
+    let file_path = write_temp_file("max_decoy_test_label_channels.toml", b"[[modification]]\naccession = \"unimod:35\"\nname = \"Oxidation\"\nfixed = false\nposition = \"A\"\namino_acid = \"M\"\nmono_mass = 15.994915\n\n[[label_channel]]\nname = \"light\"\n\n[[label_channel]]\nname = \"heavy\"\n\n[[label_channel.label]]\naccession = \"unimod:259\"\nname = \"Label:13C(6)15N(2)\"\namino_acid = \"K\"\nmono_mass = 8.014199\n");
+    let modification_registry = ModificationRegistry::from_file(&file_path).ok().unwrap();
+    assert_eq!(modification_registry.get_label_channels().len(), 2);
+    let light_registry = modification_registry.get_channel_registry(&modification_registry.get_label_channels()[0]);
+    let heavy_registry = modification_registry.get_channel_registry(&modification_registry.get_label_channels()[1]);
+    assert!(!light_registry.get_fixed_modification_map().contains_key(&'K'));
+    assert_eq!(heavy_registry.get_fixed_modification_map().get(&'K').unwrap().get_mono_mass(), mass::convert_mass_to_int(8.014199));
+    assert!(!heavy_registry.has_label_channels());
+    // a channel name must be unique, whitespaces in the table headers do not change the reported entry
+    let file_path = write_temp_file("max_decoy_test_duplicate_label_channels.toml", b"[[ label_channel ]]\nname = \"heavy\"\n\n[[ label_channel ]]\nname = \"light\"\n\n[[label_channel]]\nname = \"light\"\n");
+    match modification_config::read_modification_file(&file_path) {
+        Err(ModificationConfigError::InvalidField(_, location, field, _)) => {
+            assert_eq!(location, ConfigLocation::Entry("label_channel".to_owned(), 3));
+            assert_eq!(field, "name");
+        },
+        _ => panic!("expected ModificationConfigError::InvalidField")
+    }
+}
+
+#[test]
+/// Checks that modifications of a CSV-file are rejected for average masses, because the file has no average masses
+pub fn test_check_average_masses() {
+    let file_path = write_temp_file("max_decoy_test_average_modifications.toml", b"[[modification]]\naccession = \"unimod:4\"\nname = \"Carbamidomethyl\"\nposition = \"A\"\nfixed = true\namino_acid = \"C\"\nmono_mass = 57.021464\naverage_mass = 57.0513\n");
+    assert!(ModificationRegistry::from_file(&file_path).ok().unwrap().check_average_masses().is_ok());
+    let file_path = write_temp_file("max_decoy_test_average_modifications.csv", b"accession,name,position,is_fix,amino_acid,mono_mass\nunimod:4,Carbamidomethyl,A,1,C,57.021464\n");
+    assert!(ModificationRegistry::from_file(&file_path).ok().unwrap().check_average_masses().is_err());
+}
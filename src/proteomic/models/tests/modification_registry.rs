@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use proteomic::models::mass;
+use proteomic::models::mass::mass_type::MassType;
+use proteomic::models::mass::neutral_loss::NeutralLoss;
+use proteomic::models::amino_acids::amino_acid::AminoAcid;
+use proteomic::models::amino_acids::modification::{Modification, ModificationPosition};
+use proteomic::models::amino_acids::modification_registry::ModificationRegistry;
+use proteomic::models::peptides::modified_peptide::ModifiedPeptide;
+
+fn get_oxidation_registry() -> ModificationRegistry {
+    return ModificationRegistry::from_modifications(&vec![
+        Modification::new("unimod:4", "Carbamidomethyl", ModificationPosition::Anywhere, true, 'C', 57.021464),
+        Modification::new("unimod:35", "Oxidation", ModificationPosition::Anywhere, false, 'M', 15.994915),
+        Modification::new("unimod:425", "Dioxidation", ModificationPosition::Anywhere, false, 'M', 31.989829),
+        Modification::new("unimod:35", "Oxidation", ModificationPosition::Anywhere, false, 'M', 15.994915)
+    ]);
+}
+
+#[test]
+/// Checks that both variable modifications of M are kept and the duplicate is ignored
+pub fn test_several_variable_modifications_per_amino_acid() {
+    let registry = get_oxidation_registry();
+    assert_eq!(2, registry.get_variable_modifications('M').len());
+    assert!(registry.get_variable_modifications('S').is_empty());
+    assert_eq!(vec!['C', 'M'], registry.get_modifiable_amino_acids());
+    assert_eq!(Some((0, mass::convert_mass_to_int(31.989829))), registry.get_mass_shift_range('M', MassType::Monoisotopic));
+}
+
+#[test]
+/// Checks that the mass shift range of M consists of the average masses for average mass type
+pub fn test_average_mass_shift_range() {
+    let mut oxidation = Modification::new("unimod:35", "Oxidation", ModificationPosition::Anywhere, false, 'M', 15.994915);
+    oxidation.set_average_mass(15.9994);
+    let registry = ModificationRegistry::from_modifications(&vec![oxidation]);
+    assert_eq!(Some((0, mass::convert_mass_to_int(15.994915))), registry.get_mass_shift_range('M', MassType::Monoisotopic));
+    assert_eq!(Some((0, mass::convert_mass_to_int(15.9994))), registry.get_mass_shift_range('M', MassType::Average));
+}
+
+#[test]
+/// Checks that the second variable modification of M is tried, if the first one does not hit the precursor tolerance
+pub fn test_try_second_variable_modification() {
+    let registry = get_oxidation_registry();
+    let precursor_mass: i64 = AminoAcid::get_sequence_weight("PEPMK") + mass::convert_mass_to_int(31.989829);
+    let precursor_tolerance = mass::calculate_precursor_tolerance(precursor_mass, 5, 5);
+    let mut peptide = ModifiedPeptide::from_string("", "PEPMK", precursor_mass, precursor_tolerance.0, precursor_tolerance.1, registry.get_fixed_modification_map());
+    assert!(!peptide.hits_mass_tolerance());
+    assert!(peptide.try_variable_modifications(1, registry.get_variable_modification_map()));
+}
+
+#[test]
+#[should_panic]
+/// Checks that a second fixed modification of the same amino acid is rejected
+pub fn test_conflicting_fixed_modifications() {
+    let mut registry = get_oxidation_registry();
+    registry.add(Modification::new("unimod:39", "Methylthio", ModificationPosition::Anywhere, true, 'C', 45.987721));
+}
+
+#[test]
+/// Checks that a protein N-terminal modification is only applied to peptides at the protein N-terminus
+/// and that a fixed modification for any amino acid is applied to the peptide N-terminus
+pub fn test_terminal_modifications_for_any_amino_acid() {
+    let registry = ModificationRegistry::from_modifications(&vec![
+        Modification::new("unimod:737", "TMT6plex", ModificationPosition::NTerminus, true, '*', 229.162932),
+        Modification::new("unimod:1", "Acetyl", ModificationPosition::ProteinNTerminus, false, '*', 42.010565)
+    ]);
+    assert!(registry.get_modifiable_amino_acids().is_empty());
+    assert!(registry.has_protein_terminal_modifications());
+    let tmt_precursor_mass: i64 = AminoAcid::get_sequence_weight("PEPMK") + mass::convert_mass_to_int(229.162932);
+    let tmt_precursor_tolerance = mass::calculate_precursor_tolerance(tmt_precursor_mass, 5, 5);
+    let peptide = ModifiedPeptide::from_string("", "PEPMK", tmt_precursor_mass, tmt_precursor_tolerance.0, tmt_precursor_tolerance.1, registry.get_fixed_modification_map());
+    assert!(peptide.hits_mass_tolerance());
+    let acetyl_precursor_mass: i64 = AminoAcid::get_sequence_weight("PEPMK") + mass::convert_mass_to_int(42.010565);
+    let acetyl_precursor_tolerance = mass::calculate_precursor_tolerance(acetyl_precursor_mass, 5, 5);
+    let mut peptide = ModifiedPeptide::from_string("", "PEPMK", acetyl_precursor_mass, acetyl_precursor_tolerance.0, acetyl_precursor_tolerance.1, &HashMap::new());
+    assert!(!peptide.try_variable_modifications(1, registry.get_variable_modification_map()));
+    peptide.set_protein_termini(true, false, &HashMap::new());
+    assert!(peptide.try_variable_modifications(1, registry.get_variable_modification_map()));
+}
+
+#[test]
+/// Checks that a fixed and a variable modification on the same site are rejected, but not on different termini
+/// or on the peptide and the protein n-terminus
+pub fn test_fixed_and_variable_modification_on_same_site() {
+    let mut registry = get_oxidation_registry();
+    assert!(registry.try_add(Modification::new("unimod:39", "Methylthio", ModificationPosition::Anywhere, false, 'C', 45.987721)).is_err());
+    assert!(registry.try_add(Modification::new("unimod:737", "TMT6plex", ModificationPosition::NTerminus, true, '*', 229.162932)).is_ok());
+    assert!(registry.try_add(Modification::new("unimod:1", "Acetyl", ModificationPosition::ProteinNTerminus, false, '*', 42.010565)).is_ok());
+    assert!(registry.try_add(Modification::new("unimod:1", "Acetyl", ModificationPosition::NTerminus, false, '*', 42.010565)).is_err());
+    assert!(registry.try_add(Modification::new("unimod:2", "Amidated", ModificationPosition::CTerminus, false, '*', -0.984016)).is_ok());
+}
+
+#[test]
+/// Checks that fragments containing a modification with neutral loss are also returned with the loss and that general losses apply to every fragment
+pub fn test_fragment_masses_with_neutral_losses() {
+    let mut phospho = Modification::new("unimod:21", "Phospho", ModificationPosition::Anywhere, true, 'S', 79.966331);
+    phospho.add_neutral_loss(NeutralLoss::get("H3PO4"));
+    let registry = ModificationRegistry::from_modifications(&vec![phospho]);
+    let peptide = ModifiedPeptide::from_string("", "PSK", 0, 0, 0, registry.get_fixed_modification_map());
+    let fragment_masses = peptide.get_fragment_masses(&Vec::new());
+    assert_eq!(fragment_masses.len(), 6);
+    let phosphorylated_b_ion_mass: i64 = AminoAcid::get_sequence_weight("PS") - NeutralLoss::get("H2O").get_mono_mass() + mass::convert_mass_to_int(79.966331);
+    assert!(fragment_masses.contains(&phosphorylated_b_ion_mass));
+    assert!(fragment_masses.contains(&(phosphorylated_b_ion_mass - NeutralLoss::get("H3PO4").get_mono_mass())));
+    assert_eq!(peptide.get_fragment_masses(&vec![NeutralLoss::get("NH3")]).len(), 10);
+}
+
+#[test]
+/// Checks that a fixed protein terminal modification for any amino acid is optional in the mass shift range, like a variable one
+pub fn test_any_amino_acid_mass_shift_range() {
+    let registry = ModificationRegistry::from_modifications(&vec![
+        Modification::new("unimod:1", "Acetyl", ModificationPosition::ProteinNTerminus, true, '*', 42.010565),
+        Modification::new("unimod:2", "Amidated", ModificationPosition::CTerminus, false, '*', -0.984016)
+    ]);
+    assert_eq!((mass::convert_mass_to_int(-0.984016), mass::convert_mass_to_int(42.010565)), registry.get_any_amino_acid_mass_shift_range(MassType::Monoisotopic));
+}
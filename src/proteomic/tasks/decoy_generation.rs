@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::prelude::*;
+use std::io::LineWriter;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use threadpool::ThreadPool;
+
+use proteomic::models::mass;
+use proteomic::models::mass::mass_type::MassType;
+use proteomic::models::peptides::peptide_interface::PeptideInterface;
+use proteomic::models::amino_acids::modification_registry::ModificationRegistry;
+use proteomic::utility::database_connection::DatabaseConnection;
+use proteomic::utility::decoy_generator::{DecoyGenerator, GenerationResult, GenerationStrategy};
+use proteomic::utility::length_distribution::LengthDistribution;
+use proteomic::utility::mass_fitting::MassFittingOptions;
+use proteomic::utility::precursor_reader::{self, Precursor};
+use proteomic::utility::target_index::TargetIndex;
+
+pub struct DecoyGenerationArguments {
+    precursor_file: Option<String>,
+    precursor_mass: Option<i64>,
+    output_directory: String,
+    output_format: String,
+    modification_file: Option<String>,
+    max_modifications_per_decoy: u8,
+    number_of_decoys: usize,
+    lower_mass_tolerance_ppm: i64,
+    upper_mass_tolerance_ppm: i64,
+    thread_count: usize,
+    max_time_for_decoy_generation: i64,
+    seed: u64,
+    generation_strategy: GenerationStrategy,
+    target_index_file: Option<String>,
+    decoy_length_distribution: String,
+    min_peptide_length: usize,
+    max_peptide_length: usize,
+    mass_fitting_options: MassFittingOptions,
+    mass_type: MassType
+}
+
+impl DecoyGenerationArguments {
+    pub fn get_precursor_file(&self) -> Option<&str> {
+        return match self.precursor_file {
+            Some(ref file) => Some(file.as_str()),
+            None => None
+        };
+    }
+
+    pub fn get_precursor_mass(&self) -> Option<i64> {
+        return self.precursor_mass;
+    }
+
+    pub fn get_output_directory(&self) -> &str {
+        return self.output_directory.as_str();
+    }
+
+    pub fn get_output_format(&self) -> &str {
+        return self.output_format.as_str();
+    }
+
+    pub fn get_modification_file(&self) -> Option<&str> {
+        return match self.modification_file {
+            Some(ref file) => Some(file.as_str()),
+            None => None
+        };
+    }
+
+    pub fn get_max_modifications_per_decoy(&self) -> u8 {
+        return self.max_modifications_per_decoy;
+    }
+
+    pub fn get_number_of_decoys(&self) -> usize {
+        return self.number_of_decoys;
+    }
+
+    pub fn get_lower_mass_tolerance_ppm(&self) -> i64 {
+        return self.lower_mass_tolerance_ppm;
+    }
+
+    pub fn get_upper_mass_tolerance_ppm(&self) -> i64 {
+        return self.upper_mass_tolerance_ppm;
+    }
+
+    pub fn get_thread_count(&self) -> usize {
+        return self.thread_count;
+    }
+
+    pub fn get_max_time_for_decoy_generation(&self) -> i64 {
+        return self.max_time_for_decoy_generation;
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        return self.seed;
+    }
+
+    pub fn get_generation_strategy(&self) -> GenerationStrategy {
+        return self.generation_strategy;
+    }
+
+    pub fn get_target_index_file(&self) -> Option<&str> {
+        return match self.target_index_file {
+            Some(ref file) => Some(file.as_str()),
+            None => None
+        };
+    }
+
+    pub fn get_decoy_length_distribution(&self) -> &str {
+        return self.decoy_length_distribution.as_str();
+    }
+
+    pub fn get_min_peptide_length(&self) -> usize {
+        return self.min_peptide_length;
+    }
+
+    pub fn get_max_peptide_length(&self) -> usize {
+        return self.max_peptide_length;
+    }
+
+    pub fn get_mass_fitting_options(&self) -> &MassFittingOptions {
+        return &self.mass_fitting_options;
+    }
+
+    pub fn get_mass_type(&self) -> MassType {
+        return self.mass_type;
+    }
+
+    pub fn from_cli_args(cli_args: &clap::ArgMatches) -> Self {
+        let precursor_mass: Option<i64> = match cli_args.value_of("PRECURSOR_MASS") {
+            Some(number_string) => match number_string.to_owned().parse::<f64>() {
+                Ok(number) => Some(mass::convert_mass_to_int(number)),
+                Err(_) => panic!("proteomic::tasks::decoy_generation::DecoyGenerationArguments.from_cli_args(): could not cast precursor-mass to float")
+            },
+            None => None
+        };
+        let precursor_file: Option<String> = match cli_args.value_of("PRECURSOR_FILE") {
+            Some(file) => Some(file.to_owned()),
+            None => None
+        };
+        if precursor_mass.is_none() & precursor_file.is_none() {
+            panic!("proteomic::tasks::decoy_generation::DecoyGenerationArguments.from_cli_args(): precursor-mass or precursor-file is needed");
+        }
+        let max_modifications_per_decoy: u8 = match cli_args.value_of("MAX_MODIFICATION_PER_DECOY") {
+            Some(number_string) => match number_string.to_owned().parse::<u8>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::decoy_generation::DecoyGenerationArguments.from_cli_args(): could not cast max-modification-per-decoy to (unsigned) integer (8 bit)")
+            },
+            None => 0
+        };
+        let number_of_decoys: usize = match cli_args.value_of("NUMBER_OF_DECOYS") {
+            Some(number_string) => match number_string.to_owned().parse::<usize>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::decoy_generation::DecoyGenerationArguments.from_cli_args(): could not cast number-of-decoys to unsigned integer")
+            },
+            None => 1000
+        };
+        let lower_mass_tolerance_ppm: i64 = match cli_args.value_of("LOWER_MASS_TOLERANCE") {
+            Some(number_string) => match number_string.to_owned().parse::<i64>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::decoy_generation::DecoyGenerationArguments.from_cli_args(): could not cast lower-mass-tolerance to integer")
+            },
+            None => 5
+        };
+        let upper_mass_tolerance_ppm: i64 = match cli_args.value_of("UPPER_MASS_TOLERANCE") {
+            Some(number_string) => match number_string.to_owned().parse::<i64>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::decoy_generation::DecoyGenerationArguments.from_cli_args(): could not cast upper-mass-tolerance to integer")
+            },
+            None => 5
+        };
+        let cpu_thread_count: usize = num_cpus::get();
+        let thread_count: usize = match cli_args.value_of("THREAD_COUNT") {
+            Some(count) => match count.to_lowercase().as_str() {
+                "max" => cpu_thread_count,
+                _ => match count.to_owned().parse::<usize>() {
+                    Ok(parsed_count) => {
+                        if (1 > parsed_count) | (parsed_count > cpu_thread_count) {
+                            panic!("proteomic::tasks::decoy_generation::DecoyGenerationArguments.from_cli_args(): thread-count must be between {} and {}.", 1, cpu_thread_count);
+                        }
+                        parsed_count
+                    },
+                    Err(_) => panic!("proteomic::tasks::decoy_generation::DecoyGenerationArguments.from_cli_args(): cannot parse thread-count to (unsigned) integer")
+                }
+            },
+            None => {
+                println!("WARNING [decoy-generation]: no thread-count , set it to 1.");
+                1
+            }
+        };
+        let max_time_for_decoy_generation: i64 = match cli_args.value_of("MAX_TIME_FOR_DECOY_GENERATION") {
+            Some(number_string) => match number_string.to_owned().parse::<i64>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::decoy_generation::DecoyGenerationArguments.from_cli_args(): could not cast max-time-for-decoy-generation to integer")
+            },
+            None => 60
+        };
+        let seed: u64 = match cli_args.value_of("SEED") {
+            Some(number_string) => match number_string.to_owned().parse::<u64>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::decoy_generation::DecoyGenerationArguments.from_cli_args(): could not cast seed to unsigned integer (64 bit)")
+            },
+            None => rand::random::<u64>()
+        };
+        let generation_strategy: GenerationStrategy = match cli_args.value_of("GENERATION_STRATEGY") {
+            Some(strategy_name) => GenerationStrategy::from_str(strategy_name),
+            None => GenerationStrategy::RandomWalk
+        };
+        let min_peptide_length: usize = match cli_args.value_of("MIN_PEPTIDE_LENGTH") {
+            Some(number_string) => match number_string.to_owned().parse::<usize>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::decoy_generation::DecoyGenerationArguments.from_cli_args(): could not cast minimum-peptide-length to unsigned integer")
+            },
+            None => 5
+        };
+        let max_peptide_length: usize = match cli_args.value_of("MAX_PEPTIDE_LENGTH") {
+            Some(number_string) => match number_string.to_owned().parse::<usize>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::decoy_generation::DecoyGenerationArguments.from_cli_args(): could not cast maximum-peptide-length to unsigned integer")
+            },
+            None => 50
+        };
+        return Self {
+            precursor_file: precursor_file,
+            precursor_mass: precursor_mass,
+            output_directory: cli_args.value_of("OUTPUT_DIRECTORY").unwrap_or(".").to_owned(),
+            output_format: cli_args.value_of("OUTPUT_FORMAT").unwrap_or("fasta").to_owned(),
+            modification_file: match cli_args.value_of("MODIFICATION_FILE") {
+                Some(file) => Some(file.to_owned()),
+                None => None
+            },
+            max_modifications_per_decoy: max_modifications_per_decoy,
+            number_of_decoys: number_of_decoys,
+            lower_mass_tolerance_ppm: lower_mass_tolerance_ppm,
+            upper_mass_tolerance_ppm: upper_mass_tolerance_ppm,
+            thread_count: thread_count,
+            max_time_for_decoy_generation: max_time_for_decoy_generation,
+            seed: seed,
+            generation_strategy: generation_strategy,
+            target_index_file: match cli_args.value_of("TARGET_INDEX_FILE") {
+                Some(file) => Some(file.to_owned()),
+                None => None
+            },
+            decoy_length_distribution: cli_args.value_of("DECOY_LENGTH_DISTRIBUTION").unwrap_or("none").to_owned(),
+            min_peptide_length: min_peptide_length,
+            max_peptide_length: max_peptide_length,
+            mass_fitting_options: MassFittingOptions::from_cli_args(cli_args, min_peptide_length, max_peptide_length),
+            mass_type: MassType::from_str(cli_args.value_of("MASS_TYPE").unwrap_or("mono"))
+        }
+    }
+}
+
+/// Generates decoys for a single precursor mass or for each precursor of a precursor file and writes them to one FASTA- or TSV-file per precursor.
+/// Precursors are processed in parallel, the threads are split between the precursors which are processed at the same time.
+/// The seed of a precursor is derived from the run seed and the precursor id, so the decoys of a precursor do not depend on the other precursors.
+pub fn decoy_generation_task(decoy_generation_args: &DecoyGenerationArguments) {
+    let precursors: Vec<Precursor> = match decoy_generation_args.get_precursor_file() {
+        Some(precursor_file) => precursor_reader::read_precursors(precursor_file),
+        None => vec![Precursor::new("precursor", decoy_generation_args.get_precursor_mass().unwrap())]
+    };
+    if precursors.is_empty() {
+        println!("no precursors found");
+        return;
+    }
+    // each precursor gets its own output file, so two precursors must not share a filename
+    {
+        let mut filename_safe_ids: HashMap<String, &str> = HashMap::new();
+        for precursor in precursors.iter() {
+            if let Some(other_id) = filename_safe_ids.insert(precursor.get_filename_safe_id(), precursor.get_id()) {
+                panic!("proteomic::tasks::decoy_generation::decoy_generation_task(): the precursor ids '{}' and '{}' result in the same output file, precursor ids must be unique", other_id, precursor.get_id());
+            }
+        }
+    }
+    // prepare modifications
+    let modification_registry: ModificationRegistry = match decoy_generation_args.get_modification_file() {
+        Some(modification_file) => match ModificationRegistry::from_file(modification_file) {
+            Ok(modification_registry) => modification_registry,
+            Err(err) => panic!("proteomic::tasks::decoy_generation::decoy_generation_task(): {}", err)
+        },
+        None => ModificationRegistry::new()
+    };
+    // without average mass a modification would be applied with its mono mass
+    if decoy_generation_args.get_mass_type() == MassType::Average {
+        if let Err(err) = modification_registry.check_average_masses() {
+            panic!("proteomic::tasks::decoy_generation::decoy_generation_task(): {}", err);
+        }
+    }
+    // a stale index would let decoys pass which are targets, so the index must match the database
+    let target_index: Option<Arc<TargetIndex>> = match decoy_generation_args.get_target_index_file() {
+        Some(target_index_file) => {
+            let conn = DatabaseConnection::get_database_connection();
+            Some(Arc::new(TargetIndex::load_for_database(target_index_file, &conn)))
+        },
+        None => None
+    };
+    // each precursor generator gets an equal share of the threads
+    let number_of_parallel_precursors: usize = std::cmp::min(decoy_generation_args.get_thread_count(), precursors.len());
+    let threads_per_precursor: usize = std::cmp::max(decoy_generation_args.get_thread_count() / number_of_parallel_precursors, 1);
+    println!("seed: {}", decoy_generation_args.get_seed());
+    println!("generate decoys for {} precursors, {} at once with {} threads each", precursors.len(), number_of_parallel_precursors, threads_per_precursor);
+    let incomplete_precursors: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let thread_pool = ThreadPool::new(number_of_parallel_precursors);
+    for precursor in precursors.into_iter() {
+        let precursor_tolerance = mass::calculate_precursor_tolerance(
+            precursor.get_mass(),
+            decoy_generation_args.get_upper_mass_tolerance_ppm(),
+            decoy_generation_args.get_lower_mass_tolerance_ppm()
+        );
+        let mut generator: DecoyGenerator = DecoyGenerator::new(
+            precursor.get_mass(),
+            precursor_tolerance.0,
+            precursor_tolerance.1,
+            threads_per_precursor,
+            decoy_generation_args.get_max_modifications_per_decoy(),
+            modification_registry.get_fixed_modification_map(),
+            modification_registry.get_variable_modification_map(),
+            decoy_generation_args.get_max_time_for_decoy_generation()
+        );
+        generator.set_seed(DecoyGenerator::derive_seed_from_str(decoy_generation_args.get_seed(), precursor.get_id()));
+        generator.set_generation_strategy(decoy_generation_args.get_generation_strategy());
+        generator.set_mass_type(decoy_generation_args.get_mass_type());
+        generator.set_mass_fitting_options(decoy_generation_args.get_mass_fitting_options().clone());
+        if let Some(ref target_index) = target_index {
+            generator.set_target_index(target_index.clone());
+        }
+        if decoy_generation_args.get_decoy_length_distribution() == "range" {
+            generator.set_length_distribution(LengthDistribution::uniform(decoy_generation_args.get_min_peptide_length(), decoy_generation_args.get_max_peptide_length()));
+        }
+        let number_of_decoys: usize = decoy_generation_args.get_number_of_decoys();
+        // the extension is appended, because set_extension() would replace everything after the last dot of ids like 'scan.1'
+        let mut output_filename: PathBuf = PathBuf::from(decoy_generation_args.get_output_directory());
+        output_filename.push(format!("{}.{}", precursor.get_filename_safe_id(), decoy_generation_args.get_output_format()));
+        let output_format: String = decoy_generation_args.get_output_format().to_owned();
+        let incomplete_precursors_ptr = incomplete_precursors.clone();
+        thread_pool.execute(move || {
+            let start_time: f64 = time::precise_time_s();
+            let generation_result: GenerationResult = generator.generate_decoys(number_of_decoys);
+            let stop_time: f64 = time::precise_time_s();
+            println!(
+                "{} ({} Da): generate {} of {} decoys in {} s ({})",
+                precursor.get_id(),
+                mass::convert_mass_to_float(precursor.get_mass()),
+                generation_result.get_statistics().generated_decoys,
+                number_of_decoys,
+                stop_time - start_time,
+                generation_result.get_status().to_string()
+            );
+            if !generation_result.is_success() {
+                println!("{}: {}", precursor.get_id(), generation_result.get_statistics().to_string());
+                match incomplete_precursors_ptr.lock() {
+                    Ok(mut incomplete_precursors) => incomplete_precursors.push(precursor.get_id().to_owned()),
+                    Err(_) => panic!("proteomic::tasks::decoy_generation::decoy_generation_task(): try to lock poisened mutex for incomplete precursors")
+                }
+            }
+            write_decoys(&generator, &precursor, &output_filename, output_format.as_str());
+        });
+    }
+    thread_pool.join();
+    match incomplete_precursors.lock() {
+        Ok(incomplete_precursors) => if incomplete_precursors.len() > 0 {
+            println!("not enough decoys for {} precursors: {}", incomplete_precursors.len(), incomplete_precursors.join(", "));
+        },
+        Err(_) => panic!("proteomic::tasks::decoy_generation::decoy_generation_task(): try to lock poisened mutex for incomplete precursors")
+    }
+}
+
+/// Writes the decoys of the generator as FASTA or as TSV with the columns precursor_id, aa_sequence, weight (including modifications), modifications and proforma.
+/// The decoys are sorted by sequence, so the output of the same seed is identical.
+fn write_decoys(generator: &DecoyGenerator, precursor: &Precursor, output_filename: &PathBuf, output_format: &str) {
+    let output_file = match OpenOptions::new().write(true).create(true).truncate(true).open(output_filename) {
+        Ok(file) => file,
+        Err(err) => panic!("proteomic::tasks::decoy_generation::write_decoys(): error at opening '{}': {}", output_filename.display(), err)
+    };
+    let mut output_file = LineWriter::new(output_file);
+    let mut lines: Vec<String> = match generator.get_decoys().lock() {
+        Ok(decoys) => decoys.iter().map(|decoy| match output_format {
+            "tsv" => format!("{}\t{}\t{}\t{}\t{}\n", precursor.get_id(), decoy.get_aa_sequence(), mass::convert_mass_to_float(decoy.get_modified_weight()), decoy.get_modification_summary(), decoy.get_proforma()),
+            _ => format!("{}\n{}\n", decoy.get_header(), decoy.get_aa_sequence())
+        }).collect(),
+        Err(_) => panic!("proteomic::tasks::decoy_generation::write_decoys(): try to lock poisened mutex for decoys at generator.get_decoys().lock()")
+    };
+    lines.sort();
+    if output_format == "tsv" {
+        lines.insert(0, "precursor_id\taa_sequence\tweight\tmodifications\tproforma\n".to_owned());
+    }
+    for line in lines.iter() {
+        match output_file.write(line.as_bytes()) {
+            Ok(_) => (),
+            Err(err) => println!("proteomic::tasks::decoy_generation::write_decoys(): Could not write to '{}': {}", output_filename.display(), err)
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use std::sync::Arc;
+
+use proteomic::models::mass;
+use proteomic::models::mass::mass_type::MassType;
+use proteomic::models::mass::neutral_loss::NeutralLoss;
+use proteomic::models::persistable::Persistable;
+use proteomic::models::peptides::decoy::Decoy;
+use proteomic::models::amino_acids::amino_acid::{AminoAcid, AMINO_ACIDS_FOR_COUNTING};
+use proteomic::models::amino_acids::modification_registry::ModificationRegistry;
+use proteomic::utility::database_connection::DatabaseConnection;
+use proteomic::utility::decoy_generator::{DecoyGenerator, GenerationResult, GenerationStrategy};
+use proteomic::utility::target_index::TargetIndex;
+
+const DECOY_POOL_BIN_CONDITION: &str = "weight >= $1 AND weight < $2";
+
+pub struct DecoyPoolArguments {
+    min_mass: i64,
+    max_mass: i64,
+    bin_width: i64,
+    decoys_per_bin: usize,
+    thread_count: usize,
+    max_time_per_bin: i64,
+    seed: u64,
+    generation_strategy: GenerationStrategy,
+    target_index_file: Option<String>,
+    mass_type: MassType
+}
+
+impl DecoyPoolArguments {
+    pub fn get_min_mass(&self) -> i64 {
+        return self.min_mass;
+    }
+
+    pub fn get_max_mass(&self) -> i64 {
+        return self.max_mass;
+    }
+
+    pub fn get_bin_width(&self) -> i64 {
+        return self.bin_width;
+    }
+
+    pub fn get_decoys_per_bin(&self) -> usize {
+        return self.decoys_per_bin;
+    }
+
+    pub fn get_thread_count(&self) -> usize {
+        return self.thread_count;
+    }
+
+    pub fn get_max_time_per_bin(&self) -> i64 {
+        return self.max_time_per_bin;
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        return self.seed;
+    }
+
+    pub fn get_generation_strategy(&self) -> GenerationStrategy {
+        return self.generation_strategy;
+    }
+
+    pub fn get_target_index_file(&self) -> Option<&str> {
+        return match self.target_index_file {
+            Some(ref file) => Some(file.as_str()),
+            None => None
+        };
+    }
+
+    pub fn get_mass_type(&self) -> MassType {
+        return self.mass_type;
+    }
+
+    pub fn from_cli_args(cli_args: &clap::ArgMatches) -> Self {
+        let min_mass: f64 = match cli_args.value_of("MIN_MASS") {
+            Some(number_string) => match number_string.to_owned().parse::<f64>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::decoy_pool::DecoyPoolArguments.from_cli_args(): could not cast min-mass to float")
+            },
+            None => 500.0
+        };
+        let max_mass: f64 = match cli_args.value_of("MAX_MASS") {
+            Some(number_string) => match number_string.to_owned().parse::<f64>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::decoy_pool::DecoyPoolArguments.from_cli_args(): could not cast max-mass to float")
+            },
+            None => 6000.0
+        };
+        let bin_width: f64 = match cli_args.value_of("BIN_WIDTH") {
+            Some(number_string) => match number_string.to_owned().parse::<f64>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::decoy_pool::DecoyPoolArguments.from_cli_args(): could not cast bin-width to float")
+            },
+            None => 1.0
+        };
+        if (bin_width <= 0.0) | (min_mass >= max_mass) {
+            panic!("proteomic::tasks::decoy_pool::DecoyPoolArguments.from_cli_args(): bin-width must be greater than 0 and min-mass less than max-mass");
+        }
+        let decoys_per_bin: usize = match cli_args.value_of("DECOYS_PER_BIN") {
+            Some(number_string) => match number_string.to_owned().parse::<usize>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::decoy_pool::DecoyPoolArguments.from_cli_args(): could not cast decoys-per-bin to unsigned integer")
+            },
+            None => 1000
+        };
+        let thread_count: usize = match cli_args.value_of("THREAD_COUNT") {
+            Some(number_string) => match number_string.to_owned().parse::<usize>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::decoy_pool::DecoyPoolArguments.from_cli_args(): could not cast thread-count to unsigned integer")
+            },
+            None => 2
+        };
+        let max_time_per_bin: i64 = match cli_args.value_of("MAX_TIME_PER_BIN") {
+            Some(number_string) => match number_string.to_owned().parse::<i64>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::decoy_pool::DecoyPoolArguments.from_cli_args(): could not cast max-time-per-bin to integer")
+            },
+            None => 60
+        };
+        let seed: u64 = match cli_args.value_of("SEED") {
+            Some(number_string) => match number_string.to_owned().parse::<u64>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::decoy_pool::DecoyPoolArguments.from_cli_args(): could not cast seed to unsigned integer (64 bit)")
+            },
+            None => rand::random::<u64>()
+        };
+        let generation_strategy: GenerationStrategy = match cli_args.value_of("GENERATION_STRATEGY") {
+            Some(strategy_name) => GenerationStrategy::from_str(strategy_name),
+            None => GenerationStrategy::RandomWalk
+        };
+        return Self {
+            min_mass: mass::convert_mass_to_int(min_mass),
+            max_mass: mass::convert_mass_to_int(max_mass),
+            bin_width: mass::convert_mass_to_int(bin_width),
+            decoys_per_bin: decoys_per_bin,
+            thread_count: thread_count,
+            max_time_per_bin: max_time_per_bin,
+            seed: seed,
+            generation_strategy: generation_strategy,
+            target_index_file: match cli_args.value_of("TARGET_INDEX_FILE") {
+                Some(file) => Some(file.to_owned()),
+                None => None
+            },
+            mass_type: MassType::from_str(cli_args.value_of("MASS_TYPE").unwrap_or("mono"))
+        }
+    }
+}
+
+/// Fills the decoys-table with unmodified decoys, so each mass bin between min and max mass holds the given number of decoys.
+/// The decoys already stored in a bin are counted, so an interrupted build continues where it stopped.
+/// Modifications are applied during the identification, like for decoys found in the database.
+/// With average masses the bins are filled by the average mass of the decoys, while the stored weights stay monoisotopic.
+pub fn build_decoy_pool_task(decoy_pool_args: &DecoyPoolArguments) {
+    let conn = DatabaseConnection::get_database_connection();
+    let target_index: Option<Arc<TargetIndex>> = match decoy_pool_args.get_target_index_file() {
+        Some(target_index_file) => Some(Arc::new(TargetIndex::load_for_database(target_index_file, &conn))),
+        None => None
+    };
+    let no_modifications: ModificationRegistry = ModificationRegistry::new();
+    let bin_condition: String = get_bin_condition(decoy_pool_args.get_mass_type());
+    let mut incomplete_bins: usize = 0;
+    println!("seed: {}", decoy_pool_args.get_seed());
+    let mut bin_start: i64 = decoy_pool_args.get_min_mass();
+    while bin_start < decoy_pool_args.get_max_mass() {
+        let bin_end: i64 = std::cmp::min(bin_start + decoy_pool_args.get_bin_width(), decoy_pool_args.get_max_mass());
+        let mut number_of_stored_decoys: usize = count_bin_decoys(&conn, bin_condition.as_str(), bin_start, bin_end);
+        if number_of_stored_decoys >= decoy_pool_args.get_decoys_per_bin() {
+            println!("bin {} - {} Da: {} decoys already stored", mass::convert_mass_to_float(bin_start), mass::convert_mass_to_float(bin_end), number_of_stored_decoys);
+            bin_start = bin_end;
+            continue;
+        }
+        // generated decoys which are already stored, e.g. by an identification, do not fill the bin, so the bin is counted again after each generation
+        while number_of_stored_decoys < decoy_pool_args.get_decoys_per_bin() {
+            let number_of_missing_decoys: usize = decoy_pool_args.get_decoys_per_bin() - number_of_stored_decoys;
+            let mut generator: DecoyGenerator = DecoyGenerator::new(
+                bin_start + (bin_end - bin_start) / 2,
+                bin_start,
+                bin_end - 1,
+                decoy_pool_args.get_thread_count(),
+                0,
+                no_modifications.get_fixed_modification_map(),
+                no_modifications.get_variable_modification_map(),
+                decoy_pool_args.get_max_time_per_bin()
+            );
+            // the seed of a bin depends on the run seed, the bin start and the number of stored decoys, so a build is reproducible
+            // and a resumed build or a further generation for the bin does not draw the decoys again which are already stored
+            generator.set_seed(DecoyGenerator::derive_seed(DecoyGenerator::derive_seed(decoy_pool_args.get_seed(), bin_start as u64), number_of_stored_decoys as u64));
+            generator.set_generation_strategy(decoy_pool_args.get_generation_strategy());
+            generator.set_mass_type(decoy_pool_args.get_mass_type());
+            if let Some(ref target_index) = target_index {
+                generator.set_target_index(target_index.clone());
+            }
+            let start_time: f64 = time::precise_time_s();
+            let generation_result: GenerationResult = generator.generate_decoys(number_of_missing_decoys);
+            let stop_time: f64 = time::precise_time_s();
+            let number_of_previously_stored_decoys: usize = number_of_stored_decoys;
+            number_of_stored_decoys = count_bin_decoys(&conn, bin_condition.as_str(), bin_start, bin_end);
+            let number_of_new_decoys: usize = number_of_stored_decoys.saturating_sub(number_of_previously_stored_decoys);
+            println!("bin {} - {} Da: stored {} of {} missing decoys in {} s", mass::convert_mass_to_float(bin_start), mass::convert_mass_to_float(bin_end), number_of_new_decoys, number_of_missing_decoys, stop_time - start_time);
+            if !generation_result.is_success() {
+                println!("{}", generation_result.get_statistics().to_string());
+                incomplete_bins += 1;
+                break;
+            }
+            // the generator found only stored decoys, so a further generation with the same seed would not fill the bin either
+            if number_of_new_decoys == 0 {
+                println!("bin {} - {} Da: all generated decoys were already stored", mass::convert_mass_to_float(bin_start), mass::convert_mass_to_float(bin_end));
+                incomplete_bins += 1;
+                break;
+            }
+        }
+        bin_start = bin_end;
+    }
+    if incomplete_bins > 0 {
+        println!("{} bins are not filled completely, run the build again to continue", incomplete_bins);
+    }
+}
+
+/// Returns the condition for the decoys of a bin. The weights in the database are monoisotopic,
+/// so for average masses the mass is calculated from the amino acid counts.
+fn get_bin_condition(mass_type: MassType) -> String {
+    return match mass_type {
+        MassType::Monoisotopic => DECOY_POOL_BIN_CONDITION.to_owned(),
+        MassType::Average => {
+            let mut average_mass: String = format!("{}", NeutralLoss::get("H2O").get_average_mass());
+            for amino_acid_one_letter_code in AMINO_ACIDS_FOR_COUNTING.iter() {
+                average_mass.push_str(format!(" + {}_count * {}", amino_acid_one_letter_code.to_ascii_lowercase(), AminoAcid::get(*amino_acid_one_letter_code).get_average_mass()).as_str());
+            }
+            format!("({}) >= $1 AND ({}) < $2", average_mass, average_mass)
+        }
+    };
+}
+
+fn count_bin_decoys(conn: &postgres::Connection, bin_condition: &str, bin_start: i64, bin_end: i64) -> usize {
+    return match Decoy::count_where(conn, bin_condition, &[&bin_start, &bin_end]) {
+        Ok(count) => count as usize,
+        Err(err) => panic!("proteomic::tasks::decoy_pool::build_decoy_pool_task(): could not count decoys: {}", err)
+    };
+}
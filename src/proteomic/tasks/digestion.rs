@@ -1,5 +1,6 @@
 use proteomic::utility::input_file_digester::file_digester::FileDigester;
 use proteomic::utility::input_file_digester::fasta_digester::FastaDigester;
+use proteomic::tasks::target_index::{build_target_index_task, TargetIndexArguments};
 
 pub struct DigestionArguments {
     input_file: String,
@@ -8,7 +9,8 @@ pub struct DigestionArguments {
     number_of_missed_cleavages: u8,
     min_peptide_length: usize,
     max_peptide_length: usize,
-    enzym_name: String
+    enzym_name: String,
+    target_index_file: Option<String>
 }
 
 impl DigestionArguments {
@@ -40,6 +42,13 @@ impl DigestionArguments {
         return self.enzym_name.as_str();
     }
 
+    pub fn get_target_index_file(&self) -> Option<&str> {
+        return match self.target_index_file {
+            Some(ref file) => Some(file.as_str()),
+            None => None
+        };
+    }
+
     pub fn from_cli_args(cli_args: &clap::ArgMatches) -> Self {
         let input_file: &str = match cli_args.value_of("INPUT_FILE") {
             Some(file) => file,
@@ -118,7 +127,11 @@ impl DigestionArguments {
             number_of_missed_cleavages: number_of_missed_cleavages,
             min_peptide_length: min_peptide_length,
             max_peptide_length: max_peptide_length,
-            enzym_name: enzym_name.to_owned()
+            enzym_name: enzym_name.to_owned(),
+            target_index_file: match cli_args.value_of("TARGET_INDEX_FILE") {
+                Some(file) => Some(file.to_owned()),
+                None => None
+            }
         }
     }
 }
@@ -132,5 +145,9 @@ pub fn digest_to_database_task(digestion_arguments: &DigestionArguments) {
         digestion_arguments.get_max_peptide_length()
     );
     let seconds = digester.process_file(digestion_arguments.get_enzym_name());
-    println!("need {} days", seconds / 60.0 / 60.0 / 24.0)
+    println!("need {} days", seconds / 60.0 / 60.0 / 24.0);
+    // index all targets, so the decoy generation does not need to ask the database for each decoy
+    if let Some(target_index_file) = digestion_arguments.get_target_index_file() {
+        build_target_index_task(&TargetIndexArguments::new(target_index_file, 0.01));
+    }
 }
\ No newline at end of file
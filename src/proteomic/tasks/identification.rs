@@ -2,23 +2,36 @@ use std::collections::{HashMap, HashSet};
 use std::io::prelude::*;
 use std::io::LineWriter;
 use std::fs::OpenOptions;
+use std::sync::Arc;
 use std::path::PathBuf;
 
 
-use proteomic::models::amino_acids::amino_acid::AminoAcid;
-use proteomic::models::amino_acids::modification::Modification;
+use proteomic::models::amino_acids::amino_acid::{AminoAcid, AMINO_ACIDS_FOR_COUNTING};
+use proteomic::models::amino_acids::modification_registry::ModificationRegistry;
 use proteomic::utility::mz_ml::mz_ml_reader::MzMlReader;
-use proteomic::utility::decoy_generator::{DecoyGenerator, GenerationResult};
+use proteomic::utility::decoy_generator::{DecoyGenerator, GenerationResult, GenerationStatus, GenerationStrategy};
+use proteomic::utility::generation_statistics::{self, GenerationStatistics};
+use proteomic::utility::length_distribution::LengthDistribution;
+use proteomic::utility::mass_fitting::MassFittingOptions;
+use proteomic::utility::similarity_filter::SimilarityFilter;
+use proteomic::utility::property_filter::PropertyFilter;
+use proteomic::utility::amino_acid_composition::AminoAcidComposition;
+use proteomic::utility::target_index::TargetIndex;
+use proteomic::utility::decoy_cache::DecoyCache;
 use proteomic::utility::database_connection::DatabaseConnection;
 use proteomic::models::persistable::Persistable;
 use proteomic::models::peptides::peptide::Peptide;
 use proteomic::models::peptides::peptide_interface::PeptideInterface;
 use proteomic::models::peptides::modified_peptide::ModifiedPeptide;
 use proteomic::models::peptides::decoy::Decoy;
+use proteomic::models::peptides::peptide_properties::PeptideProperty;
 use proteomic::models::mass;
+use proteomic::models::mass::mass_type::MassType;
+use proteomic::models::mass::neutral_loss::NeutralLoss;
 use proteomic::models::fasta_entry::FastaEntry;
 use proteomic::utility;
 use proteomic::utility::comet_parameter;
+use proteomic::utility::peff::{self, PeffFormat};
 
 
 const TARGET_DECOY_QUERY_CONDITION: &str = "weight BETWEEN $1 AND $2";
@@ -26,6 +39,8 @@ const TARGET_DECOY_QUERY_CONDITION: &str = "weight BETWEEN $1 AND $2";
 
 pub struct IdentificationArguments {
     modification_csv_file: String,
+    unimod_file: String,
+    unimod_modifications: Vec<String>,
     spectrum_file: String,
     max_number_of_variable_modification_per_decoy: u8,
     number_of_decoys: usize,
@@ -34,7 +49,26 @@ pub struct IdentificationArguments {
     fragmentation_tolerance: f64,
     thread_count: usize,
     max_time_for_decoy_generation: i64,
-    comet_revision: String
+    comet_revision: String,
+    seed: u64,
+    generation_strategy: GenerationStrategy,
+    decoy_length_distribution: String,
+    min_peptide_length: usize,
+    max_peptide_length: usize,
+    similarity_max_edit_distance: Option<usize>,
+    similarity_max_shared_fragment_ratio: Option<f64>,
+    similarity_kmer_length: Option<usize>,
+    match_target_composition: bool,
+    target_index_file: Option<String>,
+    lookup_only: bool,
+    enumerate_isoforms: bool,
+    peff_format: Option<PeffFormat>,
+    peff_obo: String,
+    mass_type: MassType,
+    max_decoy_reuse: Option<usize>,
+    mass_fitting_options: MassFittingOptions,
+    target_properties: Vec<PeptideProperty>,
+    property_margin: f64
 }
 
 impl IdentificationArguments {
@@ -42,6 +76,15 @@ impl IdentificationArguments {
         return self.modification_csv_file.as_str();
     }
 
+    pub fn get_unimod_file(&self) -> &str {
+        return self.unimod_file.as_str();
+    }
+
+    /// Returns Unimod references like `UNIMOD:35@M variable`
+    pub fn get_unimod_modifications(&self) -> &Vec<String> {
+        return &self.unimod_modifications;
+    }
+
     pub fn get_spectrum_file(&self) -> &str {
         return self.spectrum_file.as_str();
     }
@@ -78,13 +121,108 @@ impl IdentificationArguments {
         return self.comet_revision.as_str();
     }
 
+    pub fn get_seed(&self) -> u64 {
+        return self.seed;
+    }
+
+    pub fn get_generation_strategy(&self) -> GenerationStrategy {
+        return self.generation_strategy;
+    }
+
+    pub fn get_decoy_length_distribution(&self) -> &str {
+        return self.decoy_length_distribution.as_str();
+    }
+
+    pub fn get_min_peptide_length(&self) -> usize {
+        return self.min_peptide_length;
+    }
+
+    pub fn get_max_peptide_length(&self) -> usize {
+        return self.max_peptide_length;
+    }
+
+    pub fn get_similarity_max_edit_distance(&self) -> Option<usize> {
+        return self.similarity_max_edit_distance;
+    }
+
+    pub fn get_similarity_max_shared_fragment_ratio(&self) -> Option<f64> {
+        return self.similarity_max_shared_fragment_ratio;
+    }
+
+    pub fn get_similarity_kmer_length(&self) -> Option<usize> {
+        return self.similarity_kmer_length;
+    }
+
+    pub fn get_match_target_composition(&self) -> bool {
+        return self.match_target_composition;
+    }
+
+    pub fn get_target_index_file(&self) -> Option<&str> {
+        return match self.target_index_file {
+            Some(ref file) => Some(file.as_str()),
+            None => None
+        };
+    }
+
+    pub fn get_lookup_only(&self) -> bool {
+        return self.lookup_only;
+    }
+
+    pub fn get_enumerate_isoforms(&self) -> bool {
+        return self.enumerate_isoforms;
+    }
+
+    /// Controlled vocabulary of the PEFF-output, None for FASTA-output
+    pub fn get_peff_format(&self) -> Option<PeffFormat> {
+        return self.peff_format;
+    }
+
+    pub fn get_peff_obo(&self) -> &str {
+        return self.peff_obo.as_str();
+    }
+
+    /// Mass type of the precursor and fragment masses
+    pub fn get_mass_type(&self) -> MassType {
+        return self.mass_type;
+    }
+
+    pub fn get_max_decoy_reuse(&self) -> Option<usize> {
+        return self.max_decoy_reuse;
+    }
+
+    pub fn get_mass_fitting_options(&self) -> &MassFittingOptions {
+        return &self.mass_fitting_options;
+    }
+
+    pub fn get_target_properties(&self) -> &Vec<PeptideProperty> {
+        return &self.target_properties;
+    }
+
+    pub fn get_property_margin(&self) -> f64 {
+        return self.property_margin;
+    }
+
 
 
     pub fn from_cli_args(cli_args: &clap::ArgMatches) -> IdentificationArguments {
         let modification_csv_file: &str = match cli_args.value_of("MODIFICATION_FILE") {
             Some(modification_csv_file) => modification_csv_file,
-            None => panic!("proteomic::tasks::identification::parse_identification_cli_arguments(): you must specify a modification-file")
+            None => ""
+        };
+        let unimod_file: &str = match cli_args.value_of("UNIMOD_FILE") {
+            Some(unimod_file) => unimod_file,
+            None => ""
         };
+        let unimod_modifications: Vec<String> = match cli_args.values_of("UNIMOD_MODIFICATION") {
+            Some(references) => references.map(|reference| reference.to_owned()).collect(),
+            None => Vec::new()
+        };
+        if modification_csv_file.is_empty() & unimod_modifications.is_empty() {
+            panic!("proteomic::tasks::identification::parse_identification_cli_arguments(): you must specify a modification-file or Unimod modifications");
+        }
+        if unimod_file.is_empty() & !unimod_modifications.is_empty() {
+            panic!("proteomic::tasks::identification::parse_identification_cli_arguments(): you must specify a unimod-file to use Unimod modifications");
+        }
         let spectrum_file: &str = match cli_args.value_of("SPECTRUM_FILE") {
             Some(spectrum_file) => spectrum_file,
             None => panic!("proteomic::tasks::identification::parse_identification_cli_arguments(): you must specify a spectrum-file")
@@ -142,8 +280,78 @@ impl IdentificationArguments {
             Some(comet_revision) => comet_revision,
             None => panic!("proteomic::tasks::identification::parse_identification_cli_arguments(): you must specify a comet-revision")
         };
+        let seed: u64 = match cli_args.value_of("SEED") {
+            Some(number_string) => match number_string.to_owned().parse::<u64>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::identification::parse_identification_cli_arguments(): could not cast seed to unsigned integer (64 bit)")
+            },
+            None => rand::random::<u64>()
+        };
+        let generation_strategy: GenerationStrategy = match cli_args.value_of("GENERATION_STRATEGY") {
+            Some(strategy_name) => GenerationStrategy::from_str(strategy_name),
+            None => GenerationStrategy::RandomWalk
+        };
+        let decoy_length_distribution: &str = match cli_args.value_of("DECOY_LENGTH_DISTRIBUTION") {
+            Some(decoy_length_distribution) => decoy_length_distribution,
+            None => "none"
+        };
+        let min_peptide_length: usize = match cli_args.value_of("MIN_PEPTIDE_LENGTH") {
+            Some(number_string) => match number_string.to_owned().parse::<usize>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::identification::parse_identification_cli_arguments(): could not cast minimum-peptide-length to unsigned integer")
+            },
+            None => 5
+        };
+        let max_peptide_length: usize = match cli_args.value_of("MAX_PEPTIDE_LENGTH") {
+            Some(number_string) => match number_string.to_owned().parse::<usize>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::identification::parse_identification_cli_arguments(): could not cast maximum-peptide-length to unsigned integer")
+            },
+            None => 50
+        };
+        let similarity_max_edit_distance: Option<usize> = match cli_args.value_of("SIMILARITY_MAX_EDIT_DISTANCE") {
+            Some(number_string) => match number_string.to_owned().parse::<usize>() {
+                Ok(number) => Some(number),
+                Err(_) => panic!("proteomic::tasks::identification::parse_identification_cli_arguments(): could not cast similarity-max-edit-distance to unsigned integer")
+            },
+            None => None
+        };
+        let similarity_max_shared_fragment_ratio: Option<f64> = match cli_args.value_of("SIMILARITY_MAX_SHARED_FRAGMENT_RATIO") {
+            Some(number_string) => match number_string.to_owned().parse::<f64>() {
+                Ok(number) => Some(number),
+                Err(_) => panic!("proteomic::tasks::identification::parse_identification_cli_arguments(): could not cast similarity-max-shared-fragment-ratio to float")
+            },
+            None => None
+        };
+        let similarity_kmer_length: Option<usize> = match cli_args.value_of("SIMILARITY_KMER_LENGTH") {
+            Some(number_string) => match number_string.to_owned().parse::<usize>() {
+                Ok(number) => Some(number),
+                Err(_) => panic!("proteomic::tasks::identification::parse_identification_cli_arguments(): could not cast similarity-kmer-length to unsigned integer")
+            },
+            None => None
+        };
+        let max_decoy_reuse: Option<usize> = match cli_args.value_of("MAX_DECOY_REUSE") {
+            Some(number_string) => match number_string.to_owned().parse::<usize>() {
+                Ok(number) => Some(number),
+                Err(_) => panic!("proteomic::tasks::identification::parse_identification_cli_arguments(): could not cast max-decoy-reuse to unsigned integer")
+            },
+            None => None
+        };
+        let target_properties: Vec<PeptideProperty> = match cli_args.value_of("MATCH_TARGET_PROPERTIES") {
+            Some(property_names) => property_names.split(",").map(|property_name| PeptideProperty::from_str(property_name)).collect(),
+            None => Vec::new()
+        };
+        let property_margin: f64 = match cli_args.value_of("PROPERTY_MARGIN") {
+            Some(number_string) => match number_string.to_owned().parse::<f64>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::tasks::identification::parse_identification_cli_arguments(): could not cast property-margin to float")
+            },
+            None => 0.1
+        };
         return Self {
             modification_csv_file: modification_csv_file.to_owned(),
+            unimod_file: unimod_file.to_owned(),
+            unimod_modifications: unimod_modifications,
             spectrum_file: spectrum_file.to_owned(),
             max_number_of_variable_modification_per_decoy: max_number_of_variable_modification_per_decoy,
             number_of_decoys: number_of_decoys,
@@ -152,47 +360,84 @@ impl IdentificationArguments {
             fragmentation_tolerance: fragmentation_tolerance,
             thread_count: thread_count,
             max_time_for_decoy_generation: max_time_for_decoy_generation,
-            comet_revision: comet_revision.to_owned()
+            comet_revision: comet_revision.to_owned(),
+            seed: seed,
+            generation_strategy: generation_strategy,
+            decoy_length_distribution: decoy_length_distribution.to_owned(),
+            min_peptide_length: min_peptide_length,
+            max_peptide_length: max_peptide_length,
+            similarity_max_edit_distance: similarity_max_edit_distance,
+            similarity_max_shared_fragment_ratio: similarity_max_shared_fragment_ratio,
+            similarity_kmer_length: similarity_kmer_length,
+            match_target_composition: cli_args.is_present("MATCH_TARGET_COMPOSITION"),
+            target_index_file: match cli_args.value_of("TARGET_INDEX_FILE") {
+                Some(file) => Some(file.to_owned()),
+                None => None
+            },
+            lookup_only: cli_args.is_present("LOOKUP_ONLY"),
+            enumerate_isoforms: cli_args.is_present("ENUMERATE_ISOFORMS"),
+            peff_format: match cli_args.value_of("PEFF_FORMAT") {
+                Some(format_name) => Some(PeffFormat::from_str(format_name)),
+                None => None
+            },
+            peff_obo: cli_args.value_of("PEFF_OBO").unwrap_or("").to_owned(),
+            mass_type: MassType::from_str(cli_args.value_of("MASS_TYPE").unwrap_or("mono")),
+            max_decoy_reuse: max_decoy_reuse,
+            mass_fitting_options: MassFittingOptions::from_cli_args(cli_args, min_peptide_length, max_peptide_length),
+            target_properties: target_properties,
+            property_margin: property_margin
         }
     }
 }
 
 pub fn identification_task(identification_args: &IdentificationArguments) {
     let conn = DatabaseConnection::get_database_connection();
+    // load the target index once for all spectra
+    let target_index: Option<Arc<TargetIndex>> = match identification_args.get_target_index_file() {
+        Some(target_index_file) => Some(Arc::new(TargetIndex::load_for_database(target_index_file, &conn))),
+        None => None
+    };
     // prepare modifications
-    let mods = Modification::create_from_csv_file(identification_args.get_modification_csv_file());
-    let mut fixed_modifications_map: HashMap<char, Modification> = HashMap::new();
-    let mut variable_modifications_map: HashMap<char, Modification> = HashMap::new();
-    for modification in mods.iter() {
-        if modification.is_fix() {
-            fixed_modifications_map.insert(modification.get_amino_acid_one_letter_code(), modification.clone());
-        } else {
-            variable_modifications_map.insert(modification.get_amino_acid_one_letter_code(), modification.clone());
+    let mut modification_registry = match identification_args.get_modification_csv_file() {
+        "" => ModificationRegistry::new(),
+        modification_file => match ModificationRegistry::from_file(modification_file) {
+            Ok(modification_registry) => modification_registry,
+            Err(err) => panic!("proteomic::tasks::identification::identification_task(): {}", err)
         }
+    };
+    if !identification_args.get_unimod_modifications().is_empty() {
+        modification_registry.add_unimod_modifications(identification_args.get_unimod_file(), identification_args.get_unimod_modifications());
     }
-    let mut sorted_modifyable_amino_acids_set: HashSet<char> = fixed_modifications_map.keys().map(|key| *key).collect();
-    for (key, _) in variable_modifications_map.iter() {
-        sorted_modifyable_amino_acids_set.insert(*key);
-    }
-    let mut sorted_modifyable_amino_acids: Vec<char> = sorted_modifyable_amino_acids_set.drain().collect();
-    sorted_modifyable_amino_acids.sort();
-    // prepare condition for target and decoys
-    let mut target_decoy_condition = TARGET_DECOY_QUERY_CONDITION.to_owned();
-    if sorted_modifyable_amino_acids.len() > 0 {
-        target_decoy_condition.push_str(" AND ");
-        let mut conditions: Vec<String> = Vec::new();
-        for (idx, amino_acid_one_letter_code) in sorted_modifyable_amino_acids.iter().enumerate() {
-            conditions.push(format!("{}_count = ${}", amino_acid_one_letter_code.to_ascii_lowercase(), idx + 3));
+    // without average mass a modification would be searched with its mono mass, so it is rejected before the search
+    if identification_args.get_mass_type() == MassType::Average {
+        if let Err(err) = modification_registry.check_average_masses() {
+            panic!("proteomic::tasks::identification::identification_task(): {}", err);
         }
-        target_decoy_condition.push_str(conditions.join(" AND ").as_str());
     }
-    // merge modifications for creating queries
-    let mut modifications_map: HashMap<char, &Modification> = HashMap::new();
-    for (key, modification_ref) in fixed_modifications_map.iter() {
-        modifications_map.insert(*key, modification_ref);
+    // each channel of a label set is searched with its labels as fixed modifications, without label set there is one unnamed channel
+    let mut channels: Vec<SearchChannel> = if modification_registry.has_label_channels() {
+        modification_registry.get_label_channels().iter().map(|label_channel| SearchChannel::new(Some(label_channel.get_name().to_owned()), modification_registry.get_channel_registry(label_channel), identification_args.get_max_decoy_reuse(), identification_args.get_mass_type())).collect()
+    } else {
+        vec![SearchChannel::new(None, modification_registry.clone(), identification_args.get_max_decoy_reuse(), identification_args.get_mass_type())]
+    };
+    // PEFF-annotations need accessions of the format's vocabulary, so modifications without one are rejected before the search
+    if let Some(peff_format) = identification_args.get_peff_format() {
+        for channel in channels.iter() {
+            if let Err(err) = peff::check_modifications(&channel.registry, peff_format) {
+                panic!("proteomic::tasks::identification::identification_task(): {}", err);
+            }
+        }
     }
-    for (key, modification_ref) in variable_modifications_map.iter() {
-        modifications_map.insert(*key, modification_ref);
+    // each channel is searched with its own database and parameters, so the channel names must be unique in the filenames
+    {
+        let mut filename_safe_channel_names: HashMap<String, &str> = HashMap::new();
+        for channel in channels.iter() {
+            if let Some(ref channel_name) = channel.name {
+                if let Some(other_channel_name) = filename_safe_channel_names.insert(get_filename_safe_channel_name(channel_name), channel_name.as_str()) {
+                    panic!("proteomic::tasks::identification::identification_task(): label channels '{}' and '{}' result in the same filename", other_channel_name, channel_name);
+                }
+            }
+        }
     }
     // initialize mzML-Reader
     let mz_ml_reader = MzMlReader::new(identification_args.get_spectrum_file());
@@ -210,184 +455,362 @@ pub fn identification_task(identification_args: &IdentificationArguments) {
             mass::convert_mass_to_int(mass::thomson_to_dalton(*spectrum.get_mass_to_charge_ratio() + mass_to_charge_tolerances.1, *spectrum.get_charge()))
         );
         println!("precursor_tolerance => ({}, {})", precursor_tolerance.0, precursor_tolerance.1);
-        // build array with maximal
-        let mut max_modification_counts: HashMap<char, i16> = HashMap::new();
-        for amino_acid_one_letter_code in sorted_modifyable_amino_acids.iter() {
-            let amino_acid = AminoAcid::get(*amino_acid_one_letter_code);
-            let max_modification_count: i16 = match modifications_map.get(&amino_acid_one_letter_code) {
-                Some(modification) => (precursor_mass / (amino_acid.get_mono_mass() + modification.get_mono_mass())) as i16,
-                None => continue
+        // the seed for this spectrum depends only on the run seed and the spectrum id, so it does not matter if the spectra are processed one by one or all at once
+        let spectrum_seed: u64 = DecoyGenerator::derive_seed_from_str(identification_args.get_seed(), spectrum.get_spectrum_id());
+        let mut decoy_generation_results: Vec<(Option<String>, GenerationResult)> = Vec::new();
+        let mut channel_seeds: Vec<(String, u64)> = Vec::new();
+        for channel in channels.iter_mut() {
+            // each channel has its own seed, so the decoys of the channels are independent of each other
+            let channel_seed: u64 = match channel.name {
+                Some(ref channel_name) => DecoyGenerator::derive_seed_from_str(spectrum_seed, channel_name.as_str()),
+                None => spectrum_seed
             };
-            max_modification_counts.insert(*amino_acid_one_letter_code, max_modification_count);
-        }
-        // get condition values
-        let mut condition_values: Vec<(i64, i64, Vec<i16>)> = Vec::new();
-        get_max_modifyable_amino_acid_counts(&modifications_map, precursor_tolerance, &sorted_modifyable_amino_acids, &max_modification_counts, 0, &mut Vec::new(), &mut condition_values);
-        // gether targets
-        println!("search targets and decoys in database...");
-        let mut targets: HashSet<FastaEntry> = HashSet::new();
-        let mut decoys: HashSet<FastaEntry> = HashSet::new();
-        let mut start_time: f64 = time::precise_time_s();
-        for query_values in condition_values {
-            let mut values: Vec<&postgres::types::ToSql> = Vec::new();
-            values.push(&query_values.0);
-            values.push(&query_values.1);
-            for count in query_values.2.iter() {
-                values.push(count);
+            if let Some(ref channel_name) = channel.name {
+                println!("search label channel '{}'...", channel_name);
+                channel_seeds.push((channel_name.clone(), channel_seed));
             }
-            let possible_targets = match Peptide::find_where(&conn, target_decoy_condition.as_str(), values.as_ref()) {
-                Ok(targets) => targets,
-                Err(err) => panic!("proteomic::tasks::identification::identification_task(): could not gether targets: {}", err)
-            };
-            for peptide in possible_targets {
-                #[allow(unused_assignments)] // `modified_target_fits_precursor_tolerance` is actually read in if-instruction below
-                let mut modified_target_fits_precursor_tolerance = false;
-                let mut modified_peptide = ModifiedPeptide::from_peptide(&peptide, precursor_mass,  precursor_tolerance.0,  precursor_tolerance.1, &fixed_modifications_map);
-                modified_target_fits_precursor_tolerance = modified_peptide.hits_mass_tolerance();
-                if !modified_target_fits_precursor_tolerance {
-                    modified_target_fits_precursor_tolerance = modified_peptide.try_variable_modifications(identification_args.get_max_number_of_variable_modification_per_decoy(), &variable_modifications_map);
+            let fixed_modifications_map = channel.registry.get_fixed_modification_map();
+            let variable_modifications_map = channel.registry.get_variable_modification_map();
+            let sorted_modifyable_amino_acids = &channel.sorted_modifyable_amino_acids;
+            let mass_shift_ranges = &channel.mass_shift_ranges;
+            let any_amino_acid_mass_shift_range: (i64, i64) = channel.any_amino_acid_mass_shift_range;
+            let has_protein_terminal_modifications: bool = channel.has_protein_terminal_modifications;
+            let target_decoy_condition = &channel.target_decoy_condition;
+            let decoy_cache = &mut channel.decoy_cache;
+            // build array with maximal
+            let mut max_modification_counts: HashMap<char, i16> = HashMap::new();
+            for amino_acid_one_letter_code in sorted_modifyable_amino_acids.iter() {
+                let amino_acid = AminoAcid::get(*amino_acid_one_letter_code);
+                let max_modification_count: i16 = match mass_shift_ranges.get(&amino_acid_one_letter_code) {
+                    Some(mass_shift_range) => (precursor_mass / (amino_acid.get_mass(identification_args.get_mass_type()) + mass_shift_range.0)) as i16,
+                    None => continue
+                };
+                max_modification_counts.insert(*amino_acid_one_letter_code, max_modification_count);
+            }
+            // get condition values
+            let mut condition_values: Vec<(i64, i64, Vec<i16>)> = Vec::new();
+            let unmodified_precursor_tolerance: (i64, i64) = (
+                precursor_tolerance.0 - any_amino_acid_mass_shift_range.1,
+                precursor_tolerance.1 - any_amino_acid_mass_shift_range.0
+            );
+            if sorted_modifyable_amino_acids.is_empty() {
+                condition_values.push((unmodified_precursor_tolerance.0, unmodified_precursor_tolerance.1, Vec::new()));
+            } else {
+                get_max_modifyable_amino_acid_counts(&mass_shift_ranges, unmodified_precursor_tolerance, &sorted_modifyable_amino_acids, &max_modification_counts, 0, &mut Vec::new(), &mut condition_values);
+            }
+            // the weights in the database are monoisotopic, so the average masses of the unmodified sequences are converted to their possible weights
+            if identification_args.get_mass_type() == MassType::Average {
+                for query_values in condition_values.iter_mut() {
+                    let weight_range: (i64, i64) = get_monoisotopic_weight_range((query_values.0, query_values.1));
+                    query_values.0 = weight_range.0;
+                    query_values.1 = weight_range.1;
                 }
-                if modified_target_fits_precursor_tolerance {
-                    targets.insert(
-                        FastaEntry::new(
-                            peptide.get_header_with_modification_summary(modified_peptide.get_modification_summary_for_header().as_str()).as_str(),
-                            peptide.get_aa_sequence()
-                        )
-                    );
+            }
+            // gether targets
+            println!("search targets and decoys in database...");
+            let mut targets: HashSet<FastaEntry> = HashSet::new();
+            let mut decoys: HashSet<FastaEntry> = HashSet::new();
+            // the headers of the same decoy differ between the sources, so decoys are deduplicated by sequence
+            let mut decoy_sequences: HashSet<String> = HashSet::new();
+            let mut target_composition: AminoAcidComposition = AminoAcidComposition::new();
+            let mut start_time: f64 = time::precise_time_s();
+            if let Some(ref decoy_cache) = *decoy_cache {
+                for decoy in decoy_cache.get_decoys_in_window(precursor_tolerance.0, precursor_tolerance.1, identification_args.get_number_of_decoys()) {
+                    decoy_sequences.insert(decoy.get_aa_sequence().to_owned());
+                    decoys.insert(decoy);
                 }
+                println!("reuse {} decoys of previous spectra", decoys.len());
             }
-            if decoys.len() < identification_args.get_number_of_decoys() {
-                let mut possible_decoys = match Decoy::find_where(&conn, target_decoy_condition.as_str(), values.as_ref()) {
-                    Ok(count) => count,
-                    Err(err) => panic!("proteomic::tasks::identification::identification_task(): could not gether decoy: {}", err)
+            for query_values in condition_values {
+                let mut values: Vec<&postgres::types::ToSql> = Vec::new();
+                values.push(&query_values.0);
+                values.push(&query_values.1);
+                for count in query_values.2.iter() {
+                    values.push(count);
+                }
+                // the protein termini are only fetched if a modification depends on them
+                let possible_targets: Vec<(Peptide, bool, bool)> = if has_protein_terminal_modifications {
+                    match Peptide::find_where_with_protein_termini(&conn, target_decoy_condition.as_str(), values.as_ref()) {
+                        Ok(targets) => targets,
+                        Err(err) => panic!("proteomic::tasks::identification::identification_task(): could not gether targets: {}", err)
+                    }
+                } else {
+                    match Peptide::find_where(&conn, target_decoy_condition.as_str(), values.as_ref()) {
+                        Ok(targets) => targets.into_iter().map(|target| (target, false, false)).collect(),
+                        Err(err) => panic!("proteomic::tasks::identification::identification_task(): could not gether targets: {}", err)
+                    }
                 };
-                for decoy in possible_decoys.iter_mut() {
-                    #[allow(unused_assignments)] // `modified_decoys_fits_precursor_tolerance` is actually read in if-instruction below
-                    let mut modified_decoys_fits_precursor_tolerance = false;
-                    let mut modified_decoy = ModifiedPeptide::from_decoy(&decoy, precursor_mass,  precursor_tolerance.0,  precursor_tolerance.1, &fixed_modifications_map);
-                    modified_decoys_fits_precursor_tolerance = modified_decoy.hits_mass_tolerance();
-                    if !modified_decoys_fits_precursor_tolerance {
-                        modified_decoys_fits_precursor_tolerance = modified_decoy.try_variable_modifications(identification_args.get_max_number_of_variable_modification_per_decoy(), &variable_modifications_map);
+                for (peptide, is_protein_n_terminus, is_protein_c_terminus) in possible_targets {
+                    #[allow(unused_assignments)] // `modified_target_fits_precursor_tolerance` is actually read in if-instruction below
+                    let mut modified_target_fits_precursor_tolerance = false;
+                    let mut modified_peptide = ModifiedPeptide::from_peptide(&peptide, precursor_mass,  precursor_tolerance.0,  precursor_tolerance.1, fixed_modifications_map);
+                    if has_protein_terminal_modifications {
+                        modified_peptide.set_protein_termini(is_protein_n_terminus, is_protein_c_terminus, fixed_modifications_map);
+                    }
+                    modified_peptide.set_mass_type(identification_args.get_mass_type());
+                    // every isoform which fits the precursor tolerance in ProForma notation
+                    let mut isoforms: Vec<String> = Vec::new();
+                    modified_target_fits_precursor_tolerance = modified_peptide.hits_mass_tolerance();
+                    if !modified_target_fits_precursor_tolerance {
+                        if identification_args.get_enumerate_isoforms() {
+                            isoforms = modified_peptide.enumerate_variable_modification_isoforms(identification_args.get_max_number_of_variable_modification_per_decoy(), variable_modifications_map);
+                            modified_target_fits_precursor_tolerance = !isoforms.is_empty();
+                        } else {
+                            modified_target_fits_precursor_tolerance = modified_peptide.try_variable_modifications(identification_args.get_max_number_of_variable_modification_per_decoy(), variable_modifications_map);
+                        }
                     }
-                    if modified_decoys_fits_precursor_tolerance {
-                        decoy.set_modification_summary(modified_decoy.get_modification_summary_for_header().as_str());
-                        decoys.insert(
+                    if modified_target_fits_precursor_tolerance {
+                        target_composition.add_peptide(&peptide);
+                        let mut header = peptide.get_header_with_modification_summary(modified_peptide.get_modification_summary_for_header().as_str(), modified_peptide.to_proforma().as_str());
+                        if !isoforms.is_empty() {
+                            header.push_str(format!(" Isoforms={}", isoforms.join(",")).as_str());
+                        }
+                        targets.insert(
                             FastaEntry::new(
-                                decoy.get_header().as_str(),
-                                decoy.get_aa_sequence()
+                                header.as_str(),
+                                peptide.get_aa_sequence()
                             )
                         );
                     }
-                    if decoys.len() >= identification_args.get_number_of_decoys() { break; }
+                }
+                if decoys.len() < identification_args.get_number_of_decoys() {
+                    let mut possible_decoys = match Decoy::find_where(&conn, target_decoy_condition.as_str(), values.as_ref()) {
+                        Ok(count) => count,
+                        Err(err) => panic!("proteomic::tasks::identification::identification_task(): could not gether decoy: {}", err)
+                    };
+                    for decoy in possible_decoys.iter_mut() {
+                        if decoy_sequences.contains(decoy.get_aa_sequence()) {
+                            continue;
+                        }
+                        #[allow(unused_assignments)] // `modified_decoys_fits_precursor_tolerance` is actually read in if-instruction below
+                        let mut modified_decoys_fits_precursor_tolerance = false;
+                        let mut modified_decoy = ModifiedPeptide::from_decoy(&decoy, precursor_mass,  precursor_tolerance.0,  precursor_tolerance.1, fixed_modifications_map);
+                        modified_decoy.set_mass_type(identification_args.get_mass_type());
+                        modified_decoys_fits_precursor_tolerance = modified_decoy.hits_mass_tolerance();
+                        if !modified_decoys_fits_precursor_tolerance {
+                            modified_decoys_fits_precursor_tolerance = modified_decoy.try_variable_modifications(identification_args.get_max_number_of_variable_modification_per_decoy(), variable_modifications_map);
+                        }
+                        if modified_decoys_fits_precursor_tolerance {
+                            decoy.set_modification_summary(modified_decoy.get_modification_summary_for_header().as_str());
+                            decoy.set_proforma(modified_decoy.to_proforma().as_str());
+                            // the reuse cap applies only to decoys taken from the cache, a decoy found in the database is always usable
+                            if let Some(ref mut decoy_cache) = *decoy_cache {
+                                decoy_cache.insert(modified_decoy.get_weight(), decoy.get_header().as_str(), decoy.get_aa_sequence());
+                            }
+                            decoy_sequences.insert(decoy.get_aa_sequence().to_owned());
+                            decoys.insert(
+                                FastaEntry::new(
+                                    decoy.get_header().as_str(),
+                                    decoy.get_aa_sequence()
+                                )
+                            );
+                        }
+                        if decoys.len() >= identification_args.get_number_of_decoys() { break; }
+                    }
                 }
             }
-        }
-        let mut stop_time: f64 = time::precise_time_s();
-        println!("found {} targets and {} decoys in {} s", targets.len(), decoys.len(), stop_time - start_time);
-        let mut number_of_target_and_decoys = targets.len() + decoys.len();
-        let mut decoy_generation_result: GenerationResult = GenerationResult::Success;
-        // generate decoys
-        if decoys.len() < identification_args.get_number_of_decoys()  {
-            let mut remaining_number_of_decoys = identification_args.get_number_of_decoys() - decoys.len();
-            let remaining_number_of_decoys_for_output = remaining_number_of_decoys;
-            println!("need to generate {} decoys...", remaining_number_of_decoys);
-            let generator: DecoyGenerator = DecoyGenerator::new(
-                precursor_mass,
-                precursor_tolerance.0,
-                precursor_tolerance.1,
-                identification_args.get_thread_count(),
-                identification_args.get_max_number_of_variable_modification_per_decoy(),
-                &fixed_modifications_map,
-                &variable_modifications_map,
-                identification_args.get_max_time_for_decoy_generation()
+            let mut stop_time: f64 = time::precise_time_s();
+            println!("found {} targets and {} decoys in {} s", targets.len(), decoys.len(), stop_time - start_time);
+            // reject decoys which are near-copies of the targets
+            let target_sequences: Vec<String> = targets.iter().map(|target| target.get_aa_sequence().to_owned()).collect();
+            let similarity_filter: Arc<SimilarityFilter> = Arc::new(
+                SimilarityFilter::new(
+                    &target_sequences,
+                    &channel.registry,
+                    identification_args.get_similarity_max_edit_distance(),
+                    identification_args.get_similarity_max_shared_fragment_ratio(),
+                    mass::convert_mass_to_int(identification_args.get_fragmentation_tolerance()),
+                    identification_args.get_similarity_kmer_length()
+                )
             );
-            start_time = time::precise_time_s();
-            decoy_generation_result = generator.generate_decoys(remaining_number_of_decoys);
-            stop_time = time::precise_time_s();
-            match generator.get_decoys().lock() {
-                Ok(generated_decoys) => {
-                    println!("generate {} decoys in {} s", generated_decoys.len(), stop_time - start_time);
-                    number_of_target_and_decoys += generated_decoys.len();
-                    for decoy in generated_decoys.iter() {
-                        decoys.insert(
-                            FastaEntry::new(
-                                decoy.get_header().as_str(),
-                                decoy.get_aa_sequence()
-                            )
-                        );
+            if similarity_filter.is_active() {
+                decoys.retain(|decoy| !similarity_filter.rejects(decoy.get_aa_sequence()));
+                println!("{} decoys left after similarity filter", decoys.len());
+            }
+            // reject decoys whose properties are outside the spread of the targets
+            let property_filter: Arc<PropertyFilter> = Arc::new(PropertyFilter::new(&target_sequences, identification_args.get_target_properties(), identification_args.get_property_margin()));
+            if property_filter.is_active() {
+                decoys.retain(|decoy| !property_filter.rejects(decoy.get_aa_sequence()));
+                println!("{} decoys left after property filter", decoys.len());
+            }
+            let mut number_of_target_and_decoys = targets.len() + decoys.len();
+            let mut decoy_generation_result: GenerationResult = GenerationResult::new(GenerationStatus::Success, GenerationStatistics::default());
+            // generate decoys
+            if identification_args.get_lookup_only() && (decoys.len() < identification_args.get_number_of_decoys()) {
+                println!("lookup-only: found only {} of {} decoys", decoys.len(), identification_args.get_number_of_decoys());
+                decoy_generation_result = GenerationResult::new(GenerationStatus::LookupShortfall, GenerationStatistics::default());
+            } else if decoys.len() < identification_args.get_number_of_decoys()  {
+                let mut remaining_number_of_decoys = identification_args.get_number_of_decoys() - decoys.len();
+                let remaining_number_of_decoys_for_output = remaining_number_of_decoys;
+                println!("need to generate {} decoys...", remaining_number_of_decoys);
+                let mut generator: DecoyGenerator = DecoyGenerator::new(
+                    precursor_mass,
+                    precursor_tolerance.0,
+                    precursor_tolerance.1,
+                    identification_args.get_thread_count(),
+                    identification_args.get_max_number_of_variable_modification_per_decoy(),
+                    fixed_modifications_map,
+                    variable_modifications_map,
+                    identification_args.get_max_time_for_decoy_generation()
+                );
+                generator.set_seed(channel_seed);
+                generator.set_mass_type(identification_args.get_mass_type());
+                generator.set_generation_strategy(identification_args.get_generation_strategy());
+                generator.set_mass_fitting_options(identification_args.get_mass_fitting_options().clone());
+                if let Some(ref target_index) = target_index {
+                    generator.set_target_index(target_index.clone());
+                }
+                if similarity_filter.is_active() {
+                    generator.set_similarity_filter(similarity_filter.clone());
+                }
+                if property_filter.is_active() {
+                    generator.set_property_filter(property_filter.clone());
+                }
+                if identification_args.get_match_target_composition() {
+                    if target_composition.is_empty() {
+                        println!("no targets for amino acid composition, use uniform amino acid distribution instead");
+                    } else {
+                        generator.set_amino_acid_composition(target_composition);
                     }
                 }
-                Err(_) => panic!("proteomic::tasks::identification::identification_task(): try to lock poisened mutex for decoys at generator.get_decoys().lock()")
-            };
+                match identification_args.get_decoy_length_distribution() {
+                    "targets" => {
+                        let target_lengths: Vec<usize> = targets.iter().map(|target| target.get_aa_sequence().len()).collect();
+                        let length_distribution = LengthDistribution::from_lengths(&target_lengths, identification_args.get_min_peptide_length(), identification_args.get_max_peptide_length());
+                        if length_distribution.is_empty() {
+                            println!("no target lengths for length distribution, use range instead");
+                            generator.set_length_distribution(LengthDistribution::uniform(identification_args.get_min_peptide_length(), identification_args.get_max_peptide_length()));
+                        } else {
+                            generator.set_length_distribution(length_distribution);
+                        }
+                    },
+                    "range" => generator.set_length_distribution(LengthDistribution::uniform(identification_args.get_min_peptide_length(), identification_args.get_max_peptide_length())),
+                    _ => ()
+                }
+                start_time = time::precise_time_s();
+                decoy_generation_result = generator.generate_decoys(remaining_number_of_decoys);
+                stop_time = time::precise_time_s();
+                println!("{}", decoy_generation_result.get_statistics().to_string());
+                match generator.get_decoys().lock() {
+                    Ok(generated_decoys) => {
+                        println!("generate {} decoys in {} s", generated_decoys.len(), stop_time - start_time);
+                        for decoy in generated_decoys.iter() {
+                            if !decoy_sequences.insert(decoy.get_aa_sequence().to_owned()) {
+                                continue;
+                            }
+                            number_of_target_and_decoys += 1;
+                            if let Some(ref mut decoy_cache) = *decoy_cache {
+                                let mut modified_decoy = ModifiedPeptide::from_decoy(&decoy, precursor_mass,  precursor_tolerance.0,  precursor_tolerance.1, fixed_modifications_map);
+                                modified_decoy.set_mass_type(identification_args.get_mass_type());
+                                if modified_decoy.hits_mass_tolerance() || modified_decoy.try_variable_modifications(identification_args.get_max_number_of_variable_modification_per_decoy(), variable_modifications_map) {
+                                    // the header must describe the modifications which belong to the cached mass
+                                    let mut cached_decoy = Decoy::new(decoy.get_aa_sequence(), 0);
+                                    cached_decoy.set_modification_summary(modified_decoy.get_modification_summary_for_header().as_str());
+                                    cached_decoy.set_proforma(modified_decoy.to_proforma().as_str());
+                                    decoy_cache.insert(modified_decoy.get_weight(), cached_decoy.get_header().as_str(), decoy.get_aa_sequence());
+                                }
+                            }
+                            decoys.insert(
+                                FastaEntry::new(
+                                    decoy.get_header().as_str(),
+                                    decoy.get_aa_sequence()
+                                )
+                            );
+                        }
+                    }
+                    Err(_) => panic!("proteomic::tasks::identification::identification_task(): try to lock poisened mutex for decoys at generator.get_decoys().lock()")
+                };
+            }
+            if similarity_filter.is_active() {
+                println!("{}", similarity_filter.get_rejection_summary());
+            }
+            if property_filter.is_active() {
+                println!("{}", property_filter.get_rejection_summary());
+            }
+            if let Some(ref mut decoy_cache) = *decoy_cache {
+                for decoy in decoys.iter() {
+                    decoy_cache.record_usage(decoy.get_aa_sequence());
+                }
+                println!("{} decoys in cache", decoy_cache.get_number_of_decoys());
+            }
+            // the labels of the channel are static modifications in the search of the channel, so the channels can not be pooled into one search
+            let channel_targets: Vec<FastaEntry> = targets.iter().flat_map(|target| get_output_entries(target, &channel, identification_args)).collect();
+            let channel_decoys: Vec<FastaEntry> = decoys.iter().flat_map(|decoy| get_output_entries(decoy, &channel, identification_args)).collect();
+            write_search_files(identification_args, &channel, &channel_targets, &channel_decoys, number_of_target_and_decoys, decoy_generation_result.is_success());
+            decoy_generation_results.push((channel.name.clone(), decoy_generation_result));
         }
         // build filename by replace the file extension with fasta
-        let mut fasta_filename = PathBuf::from(identification_args.get_spectrum_file());
-        fasta_filename.set_extension("fasta");
-        let fasta_file = match OpenOptions::new().read(true).write(true).create(true).open(&fasta_filename) {
+        let fasta_filename: PathBuf = get_output_filename(identification_args.get_spectrum_file(), &None, "fasta");
+        // write the statistics of the decoy generation to a file with file-extension "generation_statistics.csv", to find out why a generation timed out
+        let mut generation_statistics_filename = fasta_filename.to_owned();
+        generation_statistics_filename.set_extension("generation_statistics.csv");
+        let generation_statistics_file = match OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&generation_statistics_filename) {
             Ok(file) => file,
-            Err(err) => panic!("proteomic::tasks::identification::identification_task(): error at opening fasta-file: {}", err)
+            Err(err) => panic!("proteomic::tasks::identification::identification_task(): error at opening generation_statistics-file: {}", err)
         };
-        let mut fasta_file = LineWriter::new(fasta_file);
-        for fasta_entry in targets.iter() {
-            match fasta_file.write(fasta_entry.to_string().as_bytes()) {
-                Ok(_) => (),
-                Err(err) => println!("proteomic::tasks::identification::identification_task(): Could not write to FASTA-file: {}", err)
-            }
+        let mut generation_statistics_file = LineWriter::new(generation_statistics_file);
+        // one row per channel, the channel column is only present when a label set is used
+        let mut generation_statistics = format!("spectrum_id,status,{}", generation_statistics::CSV_HEADER);
+        if modification_registry.has_label_channels() {
+            generation_statistics.push_str(",channel");
         }
-        for fasta_entry in decoys.iter() {
-            match fasta_file.write(fasta_entry.to_string().as_bytes()) {
-                Ok(_) => (),
-                Err(err) => println!("proteomic::tasks::identification::identification_task(): Could not write to FASTA-file: {}", err)
+        generation_statistics.push('\n');
+        for &(ref channel_name, ref decoy_generation_result) in decoy_generation_results.iter() {
+            generation_statistics.push_str(
+                format!(
+                    "\"{}\",{},{}",
+                    spectrum.get_spectrum_id().replace("\"", "\"\""),
+                    decoy_generation_result.get_status().to_string(),
+                    decoy_generation_result.get_statistics().to_csv_row()
+                ).as_str()
+            );
+            if let Some(ref channel_name) = *channel_name {
+                generation_statistics.push_str(format!(",\"{}\"", channel_name.replace("\"", "\"\"")).as_str());
             }
+            generation_statistics.push('\n');
         }
-        // if decoy generation timed out, write the number of used decoys to a file with file-extension "less_decoys"
-        if decoy_generation_result == GenerationResult::Timeout {
-            let mut less_decoy_filename = fasta_filename.to_owned();
-            less_decoy_filename.set_extension("less_decoys");
-            let less_decoy_file = match OpenOptions::new().read(true).write(true).create(true).open(&less_decoy_filename) {
-                Ok(file) => file,
-                Err(err) => panic!("proteomic::tasks::identification::identification_task(): error at opening less_decoy-file: {}", err)
-            };
-            let mut less_decoy_file = LineWriter::new(less_decoy_file);
-            match less_decoy_file.write(format!("{}", decoys.len()).as_bytes()) {
-                Ok(_) => (),
-                Err(err) => println!("proteomic::tasks::identification::identification_task(): Could not write to less_decoy-file: {}", err)
-            }
+        match generation_statistics_file.write(generation_statistics.as_bytes()) {
+            Ok(_) => (),
+            Err(err) => println!("proteomic::tasks::identification::identification_task(): Could not write to generation_statistics-file: {}", err)
         }
-
-        let mut comet_params_filename = fasta_filename.to_owned();
-        comet_params_filename.set_extension("comet.params");
-        let comet_params_file = match OpenOptions::new().read(true).write(true).create(true).open(&comet_params_filename) {
+        // write the seed of this spectrum to a file with file-extension "decoy_seed", so the decoy generation can be reproduced
+        let mut decoy_seed_filename = fasta_filename.to_owned();
+        decoy_seed_filename.set_extension("decoy_seed");
+        let decoy_seed_file = match OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&decoy_seed_filename) {
             Ok(file) => file,
-            Err(err) => panic!("proteomic::tasks::identification::identification_task(): error at opening comet.params: {}", err)
+            Err(err) => panic!("proteomic::tasks::identification::identification_task(): error at opening decoy_seed-file: {}", err)
         };
-        let mut comet_params_file = LineWriter::new(comet_params_file);
-        match comet_params_file.write(comet_parameter::new(identification_args.get_comet_revision(), &fixed_modifications_map, &variable_modifications_map, &fasta_filename, number_of_target_and_decoys, identification_args.max_number_of_variable_modification_per_decoy, identification_args.get_fragmentation_tolerance(), identification_args.get_lower_mass_tolerance(), identification_args.get_upper_mass_tolerance()).as_bytes()) {
+        let mut decoy_seed_file = LineWriter::new(decoy_seed_file);
+        let mut decoy_seeds: String = format!("run_seed={}\nspectrum_seed={}\nthread_count={}\n", identification_args.get_seed(), spectrum_seed, identification_args.get_thread_count());
+        for &(ref channel_name, channel_seed) in channel_seeds.iter() {
+            decoy_seeds.push_str(format!("channel_seed.{}={}\n", get_filename_safe_channel_name(channel_name), channel_seed).as_str());
+        }
+        match decoy_seed_file.write(decoy_seeds.as_bytes()) {
             Ok(_) => (),
-            Err(err) => println!("proteomic::tasks::identification::identification_task(): Could not write to comet.params: {}", err)
+            Err(err) => println!("proteomic::tasks::identification::identification_task(): Could not write to decoy_seed-file: {}", err)
         }
     }
 }
 
 /// Recursive funtion which fills the argument `results` (call by reference) with all combination of different amounts of applied amino acid modificatons and the resulting changes in precursor tolerance limits
 /// After the function is finsihed, the argument `results` contains tuples of the form (lower_precursor_tolerance_with_respect_to_amount_of_modified_amino_acids, upper_precursor_tolerance_with_respect_to_amount_of_modified_amino_acids, Vec[amount_of_modifyable_amino_acid_1, amount_of_modifyable_amino_acid_2, ...])
-fn get_max_modifyable_amino_acid_counts(modifications_map: &HashMap<char, &Modification>, precursor_tolerance: (i64, i64), amino_acid_one_letter_codes: &Vec<char>, max_modification_counts: &HashMap<char, i16>, amino_acid_index: usize, count_combination: &mut Vec<i16>, results: &mut Vec<(i64, i64, Vec<i16>)>) {
+fn get_max_modifyable_amino_acid_counts(mass_shift_ranges: &HashMap<char, (i64, i64)>, precursor_tolerance: (i64, i64), amino_acid_one_letter_codes: &Vec<char>, max_modification_counts: &HashMap<char, i16>, amino_acid_index: usize, count_combination: &mut Vec<i16>, results: &mut Vec<(i64, i64, Vec<i16>)>) {
     let amino_acid_one_letter_code = match amino_acid_one_letter_codes.get(amino_acid_index) {
         Some(one_letter_code) => one_letter_code,
         None => &'_'
     };
     if let Some(max_modification_count) = max_modification_counts.get(amino_acid_one_letter_code) {
-        if let Some(ref modification) = modifications_map.get(amino_acid_one_letter_code) {
+        if let Some(mass_shift_range) = mass_shift_ranges.get(amino_acid_one_letter_code) {
             for mod_count in 0..*max_modification_count {
-                let modification_mass = mod_count as i64 * modification.get_mono_mass();
+                // each residue carries the lightest to the heaviest mass shift of the amino acid
                 let new_precursor_tolerance = (
-                    precursor_tolerance.0 - modification_mass,
-                    precursor_tolerance.1 - modification_mass
+                    precursor_tolerance.0 - mod_count as i64 * mass_shift_range.1,
+                    precursor_tolerance.1 - mod_count as i64 * mass_shift_range.0
                 );
                 if precursor_tolerance.0 > 0 {
                     count_combination.push(mod_count);
                     if amino_acid_index < max_modification_counts.len() - 1 {
-                        get_max_modifyable_amino_acid_counts(modifications_map, new_precursor_tolerance,  amino_acid_one_letter_codes, max_modification_counts, amino_acid_index + 1, count_combination, results);
+                        get_max_modifyable_amino_acid_counts(mass_shift_ranges, new_precursor_tolerance,  amino_acid_one_letter_codes, max_modification_counts, amino_acid_index + 1, count_combination, results);
                     } else {
                         results.push((
                             new_precursor_tolerance.0,
@@ -401,3 +824,162 @@ fn get_max_modifyable_amino_acid_counts(modifications_map: &HashMap<char, &Modif
         }
     }
 }
+
+/// Returns the range of the monoisotopic weights of unmodified sequences with an average mass in the given range.
+/// The ratio of mono to average mass differs between the amino acids, e.g. cysteine is lighter and selenocysteine heavier than the average ratio,
+/// so the residues are converted with the smallest ratio for the lower and the largest ratio for the upper limit.
+fn get_monoisotopic_weight_range(average_mass_range: (i64, i64)) -> (i64, i64) {
+    let mut ratio_range: (f64, f64) = (std::f64::MAX, std::f64::MIN);
+    for amino_acid_one_letter_code in AMINO_ACIDS_FOR_COUNTING.iter() {
+        let amino_acid = AminoAcid::get(*amino_acid_one_letter_code);
+        let ratio: f64 = amino_acid.get_mono_mass() as f64 / amino_acid.get_average_mass() as f64;
+        ratio_range.0 = ratio_range.0.min(ratio);
+        ratio_range.1 = ratio_range.1.max(ratio);
+    }
+    let water = NeutralLoss::get("H2O");
+    return (
+        water.get_mono_mass() + ((average_mass_range.0 - water.get_average_mass()) as f64 * ratio_range.0).floor() as i64,
+        water.get_mono_mass() + ((average_mass_range.1 - water.get_average_mass()) as f64 * ratio_range.1).ceil() as i64
+    );
+}
+
+/// Modifications and precomputed query values of one label channel
+struct SearchChannel {
+    name: Option<String>,
+    registry: ModificationRegistry,
+    sorted_modifyable_amino_acids: Vec<char>,
+    // terminal modifications for any amino acid shift the mass of the peptide independently of its amino acid counts
+    any_amino_acid_mass_shift_range: (i64, i64),
+    has_protein_terminal_modifications: bool,
+    target_decoy_condition: String,
+    // lightest and heaviest mass shift per residue of the modifiable amino acids for creating queries
+    mass_shift_ranges: HashMap<char, (i64, i64)>,
+    // decoys of previous spectra, which can be reused by spectra with overlapping precursor tolerances
+    decoy_cache: Option<DecoyCache>
+}
+
+impl SearchChannel {
+    fn new(name: Option<String>, registry: ModificationRegistry, max_decoy_reuse: Option<usize>, mass_type: MassType) -> Self {
+        let sorted_modifyable_amino_acids: Vec<char> = registry.get_modifiable_amino_acids();
+        // prepare condition for target and decoys
+        let mut target_decoy_condition = TARGET_DECOY_QUERY_CONDITION.to_owned();
+        if sorted_modifyable_amino_acids.len() > 0 {
+            target_decoy_condition.push_str(" AND ");
+            let mut conditions: Vec<String> = Vec::new();
+            for (idx, amino_acid_one_letter_code) in sorted_modifyable_amino_acids.iter().enumerate() {
+                conditions.push(format!("{}_count = ${}", amino_acid_one_letter_code.to_ascii_lowercase(), idx + 3));
+            }
+            target_decoy_condition.push_str(conditions.join(" AND ").as_str());
+        }
+        let mut mass_shift_ranges: HashMap<char, (i64, i64)> = HashMap::new();
+        for amino_acid_one_letter_code in sorted_modifyable_amino_acids.iter() {
+            if let Some(mass_shift_range) = registry.get_mass_shift_range(*amino_acid_one_letter_code, mass_type) {
+                mass_shift_ranges.insert(*amino_acid_one_letter_code, mass_shift_range);
+            }
+        }
+        return Self {
+            name: name,
+            any_amino_acid_mass_shift_range: registry.get_any_amino_acid_mass_shift_range(mass_type),
+            has_protein_terminal_modifications: registry.has_protein_terminal_modifications(),
+            registry: registry,
+            sorted_modifyable_amino_acids: sorted_modifyable_amino_acids,
+            target_decoy_condition: target_decoy_condition,
+            mass_shift_ranges: mass_shift_ranges,
+            decoy_cache: match max_decoy_reuse {
+                Some(max_decoy_reuse) => Some(DecoyCache::new(max_decoy_reuse)),
+                None => None
+            }
+        }
+    }
+}
+
+/// Appends the label channel to the FASTA-header, so the search results can be assigned to their channel
+fn get_channel_header(header: &str, channel_name: &Option<String>) -> String {
+    return match channel_name {
+        &Some(ref channel_name) => format!("{} Channel={}", header, channel_name),
+        &None => header.to_owned()
+    };
+}
+
+/// Returns the entries for the search database, a FASTA-entry with channel or PEFF-entries with the modifications at explicit positions, one per isoform.
+/// The fixed modifications of the channel, including its labels, are static modifications in the search of the channel.
+fn get_output_entries(fasta_entry: &FastaEntry, channel: &SearchChannel, identification_args: &IdentificationArguments) -> Vec<FastaEntry> {
+    let channel_header: String = get_channel_header(fasta_entry.get_header(), &channel.name);
+    return match identification_args.get_peff_format() {
+        Some(peff_format) => match peff::get_entry_headers(channel_header.as_str(), &channel.registry, channel.registry.get_fixed_modification_map(), peff_format) {
+            Ok(peff_headers) => peff_headers.iter().map(|peff_header| FastaEntry::new(peff_header.as_str(), fasta_entry.get_aa_sequence())).collect(),
+            Err(err) => panic!("proteomic::tasks::identification::get_output_entries(): {}", err)
+        },
+        None => vec![FastaEntry::new(channel_header.as_str(), fasta_entry.get_aa_sequence())]
+    };
+}
+
+/// Returns the channel name with all characters replaced which are not allowed in filenames
+fn get_filename_safe_channel_name(channel_name: &str) -> String {
+    return channel_name.chars().map(|character| if character.is_ascii_alphanumeric() | (character == '-') | (character == '.') { character } else { '_' }).collect();
+}
+
+/// Returns the path of an output file for the spectrum file with the given file extension.
+/// Files of a label channel contain the channel name, e.g. `spectrum.heavy.fasta`.
+fn get_output_filename(spectrum_file: &str, channel_name: &Option<String>, extension: &str) -> PathBuf {
+    let mut filename = PathBuf::from(spectrum_file);
+    match channel_name {
+        &Some(ref channel_name) => filename.set_extension(format!("{}.{}", get_filename_safe_channel_name(channel_name), extension)),
+        &None => filename.set_extension(extension)
+    };
+    return filename;
+}
+
+/// Writes the search database (FASTA- or PEFF-file) and the Comet parameters for the channel.
+/// If the decoy generation was not successful, the number of decoys is written to a file with file-extension "less_decoys".
+fn write_search_files(identification_args: &IdentificationArguments, channel: &SearchChannel, targets: &Vec<FastaEntry>, decoys: &Vec<FastaEntry>, number_of_target_and_decoys: usize, is_generation_successful: bool) {
+    // the search database is a PEFF-file instead of the FASTA-file, if a PEFF format is given
+    let database_filename: PathBuf = match identification_args.get_peff_format() {
+        Some(_) => get_output_filename(identification_args.get_spectrum_file(), &channel.name, "peff"),
+        None => get_output_filename(identification_args.get_spectrum_file(), &channel.name, "fasta")
+    };
+    let fasta_file = match OpenOptions::new().read(true).write(true).create(true).open(&database_filename) {
+        Ok(file) => file,
+        Err(err) => panic!("proteomic::tasks::identification::write_search_files(): error at opening fasta-file: {}", err)
+    };
+    let mut fasta_file = LineWriter::new(fasta_file);
+    if identification_args.get_peff_format().is_some() {
+        let database_name: &str = match database_filename.file_name().and_then(|file_name| file_name.to_str()) {
+            Some(file_name) => file_name,
+            None => ""
+        };
+        match fasta_file.write(peff::get_file_header(database_name, targets.len() + decoys.len()).as_bytes()) {
+            Ok(_) => (),
+            Err(err) => println!("proteomic::tasks::identification::write_search_files(): Could not write to PEFF-file: {}", err)
+        }
+    }
+    for fasta_entry in targets.iter().chain(decoys.iter()) {
+        match fasta_file.write(fasta_entry.to_string().as_bytes()) {
+            Ok(_) => (),
+            Err(err) => println!("proteomic::tasks::identification::write_search_files(): Could not write to FASTA-file: {}", err)
+        }
+    }
+    // if decoy generation timed out or found no solution, write the number of used decoys to a file with file-extension "less_decoys"
+    if !is_generation_successful {
+        let less_decoy_filename: PathBuf = get_output_filename(identification_args.get_spectrum_file(), &channel.name, "less_decoys");
+        let less_decoy_file = match OpenOptions::new().read(true).write(true).create(true).open(&less_decoy_filename) {
+            Ok(file) => file,
+            Err(err) => panic!("proteomic::tasks::identification::write_search_files(): error at opening less_decoy-file: {}", err)
+        };
+        let mut less_decoy_file = LineWriter::new(less_decoy_file);
+        match less_decoy_file.write(format!("{}", decoys.len()).as_bytes()) {
+            Ok(_) => (),
+            Err(err) => println!("proteomic::tasks::identification::write_search_files(): Could not write to less_decoy-file: {}", err)
+        }
+    }
+    let comet_params_filename: PathBuf = get_output_filename(identification_args.get_spectrum_file(), &channel.name, "comet.params");
+    let comet_params_file = match OpenOptions::new().read(true).write(true).create(true).open(&comet_params_filename) {
+        Ok(file) => file,
+        Err(err) => panic!("proteomic::tasks::identification::write_search_files(): error at opening comet.params: {}", err)
+    };
+    let mut comet_params_file = LineWriter::new(comet_params_file);
+    match comet_params_file.write(comet_parameter::new(identification_args.get_comet_revision(), &channel.registry, &database_filename, number_of_target_and_decoys, identification_args.max_number_of_variable_modification_per_decoy, identification_args.get_fragmentation_tolerance(), identification_args.get_lower_mass_tolerance(), identification_args.get_upper_mass_tolerance(), identification_args.get_peff_format(), identification_args.get_peff_obo(), identification_args.get_mass_type()).as_bytes()) {
+        Ok(_) => (),
+        Err(err) => println!("proteomic::tasks::identification::write_search_files(): Could not write to comet.params: {}", err)
+    }
+}
@@ -1,3 +1,6 @@
 pub mod identification;
 pub mod digestion;
-pub mod sequence_mass;
\ No newline at end of file
+pub mod sequence_mass;
+pub mod target_index;
+pub mod decoy_pool;
+pub mod decoy_generation;
\ No newline at end of file
@@ -1,27 +1,46 @@
 use proteomic::models::mass;
-use proteomic::models::amino_acids::amino_acid::AminoAcid;
+use proteomic::models::amino_acids::modification_registry::ModificationRegistry;
+use proteomic::models::peptides::modified_peptide::ModifiedPeptide;
 
 pub struct SequenceMassArguments {
-    sequence: String
+    sequence: String,
+    modification_file: String
 }
 
 impl SequenceMassArguments {
+    /// Plain sequence or ProForma 2.0 string, e.g. `[UNIMOD:1]-PEPM[UNIMOD:35]K`
     pub fn get_sequence(&self) -> &str {
         return self.sequence.as_str();
     }
 
+    /// Modification file to resolve modification names and accessions of the ProForma string, empty if not given
+    pub fn get_modification_file(&self) -> &str {
+        return self.modification_file.as_str();
+    }
+
     pub fn from_cli_args(cli_args: &clap::ArgMatches) -> Self {
         let sequence: &str = match cli_args.value_of("SEQUENCE") {
             Some(sequence) => sequence,
             None => panic!("proteomic::tasks::digestion::SequenceMassArguments.from_cli_args(): No sequence spezified.")
         };
         return Self {
-            sequence: sequence.to_owned()
+            sequence: sequence.to_owned(),
+            modification_file: cli_args.value_of("MODIFICATION_FILE").unwrap_or("").to_owned()
         }
     }
 }
 
 pub fn sequence_mass_task(sequence_mass_args: &SequenceMassArguments) {
-    let mass = AminoAcid::get_sequence_weight(sequence_mass_args.get_sequence());
-    println!("sequence '{}', has a mass of {} Da", sequence_mass_args.get_sequence(), mass::convert_mass_to_float(mass));
-}
\ No newline at end of file
+    let modification_registry = match sequence_mass_args.get_modification_file() {
+        "" => ModificationRegistry::new(),
+        modification_file => match ModificationRegistry::from_file(modification_file) {
+            Ok(modification_registry) => modification_registry,
+            Err(err) => panic!("proteomic::tasks::sequence_mass::sequence_mass_task(): {}", err)
+        }
+    };
+    let modified_peptide = match ModifiedPeptide::from_proforma("", sequence_mass_args.get_sequence(), 0, 0, 0, &modification_registry) {
+        Ok(modified_peptide) => modified_peptide,
+        Err(err) => panic!("proteomic::tasks::sequence_mass::sequence_mass_task(): {}", err)
+    };
+    println!("sequence '{}', has a mass of {} Da", modified_peptide.to_proforma(), mass::convert_mass_to_float(modified_peptide.get_weight()));
+}
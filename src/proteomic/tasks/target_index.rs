@@ -0,0 +1,52 @@
+use proteomic::utility::database_connection::DatabaseConnection;
+use proteomic::utility::target_index::TargetIndex;
+
+pub struct TargetIndexArguments {
+    target_index_file: String,
+    false_positive_rate: f64
+}
+
+impl TargetIndexArguments {
+    pub fn new(target_index_file: &str, false_positive_rate: f64) -> Self {
+        return Self {
+            target_index_file: target_index_file.to_owned(),
+            false_positive_rate: false_positive_rate
+        }
+    }
+
+    pub fn get_target_index_file(&self) -> &str {
+        return self.target_index_file.as_str();
+    }
+
+    pub fn get_false_positive_rate(&self) -> f64 {
+        return self.false_positive_rate;
+    }
+
+    pub fn from_cli_args(cli_args: &clap::ArgMatches) -> Self {
+        let target_index_file: &str = match cli_args.value_of("TARGET_INDEX_FILE") {
+            Some(file) => file,
+            None => panic!("proteomic::tasks::target_index::TargetIndexArguments.from_cli_args(): No target-index-file spezified.")
+        };
+        let false_positive_rate: f64 = match cli_args.value_of("FALSE_POSITIVE_RATE") {
+            Some(number_string) => match number_string.to_owned().parse::<f64>() {
+                Ok(rate) => rate,
+                Err(_err) => panic!("proteomic::tasks::target_index::TargetIndexArguments.from_cli_args(): Could not parse false-positive-rate to float.")
+            },
+            None => 0.01
+        };
+        if (false_positive_rate <= 0.0) | (false_positive_rate >= 1.0) {
+            panic!("proteomic::tasks::target_index::TargetIndexArguments.from_cli_args(): false-positive-rate must be between 0 and 1 (both exclusive).");
+        }
+        return Self::new(target_index_file, false_positive_rate);
+    }
+}
+
+/// Builds the target index from the peptides-table and writes it to the target index file
+pub fn build_target_index_task(target_index_args: &TargetIndexArguments) {
+    let conn = DatabaseConnection::get_database_connection();
+    let start_time: f64 = time::precise_time_s();
+    let target_index = TargetIndex::build_from_database(&conn, target_index_args.get_false_positive_rate());
+    target_index.save(target_index_args.get_target_index_file());
+    let stop_time: f64 = time::precise_time_s();
+    println!("indexed {} targets in {} s, written to '{}'", target_index.get_number_of_sequences(), stop_time - start_time, target_index_args.get_target_index_file());
+}
@@ -0,0 +1,70 @@
+use std::collections::HashSet;
+
+use rand::Rng;
+
+use proteomic::models::peptides::peptide::Peptide;
+use proteomic::models::peptides::peptide_interface::PeptideInterface;
+use proteomic::models::amino_acids::amino_acid::AMINO_ACIDS_FOR_DECOY_GENERATION;
+
+/// Added to each amino acid count, so amino acids which do not occur in the targets are still possible
+const PSEUDO_COUNT: f64 = 1.0;
+
+/// Amino acid composition of a peptide population, e.g. the targets of a spectrum.
+/// Used to bias the decoy generation, so targets and decoys differ in sequence order but not in chemistry.
+pub struct AminoAcidComposition {
+    counts: Vec<(char, usize)>,     // in order of AMINO_ACIDS_FOR_DECOY_GENERATION
+    aa_sequences: HashSet<String>   // sequences which are already counted
+}
+
+impl AminoAcidComposition {
+    pub fn new() -> Self {
+        return Self {
+            counts: AMINO_ACIDS_FOR_DECOY_GENERATION.iter().map(|aa_one_letter_code| (*aa_one_letter_code, 0)).collect(),
+            aa_sequences: HashSet::new()
+        };
+    }
+
+    /// Adds the amino acids of the given peptide, using its amino acid counts (*_count-columns).
+    /// Each sequence is counted once, even if it is added several times, e.g. by several queries.
+    pub fn add_peptide(&mut self, peptide: &Peptide) {
+        if !self.aa_sequences.insert(peptide.get_aa_sequence().to_owned()) {
+            return;
+        }
+        for count in self.counts.iter_mut() {
+            count.1 += *peptide.get_count_for_amino_acid(&count.0) as usize;
+        }
+    }
+
+    /// Returns the number of counted amino acids
+    pub fn get_number_of_amino_acids(&self) -> usize {
+        return self.counts.iter().map(|count| count.1).sum();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.get_number_of_amino_acids() == 0;
+    }
+
+    /// Returns the relative frequency of the given amino acid, including the pseudo count
+    pub fn get_frequency(&self, aa_one_letter_code: char) -> f64 {
+        let total: f64 = self.get_number_of_amino_acids() as f64 + PSEUDO_COUNT * self.counts.len() as f64;
+        return match self.counts.iter().find(|count| count.0 == aa_one_letter_code) {
+            Some(count) => (count.1 as f64 + PSEUDO_COUNT) / total,
+            None => 0.0
+        };
+    }
+
+    /// Picks an amino acid with a probability of its relative frequency
+    pub fn choose_amino_acid<R: Rng>(&self, rng: &mut R) -> char {
+        let total: f64 = self.get_number_of_amino_acids() as f64 + PSEUDO_COUNT * self.counts.len() as f64;
+        let mut threshold: f64 = rng.gen::<f64>() * total;
+        for count in self.counts.iter() {
+            let weight: f64 = count.1 as f64 + PSEUDO_COUNT;
+            if threshold < weight {
+                return count.0;
+            }
+            threshold -= weight;
+        }
+        // floating point rounding might leave a small remainder
+        return self.counts[self.counts.len() - 1].0;
+    }
+}
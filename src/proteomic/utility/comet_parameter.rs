@@ -1,21 +1,21 @@
-use std::collections::HashMap;
 use std::path::Path;
 
 use proteomic::models::amino_acids::modification::Modification;
+use proteomic::models::amino_acids::modification_registry::ModificationRegistry;
+use proteomic::models::amino_acids::amino_acid::AminoAcid;
+use proteomic::models::mass;
+use proteomic::models::mass::mass_type::MassType;
+use proteomic::utility::peff::PeffFormat;
 
 const COMET_PARAMS_BEGIN: &'static str = "
 # Comet MS/MS search engine parameters file.
 # Everything following the '#' symbol is treated as a comment.
 
 decoy_search = 0
-peff_format = 0
-peff_obo =
 
 num_threads = 0
 
 peptide_mass_units = 2
-mass_type_parent = 1
-mass_type_fragment = 1
 precursor_tolerance_type = 1
 isotope_error = 3
 
@@ -33,7 +33,6 @@ use_C_ions = 0
 use_X_ions = 0
 use_Y_ions = 1
 use_Z_ions = 0
-use_NL_ions = 0
 
 output_sqtstream = 0
 output_sqtfile = 0
@@ -93,13 +92,33 @@ const COMET_PARAMS_END: &'static str = "
 10. Chymotrypsin           1      FWYL        P
 ";
 
-pub fn new(comet_revision: &str, fix_modifications_map: &HashMap<char, Modification>, variable_modifications_map: &HashMap<char, Modification>, fasta_file_path: &Path, number_of_target_and_decoys: usize, max_number_of_variable_modification_per_peptide: u8, fragmentation_tolerance: f64, lower_precursor_tolerance: i64, upper_precursor_tolerance: i64) -> String {
+/// Creates the Comet parameters. If `peff_format` is given, the database is a PEFF-file with the variable modifications at explicit positions,
+/// so no variable modifications are passed to Comet. Comet searches the annotated modifications as optional variants, so the unmodified forms are searched too.
+/// `mass_type` is used for the precursor and fragment masses as well as for the modification masses.
+/// Each label channel is searched on its own with the registry of the channel, which contains its labels as static modifications,
+/// see ModificationRegistry.get_channel_registry().
+pub fn new(comet_revision: &str, modification_registry: &ModificationRegistry, fasta_file_path: &Path, number_of_target_and_decoys: usize, max_number_of_variable_modification_per_peptide: u8, fragmentation_tolerance: f64, lower_precursor_tolerance: i64, upper_precursor_tolerance: i64, peff_format: Option<PeffFormat>, peff_obo: &str, mass_type: MassType) -> String {
+    if modification_registry.has_label_channels() {
+        panic!("proteomic::utility::comet_parameter::new(): label channels are searched separately, use the registry of a channel");
+    }
     let mut params = comet_revision.to_owned();
     params.push_str("\n");
     params.push_str(COMET_PARAMS_BEGIN);
+    match peff_format {
+        Some(peff_format) => {
+            params.push_str(format!("peff_format = {}\n", peff_format.get_comet_peff_format()).as_str());
+            params.push_str(format!("peff_obo = {}\n", peff_obo).as_str());
+        },
+        None => params.push_str("peff_format = 0\npeff_obo =\n")
+    }
+    params.push_str(format!("mass_type_parent = {}\n", mass_type.get_comet_mass_type()).as_str());
+    params.push_str(format!("mass_type_fragment = {}\n", mass_type.get_comet_mass_type()).as_str());
     // Comet has only one parameter for upper and lower precursor tolerance which is peptide_mass_tolerance. So we use the greatest of them.
     params.push_str(format!("peptide_mass_tolerance = {:.4}\n", std::cmp::max(lower_precursor_tolerance, upper_precursor_tolerance) as f64).as_str());
     params.push_str(format!("fragment_bin_tol = {}\n", fragmentation_tolerance).as_str());
+    // use_NL_ions enables NH3 and H2O losses for all fragments, so it is only set if one of them is a general neutral loss.
+    // Neutral losses of variable modifications are given in their parameter, see Modification.to_comet_variable_modification_param()
+    params.push_str(format!("use_NL_ions = {}\n", modification_registry.uses_comet_neutral_loss_ions() as u8).as_str());
     params.push_str(format!("num_results = {}\n", number_of_target_and_decoys).as_str());
     params.push_str(format!("num_output_lines = {}\n", number_of_target_and_decoys).as_str());
     let fasta_file_path_as_str = match fasta_file_path.as_os_str().to_str() {
@@ -107,17 +126,32 @@ pub fn new(comet_revision: &str, fix_modifications_map: &HashMap<char, Modificat
         None => panic!("proteomic::utility::comet_parameter::new(): No FASTA-file path")
     };
     params.push_str(format!("database_name = {}\n", fasta_file_path_as_str).as_str());
+    let fix_modifications_map = modification_registry.get_fixed_modification_map();
     for (_, modification) in fix_modifications_map {
-        params.push_str(modification.to_comet_static_modification_param().as_str());
+        params.push_str(modification.to_comet_static_modification_param(mass_type).as_str());
         params.push_str("\n");
     }
-    if !fix_modifications_map.contains_key(&'J') { params.push_str("add_J_user_amino_acid = 113.08406\n"); }
-    let mut modification_number: u8 = 1;
-    for (_, modification) in variable_modifications_map {
-        params.push_str(modification.to_comet_variable_modification_param(modification_number, max_number_of_variable_modification_per_peptide).as_str());
+    if !fix_modifications_map.contains_key(&'J') { params.push_str(format!("add_J_user_amino_acid = {}\n", mass::convert_mass_to_float(AminoAcid::get('J').get_mass(mass_type))).as_str()); }
+    // Comet has 9 variable modification slots, modifications with the same mass and position share a slot, e.g. phosphorylation of STY
+    let mut variable_modifications: Vec<&Modification> = modification_registry.get_sorted_variable_modifications();
+    let mut variable_modification_slots: Vec<(&Modification, String)> = Vec::new();
+    if peff_format.is_some() {
+        variable_modifications.clear();
+    }
+    for modification in variable_modifications {
+        match variable_modification_slots.iter_mut().find(|slot| (slot.0.get_mass(mass_type) == modification.get_mass(mass_type)) & (slot.0.get_position() == modification.get_position()) & (slot.0.get_comet_neutral_loss(mass_type) == modification.get_comet_neutral_loss(mass_type))) {
+            Some(slot) => if !slot.1.contains(modification.get_amino_acid_one_letter_code()) {
+                slot.1.push(modification.get_amino_acid_one_letter_code())
+            },
+            None => variable_modification_slots.push((modification, modification.get_amino_acid_one_letter_code().to_string()))
+        }
+    }
+    if variable_modification_slots.len() > 9 {
+        println!("WARNING: Comet supports only 9 variable modifications, {} variable modifications with different masses or positions are omitted", variable_modification_slots.len() - 9);
+    }
+    for (idx, slot) in variable_modification_slots.iter().take(9).enumerate() {
+        params.push_str(slot.0.to_comet_variable_modification_param(idx as u8 + 1, slot.1.as_str(), max_number_of_variable_modification_per_peptide, mass_type).as_str());
         params.push_str("\n");
-        if modification_number == 9 { break; }
-        modification_number += 1;
     }
     params.push_str(COMET_PARAMS_END);
     return params;
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use proteomic::models::mass::neutral_loss::NeutralLoss;
+use proteomic::models::mass::mass_type::MassType;
+use proteomic::models::amino_acids::amino_acid::AminoAcid;
+use proteomic::models::amino_acids::amino_acid::AMINO_ACIDS_FOR_DECOY_GENERATION;
+use proteomic::models::amino_acids::modification::{Modification, ModificationPosition, ANY_AMINO_ACID};
+use proteomic::models::peptides::modified_peptide::{ModifiedPeptide, ModifiedPeptideError};
+use proteomic::utility::amino_acid_composition::AminoAcidComposition;
+
+/// Default width of a mass bin, 0.001 Da
+pub const DEFAULT_MASS_RESOLUTION: i64 = 1000;
+/// Maximum length of a sequence, limited by the aa_sequence-column of the database
+const MAX_SEQUENCE_LENGTH: usize = 60;
+/// Number of compositions which are drawn for one decoy, before giving up
+const MAX_DRAWS_PER_DECOY: usize = 100;
+
+/// A building block of a composition, which is an amino acid with its fixed modification (if it is not terminal)
+/// or with one of its variable modifications.
+struct CompositionResidue {
+    amino_acid_one_letter_code: char,
+    variable_modification: Option<Modification>,
+    binned_mass: usize,
+    weight: f64
+}
+
+/// Solver for amino acid compositions whose mass lies inside a precursor tolerance window.
+///
+/// The masses of the residues are binned with `mass_resolution` and a dynamic programming table holds
+/// the number of sequences for each binned mass up to the upper tolerance limit. A decoy is drawn
+/// by picking a bin inside the tolerance window and walking the table backwards, so every sequence of
+/// the window has the same probability. The drawn composition is permuted and its exact mass is checked
+/// against the tolerance window, because the binning introduces small rounding errors.
+/// Terminal modifications are not part of the compositions. A fixed peptide terminal modification of any amino acid (e.g. TMT)
+/// is carried by every decoy, so its mass is subtracted from the window. A drawn decoy which does not fit due to
+/// terminal modifications of specific amino acids is rejected by the exact check.
+/// If an amino acid composition is given, each sequence is weighted by the product of the frequencies of its amino acids.
+pub struct CompositionSolver {
+    residues: Vec<CompositionResidue>,
+    lower_bin: usize,
+    upper_bin: usize,
+    number_of_sequences: Vec<f64>,
+    max_number_of_variable_modifications: u8,
+    mass_type: MassType
+}
+
+impl CompositionSolver {
+    /// Creates a new solver for the given tolerance window
+    ///
+    /// # Arguments
+    ///
+    /// * `lower_weight_limit` - Lower precursor tolerance limit
+    /// * `upper_weight_limit` - Upper precursor tolerance limit
+    /// * `fixed_modification_map` - Fixed modifications
+    /// * `variable_modification_map` - Variable modifications
+    /// * `max_number_of_variable_modifications` - Maximum number of variable modifications per sequence
+    /// * `mass_resolution` - Width of a mass bin
+    /// * `mass_type` - Mass type of the tolerance limits
+    pub fn new(lower_weight_limit: i64, upper_weight_limit: i64, fixed_modification_map: &HashMap<char, Modification>, variable_modification_map: &HashMap<char, Vec<Modification>>, max_number_of_variable_modifications: u8, mass_resolution: i64, mass_type: MassType) -> Self {
+        return Self::new_weighted(lower_weight_limit, upper_weight_limit, fixed_modification_map, variable_modification_map, max_number_of_variable_modifications, mass_resolution, mass_type, None);
+    }
+
+    /// Creates a new solver, which draws sequences biased towards the given amino acid composition
+    ///
+    /// # Arguments
+    ///
+    /// * `lower_weight_limit` - Lower precursor tolerance limit
+    /// * `upper_weight_limit` - Upper precursor tolerance limit
+    /// * `fixed_modification_map` - Fixed modifications
+    /// * `variable_modification_map` - Variable modifications
+    /// * `max_number_of_variable_modifications` - Maximum number of variable modifications per sequence
+    /// * `mass_resolution` - Width of a mass bin
+    /// * `mass_type` - Mass type of the tolerance limits
+    /// * `amino_acid_composition` - Composition the drawn sequences should follow, all amino acids are equally likely if None
+    pub fn new_weighted(lower_weight_limit: i64, upper_weight_limit: i64, fixed_modification_map: &HashMap<char, Modification>, variable_modification_map: &HashMap<char, Vec<Modification>>, max_number_of_variable_modifications: u8, mass_resolution: i64, mass_type: MassType, amino_acid_composition: Option<&AminoAcidComposition>) -> Self {
+        // every decoy carries the water and the fixed terminal modifications of any amino acid
+        let fixed_mass: i64 = NeutralLoss::get("H2O").get_mass(mass_type) + Self::get_fixed_terminal_mass_shift(fixed_modification_map, mass_type);
+        let mut residues: Vec<CompositionResidue> = Vec::new();
+        for aa_one_letter_code in AMINO_ACIDS_FOR_DECOY_GENERATION {
+            let mut amino_acid_mass: i64 = AminoAcid::get(*aa_one_letter_code).get_mass(mass_type);
+            let mut has_fixed_modification = false;
+            // normalize the frequencies, so the weights of a uniform composition are 1
+            let weight: f64 = match amino_acid_composition {
+                Some(composition) => composition.get_frequency(*aa_one_letter_code) * AMINO_ACIDS_FOR_DECOY_GENERATION.len() as f64,
+                None => 1.0
+            };
+            if let Some(modification) = fixed_modification_map.get(aa_one_letter_code) {
+                if modification.get_position() == ModificationPosition::Anywhere {
+                    amino_acid_mass += modification.get_mass(mass_type);
+                    has_fixed_modification = true;
+                }
+            }
+            residues.push(CompositionResidue {
+                amino_acid_one_letter_code: *aa_one_letter_code,
+                variable_modification: None,
+                binned_mass: Self::bin_mass(amino_acid_mass, mass_resolution),
+                weight: weight
+            });
+            // variable modifications are only possible if no fixed modification is in place
+            if (max_number_of_variable_modifications > 0) & !has_fixed_modification {
+                if let Some(modifications) = variable_modification_map.get(aa_one_letter_code) {
+                    for modification in modifications.iter() {
+                        if modification.get_position() == ModificationPosition::Anywhere {
+                            residues.push(CompositionResidue {
+                                amino_acid_one_letter_code: *aa_one_letter_code,
+                                variable_modification: Some(modification.clone()),
+                                binned_mass: Self::bin_mass(amino_acid_mass + modification.get_mass(mass_type), mass_resolution),
+                                weight: weight
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        // each residue mass is rounded by up to half a bin, so the window is widened by half a bin per residue of the longest possible sequence.
+        // compositions which are outside the exact window are rejected by `draw_decoy()`
+        let lightest_binned_mass: usize = residues.iter().map(|residue| residue.binned_mass).min().unwrap_or(1);
+        let max_length: usize = std::cmp::min(Self::bin_mass(std::cmp::max(upper_weight_limit - fixed_mass, 0), mass_resolution) / lightest_binned_mass + 1, MAX_SEQUENCE_LENGTH);
+        let rounding_margin: i64 = (max_length / 2 + 1) as i64;
+        let lower_bin: usize = std::cmp::max((lower_weight_limit - fixed_mass) / mass_resolution - rounding_margin, 0) as usize;
+        let upper_bin: usize = std::cmp::max((upper_weight_limit - fixed_mass) / mass_resolution + 1 + rounding_margin, 0) as usize;
+        let mut number_of_sequences: Vec<f64> = vec![0.0; upper_bin + 1];
+        number_of_sequences[0] = 1.0;
+        for binned_mass in 1..=upper_bin {
+            let mut sequences_for_bin: f64 = 0.0;
+            for residue in residues.iter() {
+                if residue.binned_mass <= binned_mass {
+                    sequences_for_bin += residue.weight * number_of_sequences[binned_mass - residue.binned_mass];
+                }
+            }
+            number_of_sequences[binned_mass] = sequences_for_bin;
+        }
+        return Self {
+            residues: residues,
+            lower_bin: lower_bin,
+            upper_bin: upper_bin,
+            number_of_sequences: number_of_sequences,
+            max_number_of_variable_modifications: max_number_of_variable_modifications,
+            mass_type: mass_type
+        };
+    }
+
+    /// Returns the mass of the fixed peptide terminal modifications of any amino acid, which are applied to every decoy.
+    /// Protein terminal modifications are ignored, because a decoy is never a protein terminus.
+    pub fn get_fixed_terminal_mass_shift(fixed_modification_map: &HashMap<char, Modification>, mass_type: MassType) -> i64 {
+        return match fixed_modification_map.get(&ANY_AMINO_ACID) {
+            Some(modification) => match modification.get_position() {
+                ModificationPosition::NTerminus | ModificationPosition::CTerminus => modification.get_mass(mass_type),
+                _ => 0
+            },
+            None => 0
+        };
+    }
+
+    fn bin_mass(mass: i64, mass_resolution: i64) -> usize {
+        // round to nearest bin, each residue needs at least one bin
+        return std::cmp::max((mass + mass_resolution / 2) / mass_resolution, 1) as usize;
+    }
+
+    /// Returns the number of sequences (with respect to the binning and weighted by the composition) which fall into the tolerance window
+    pub fn get_number_of_sequences_in_window(&self) -> f64 {
+        return self.number_of_sequences[self.lower_bin..=self.upper_bin].iter().sum();
+    }
+
+    /// Returns true if at least one composition falls into the tolerance window
+    pub fn has_solutions(&self) -> bool {
+        return self.get_number_of_sequences_in_window() > 0.0;
+    }
+
+    /// Picks an index with a probability proportional to its weight.
+    fn pick_weighted<R: Rng>(rng: &mut R, weights: &Vec<(usize, f64)>) -> usize {
+        let total_weight: f64 = weights.iter().map(|weight| weight.1).sum();
+        let mut threshold: f64 = rng.gen::<f64>() * total_weight;
+        for weight in weights.iter() {
+            if threshold < weight.1 {
+                return weight.0;
+            }
+            threshold -= weight.1;
+        }
+        // floating point rounding might leave a small remainder, so return the last index with a weight
+        return match weights.iter().filter(|weight| weight.1 > 0.0).last() {
+            Some(weight) => weight.0,
+            None => weights[weights.len() - 1].0
+        };
+    }
+
+    /// Draws a composition from the tolerance window and returns it as a random permutation of residue indices.
+    /// Returns None if the window contains no composition.
+    fn draw_permutation<R: Rng>(&self, rng: &mut R) -> Option<Vec<usize>> {
+        let bin_weights: Vec<(usize, f64)> = (self.lower_bin..=self.upper_bin).map(|binned_mass| (binned_mass, self.number_of_sequences[binned_mass])).collect();
+        if bin_weights.iter().all(|weight| weight.1 == 0.0) {
+            return None;
+        }
+        let mut binned_mass: usize = Self::pick_weighted(rng, &bin_weights);
+        let mut composition: Vec<usize> = Vec::new();
+        while binned_mass > 0 {
+            let residue_weights: Vec<(usize, f64)> = self.residues.iter().enumerate().map(
+                |(idx, residue)| if residue.binned_mass <= binned_mass {
+                    (idx, residue.weight * self.number_of_sequences[binned_mass - residue.binned_mass])
+                } else {
+                    (idx, 0.0)
+                }
+            ).collect();
+            let residue_idx: usize = Self::pick_weighted(rng, &residue_weights);
+            composition.push(residue_idx);
+            binned_mass -= self.residues[residue_idx].binned_mass;
+        }
+        composition.shuffle(rng);
+        return Some(composition);
+    }
+
+    /// Draws a decoy which hits the precursor tolerance.
+    /// Returns None if no composition is found within a limited number of draws.
+    pub fn draw_decoy<R: Rng>(&self, rng: &mut R, precursor_mass: i64, lower_weight_limit: i64, upper_weight_limit: i64, fixed_modification_map: &HashMap<char, Modification>) -> Option<ModifiedPeptide> {
+        'draws: for _ in 0..MAX_DRAWS_PER_DECOY {
+            let composition: Vec<usize> = match self.draw_permutation(rng) {
+                Some(composition) => composition,
+                None => return None
+            };
+            if (composition.len() == 0) | (composition.len() > MAX_SEQUENCE_LENGTH) {
+                continue 'draws;
+            }
+            let number_of_variable_modifications = composition.iter().filter(|residue_idx| self.residues[**residue_idx].variable_modification.is_some()).count();
+            if number_of_variable_modifications > self.max_number_of_variable_modifications as usize {
+                continue 'draws;
+            }
+            let mut decoy: ModifiedPeptide = ModifiedPeptide::new_decoy(precursor_mass, lower_weight_limit, upper_weight_limit);
+            decoy.set_mass_type(self.mass_type);
+            for residue_idx in composition.iter() {
+                let amino_acid_one_letter_code = self.residues[*residue_idx].amino_acid_one_letter_code;
+                let modification_option = match fixed_modification_map.get(&amino_acid_one_letter_code) {
+                    Some(modification) => Some(modification.clone()),
+                    None => None
+                };
+                match decoy.push_amino_acid_and_fix_modification(&AminoAcid::get(amino_acid_one_letter_code), &modification_option) {
+                    Ok(_) => (),
+                    Err(err) => panic!("proteomic::utility::composition_solver::CompositionSolver.draw_decoy(): Error at decoy.push_amino_acid_and_fix_modification: {}", err)
+                }
+            }
+            decoy.apply_fixed_terminal_modifications(fixed_modification_map);
+            for (idx, residue_idx) in composition.iter().enumerate() {
+                if let Some(ref modification) = self.residues[*residue_idx].variable_modification {
+                    match decoy.set_variable_modification_at(idx, modification) {
+                        Ok(_) => (),
+                        Err(err) => match err {
+                            ModifiedPeptideError::AlreadyFixModificationInPlace => continue 'draws,
+                            _ => panic!("proteomic::utility::composition_solver::CompositionSolver.draw_decoy(): Error at decoy.set_variable_modification_at: {}", err)
+                        }
+                    }
+                }
+            }
+            if decoy.hits_mass_tolerance() {
+                return Some(decoy);
+            }
+        }
+        return None;
+    }
+}
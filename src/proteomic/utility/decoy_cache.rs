@@ -0,0 +1,75 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use proteomic::models::fasta_entry::FastaEntry;
+
+/// Run-level cache of the decoys which were used for the spectra of an identification,
+/// keyed by the mass of the decoy including the modifications which let it hit a precursor tolerance.
+/// Later spectra take decoys from the cache whose mass is within their precursor tolerance.
+/// A cached decoy is reused by at most `max_reuse` later spectra, so the decoy sets of neighbouring spectra stay sufficiently independent.
+/// The cap applies only to reuse from the cache, decoys which a spectrum finds in the database or generates itself are not restricted.
+pub struct DecoyCache {
+    decoys_by_mass: BTreeMap<i64, Vec<FastaEntry>>,
+    cached_decoys: HashSet<(i64, String)>,  // (mass, aa_sequence) of all cached decoys, to prevent duplicates
+    usages: HashMap<String, usize>,         // number of spectra which used the aa_sequence
+    max_reuse: usize
+}
+
+impl DecoyCache {
+    pub fn new(max_reuse: usize) -> Self {
+        return Self {
+            decoys_by_mass: BTreeMap::new(),
+            cached_decoys: HashSet::new(),
+            usages: HashMap::new(),
+            max_reuse: max_reuse
+        };
+    }
+
+    pub fn get_max_reuse(&self) -> usize {
+        return self.max_reuse;
+    }
+
+    pub fn get_number_of_decoys(&self) -> usize {
+        return self.cached_decoys.len();
+    }
+
+    /// Adds a decoy with its modified mass. The header should contain the modifications.
+    pub fn insert(&mut self, mass: i64, header: &str, aa_sequence: &str) {
+        if self.cached_decoys.insert((mass, aa_sequence.to_owned())) {
+            self.decoys_by_mass.entry(mass).or_insert(Vec::new()).push(FastaEntry::new(header, aa_sequence));
+        }
+    }
+
+    /// Returns true if the cached decoy may be reused, i.e. it was used by less than `1 + max_reuse` spectra
+    pub fn can_use(&self, aa_sequence: &str) -> bool {
+        return match self.usages.get(aa_sequence) {
+            Some(usages) => *usages <= self.max_reuse,
+            None => true
+        };
+    }
+
+    /// Counts a usage of the decoy. Should be called once per spectrum for each decoy in its final decoy set.
+    pub fn record_usage(&mut self, aa_sequence: &str) {
+        *self.usages.entry(aa_sequence.to_owned()).or_insert(0) += 1;
+    }
+
+    /// Returns up to `number_of_decoys` usable decoys with a mass between the given limits (inclusive), ordered by mass.
+    /// Usages are not counted here, because the decoys may be rejected afterwards, e.g. by the similarity filter.
+    pub fn get_decoys_in_window(&self, lower_mass_limit: i64, upper_mass_limit: i64, number_of_decoys: usize) -> Vec<FastaEntry> {
+        let mut decoys: Vec<FastaEntry> = Vec::new();
+        let mut aa_sequences: HashSet<&str> = HashSet::new();
+        if lower_mass_limit > upper_mass_limit {
+            return decoys;
+        }
+        for (_, decoys_with_mass) in self.decoys_by_mass.range(lower_mass_limit..=upper_mass_limit) {
+            for decoy in decoys_with_mass.iter() {
+                if decoys.len() >= number_of_decoys {
+                    return decoys;
+                }
+                if self.can_use(decoy.get_aa_sequence()) && aa_sequences.insert(decoy.get_aa_sequence()) {
+                    decoys.push(decoy.clone());
+                }
+            }
+        }
+        return decoys;
+    }
+}
@@ -1,11 +1,10 @@
-use std::collections::{HashSet, HashMap};
+use std::collections::{HashSet, HashMap, BTreeMap};
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
 use std::thread;
 use std::time;
 
-use time as better_time;
-
 use rand::prelude::*;
 use threadpool::ThreadPool;
 
@@ -13,19 +12,221 @@ use proteomic::utility::database_connection::DatabaseConnection;
 use proteomic::models::peptides::peptide_interface::PeptideInterface;
 use proteomic::models::peptides::peptide::Peptide;
 use proteomic::models::peptides::decoy::Decoy;
+use proteomic::models::peptides::decoy_provenance::DecoyProvenance;
 use proteomic::models::persistable::{Persistable, QueryOk, QueryError};
 use proteomic::models::peptides::modified_peptide::{ModifiedPeptide as NewDecoy, PushAminoAcidOk};
 use proteomic::models::amino_acids::amino_acid::AminoAcid;
 use proteomic::models::amino_acids::amino_acid::AMINO_ACIDS_FOR_DECOY_GENERATION;
 use proteomic::models::amino_acids::modification::Modification;
+use proteomic::utility::composition_solver::{CompositionSolver, DEFAULT_MASS_RESOLUTION};
+use proteomic::utility::length_distribution::LengthDistribution;
+use proteomic::utility::similarity_filter::SimilarityFilter;
+use proteomic::utility::property_filter::PropertyFilter;
+use proteomic::utility::amino_acid_composition::AminoAcidComposition;
+use proteomic::utility::target_index::TargetIndex;
+use proteomic::utility::generation_statistics::{GenerationCounters, GenerationStatistics};
+use proteomic::utility::mass_fitting::{MassFittingOptions, TwoAminoAcidSubstitutionTable, DEFAULT_MAX_ITERATIONS};
+use proteomic::models::mass::neutral_loss::NeutralLoss;
+use proteomic::models::mass::mass_type::MassType;
 
 
-const REPORT_INTERVALL: i64 = 20;   // report all 20 seconds
+const REPORT_INTERVALL: u64 = 20;   // report all 20 seconds
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
 
-#[derive(PartialEq)]
-pub enum GenerationResult {
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GenerationStatus {
     Success,
-    Timeout
+    Timeout,
+    NoSolution,         // no amino acid composition hits the precursor tolerance
+    LookupShortfall     // lookup only, the database and the cache hold too few decoys
+}
+
+impl GenerationStatus {
+    pub fn to_string(&self) -> String {
+        return match self {
+            GenerationStatus::Success => "success".to_string(),
+            GenerationStatus::Timeout => "timeout".to_string(),
+            GenerationStatus::NoSolution => "no-solution".to_string(),
+            GenerationStatus::LookupShortfall => "lookup-shortfall".to_string()
+        }
+    }
+}
+
+/// Status and statistics of a generation run
+pub struct GenerationResult {
+    status: GenerationStatus,
+    statistics: GenerationStatistics
+}
+
+impl GenerationResult {
+    pub fn new(status: GenerationStatus, statistics: GenerationStatistics) -> Self {
+        return Self {
+            status: status,
+            statistics: statistics
+        };
+    }
+
+    pub fn get_status(&self) -> GenerationStatus {
+        return self.status;
+    }
+
+    pub fn is_success(&self) -> bool {
+        return self.status == GenerationStatus::Success;
+    }
+
+    pub fn get_statistics(&self) -> &GenerationStatistics {
+        return &self.statistics;
+    }
+}
+
+/// Strategy for building decoys
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GenerationStrategy {
+    RandomWalk,         // add random amino acids until the mass is exceeded, afterwards swap amino acids to hit the precursor tolerance
+    Composition         // draw amino acid compositions which hit the precursor tolerance, see CompositionSolver
+}
+
+impl GenerationStrategy {
+    pub fn to_string(&self) -> String {
+        return match self {
+            GenerationStrategy::RandomWalk => "random-walk".to_string(),
+            GenerationStrategy::Composition => "composition".to_string()
+        }
+    }
+
+    pub fn from_str(strategy_name: &str) -> GenerationStrategy {
+        return match strategy_name.to_lowercase().as_str() {
+            "random-walk" => GenerationStrategy::RandomWalk,
+            "composition" => GenerationStrategy::Composition,
+            _ => panic!("proteomic::utility::decoy_generator::GenerationStrategy::from_str(): Unknown generation strategy '{}', use 'random-walk' or 'composition'", strategy_name)
+        }
+    }
+}
+
+/// Decides if a sequence is a target, e.g. a lookup in an in-memory set of the target sequences.
+/// Closures `Fn(&str) -> bool` are target memberships too.
+pub trait TargetMembership: Send + Sync {
+    fn is_target(&self, aa_sequence: &str) -> bool;
+}
+
+impl<F> TargetMembership for F where F: Fn(&str) -> bool + Send + Sync {
+    fn is_target(&self, aa_sequence: &str) -> bool {
+        return self(aa_sequence);
+    }
+}
+
+/// Where a worker looks up targets and keeps its decoys
+enum WorkerStorage {
+    InMemory(Arc<dyn TargetMembership>),    // targets are recognized by the target membership, decoys are only kept in memory
+    Database(postgres::Connection)          // targets are looked up in the database, decoys are saved with their provenance
+}
+
+/// State of a single worker thread
+struct WorkerState {
+    thread_id: usize,
+    decoys: HashSet<String>,                        // sequences of the decoys generated by this worker
+    previous_decoys: Arc<HashSet<String>>,          // sequences of the decoys of the previous rounds of all workers
+    length_quotas: Option<Vec<(usize, usize)>>,     // (length, quota) sorted by length, if the decoys should match a length distribution
+    length_counts: BTreeMap<usize, usize>,
+    similarity_filter: Option<Arc<SimilarityFilter>>,
+    property_filter: Option<Arc<PropertyFilter>>,
+    target_index: Option<Arc<TargetIndex>>,
+    storage: WorkerStorage,
+    provenance: DecoyProvenance,                    // template for the provenance of each generated decoy
+    counters: Arc<GenerationCounters>               // shared counters of all workers
+}
+
+impl WorkerState {
+    fn new(thread_id: usize, previous_decoys: Arc<HashSet<String>>, length_quotas: Option<Vec<(usize, usize)>>, similarity_filter: Option<Arc<SimilarityFilter>>, property_filter: Option<Arc<PropertyFilter>>, target_index: Option<Arc<TargetIndex>>, storage: WorkerStorage, provenance: DecoyProvenance, counters: Arc<GenerationCounters>) -> Self {
+        return Self {
+            thread_id: thread_id,
+            decoys: HashSet::new(),
+            previous_decoys: previous_decoys,
+            length_quotas: length_quotas,
+            length_counts: BTreeMap::new(),
+            similarity_filter: similarity_filter,
+            property_filter: property_filter,
+            target_index: target_index,
+            storage: storage,
+            provenance: provenance,
+            counters: counters
+        };
+    }
+
+    /// Returns true if the decoy is a target. The target membership or database is only asked if the target index might contain the decoy.
+    fn is_peptide(&self, decoy: &Decoy) -> bool {
+        if let Some(ref target_index) = self.target_index {
+            if !target_index.might_contain(decoy.get_aa_sequence()) {
+                return false;
+            }
+        }
+        return match self.storage {
+            WorkerStorage::InMemory(ref target_membership) => target_membership.is_target(decoy.get_aa_sequence()),
+            WorkerStorage::Database(ref conn) => decoy.is_peptide(conn)
+        };
+    }
+
+    /// Returns true if a decoy with the given sequence would be a new decoy for this worker and was not generated in a previous round,
+    /// its length quota is not filled yet and it is not too similar to a target and resembles the targets' properties. Rejections are counted.
+    fn accepts(&self, aa_sequence: &str) -> bool {
+        if self.decoys.contains(aa_sequence) {
+            GenerationCounters::increment(&self.counters.duplicates);
+            return false;
+        }
+        if self.previous_decoys.contains(aa_sequence) {
+            GenerationCounters::increment(&self.counters.cross_worker_duplicates);
+            return false;
+        }
+        if let Some(ref quotas) = self.length_quotas {
+            if !LengthDistribution::has_remaining_quota(quotas, &self.length_counts, aa_sequence.len()) {
+                GenerationCounters::increment(&self.counters.length_quota_rejections);
+                return false;
+            }
+        }
+        if let Some(ref similarity_filter) = self.similarity_filter {
+            if similarity_filter.rejects(aa_sequence) {
+                GenerationCounters::increment(&self.counters.similarity_rejections);
+                return false;
+            }
+        }
+        if let Some(ref property_filter) = self.property_filter {
+            if property_filter.rejects(aa_sequence) {
+                GenerationCounters::increment(&self.counters.property_rejections);
+                return false;
+            }
+        }
+        return true;
+    }
+
+    /// Counts a candidate which hits the precursor tolerance, depending on how it hit the tolerance
+    fn count_hit(&self, new_decoy: &NewDecoy, after_swaps: bool) {
+        if new_decoy.has_variable_modifications() {
+            GenerationCounters::increment(&self.counters.hits_after_variable_modifications);
+        } else if after_swaps {
+            GenerationCounters::increment(&self.counters.hits_after_swaps);
+        } else {
+            GenerationCounters::increment(&self.counters.direct_hits);
+        }
+    }
+
+    fn add_decoy(&mut self, aa_sequence: &str) {
+        *self.length_counts.entry(aa_sequence.len()).or_insert(0) += 1;
+        self.decoys.insert(aa_sequence.to_owned());
+    }
+}
+
+/// Notifies the main thread when a worker is finished, also if the worker panics
+struct WorkerFinishedNotifier {
+    thread_id: usize,
+    sender: mpsc::Sender<usize>
+}
+
+impl Drop for WorkerFinishedNotifier {
+    fn drop(&mut self) {
+        // the receiver is gone if the main thread stopped waiting, so an error can be ignored
+        let _ = self.sender.send(self.thread_id);
+    }
 }
 
 pub struct DecoyGenerator {
@@ -35,15 +236,27 @@ pub struct DecoyGenerator {
     thread_count: usize,
     max_modifications_per_decoy: u8,
     fixed_modification_map: Arc<HashMap<char, Modification>>,
-    variable_modification_map: Arc<HashMap<char, Modification>>,
+    variable_modification_map: Arc<HashMap<char, Vec<Modification>>>,
     one_amino_acid_substitute_map: Arc<HashMap<char, HashMap<char, i64>>>,
+    two_amino_acid_substitution_table: Arc<TwoAminoAcidSubstitutionTable>,
+    mass_fitting_options: MassFittingOptions,
+    mass_type: MassType,
     decoys: Arc<Mutex<HashSet<Decoy>>>,
+    counters: Arc<GenerationCounters>,
     max_time_for_decoy_generation: i64,
-    timeout: Arc<AtomicBool>
+    timeout: Arc<AtomicBool>,
+    seed: u64,
+    generation_strategy: GenerationStrategy,
+    length_distribution: Option<Arc<LengthDistribution>>,
+    similarity_filter: Option<Arc<SimilarityFilter>>,
+    property_filter: Option<Arc<PropertyFilter>>,
+    amino_acid_composition: Option<Arc<AminoAcidComposition>>,
+    target_index: Option<Arc<TargetIndex>>,
+    target_membership: Option<Arc<dyn TargetMembership>>
 }
 
 impl DecoyGenerator {
-    pub fn new(precursor_mass: i64, lower_precursor_tolerance_limit: i64, upper_precursor_tolerance_limit: i64, thread_count: usize, max_modifications_per_decoy: u8, fixed_modification_map: &HashMap<char, Modification>, variable_modification_map: &HashMap<char, Modification>, max_time_for_decoy_generation: i64) -> Self {
+    pub fn new(precursor_mass: i64, lower_precursor_tolerance_limit: i64, upper_precursor_tolerance_limit: i64, thread_count: usize, max_modifications_per_decoy: u8, fixed_modification_map: &HashMap<char, Modification>, variable_modification_map: &HashMap<char, Vec<Modification>>, max_time_for_decoy_generation: i64) -> Self {
         return DecoyGenerator{
             precursor_mass: precursor_mass,
             lower_precursor_tolerance_limit: lower_precursor_tolerance_limit,
@@ -52,11 +265,143 @@ impl DecoyGenerator {
             max_modifications_per_decoy: max_modifications_per_decoy,
             fixed_modification_map: Arc::new(fixed_modification_map.clone()),
             variable_modification_map: Arc::new(variable_modification_map.clone()),
-            one_amino_acid_substitute_map: Arc::new(*Self::get_one_amino_acid_substitute_map(fixed_modification_map)),
+            one_amino_acid_substitute_map: Arc::new(*Self::get_one_amino_acid_substitute_map(fixed_modification_map, MassType::Monoisotopic)),
+            two_amino_acid_substitution_table: Arc::new(TwoAminoAcidSubstitutionTable::new(fixed_modification_map, MassType::Monoisotopic)),
+            mass_fitting_options: MassFittingOptions::new(DEFAULT_MAX_ITERATIONS),
+            mass_type: MassType::Monoisotopic,
             decoys: Arc::new(Mutex::new(HashSet::new())),
+            counters: Arc::new(GenerationCounters::new()),
             max_time_for_decoy_generation: max_time_for_decoy_generation,
-            timeout: Arc::new(AtomicBool::new(true))
+            timeout: Arc::new(AtomicBool::new(true)),
+            seed: rand::random::<u64>(),
+            generation_strategy: GenerationStrategy::RandomWalk,
+            length_distribution: None,
+            similarity_filter: None,
+            property_filter: None,
+            amino_acid_composition: None,
+            target_index: None,
+            target_membership: None
+        }
+    }
+
+    /// Sets a length distribution the generated decoys should follow, e.g. the lengths of the targets.
+    /// Lengths which can not hit the precursor tolerance are ignored.
+    pub fn set_length_distribution(&mut self, length_distribution: LengthDistribution) {
+        self.length_distribution = Some(Arc::new(length_distribution));
+    }
+
+    /// Sets the iteration budget and the moves for fitting random walk decoys into the precursor tolerance, length changes are ignored while a length distribution is set.
+    pub fn set_mass_fitting_options(&mut self, mass_fitting_options: MassFittingOptions) {
+        self.mass_fitting_options = mass_fitting_options;
+    }
+
+    /// Sets the mass type of the precursor mass and tolerance limits, which is used for fitting the decoys into the precursor tolerance.
+    pub fn set_mass_type(&mut self, mass_type: MassType) {
+        self.mass_type = mass_type;
+        self.one_amino_acid_substitute_map = Arc::new(*Self::get_one_amino_acid_substitute_map(self.fixed_modification_map.as_ref(), mass_type));
+        self.two_amino_acid_substitution_table = Arc::new(TwoAminoAcidSubstitutionTable::new(self.fixed_modification_map.as_ref(), mass_type));
+    }
+
+    pub fn get_mass_type(&self) -> MassType {
+        return self.mass_type;
+    }
+
+    /// Sets a filter which rejects decoys which are too similar to the targets.
+    /// The filter is shared, so its rejection counts can be read after the generation.
+    pub fn set_similarity_filter(&mut self, similarity_filter: Arc<SimilarityFilter>) {
+        self.similarity_filter = Some(similarity_filter);
+    }
+
+    /// Sets a filter which rejects decoys whose physicochemical properties are outside the spread of the targets
+    pub fn set_property_filter(&mut self, property_filter: Arc<PropertyFilter>) {
+        self.property_filter = Some(property_filter);
+    }
+
+    /// Sets an amino acid composition, e.g. of the targets, which the amino acids of the decoys should follow.
+    pub fn set_amino_acid_composition(&mut self, amino_acid_composition: AminoAcidComposition) {
+        self.amino_acid_composition = Some(Arc::new(amino_acid_composition));
+    }
+
+    /// Sets an index of the target sequences, so the database is only asked if a decoy might be a target.
+    pub fn set_target_index(&mut self, target_index: Arc<TargetIndex>) {
+        self.target_index = Some(target_index);
+    }
+
+    /// Generates the decoys without a database: targets are recognized by the given target membership
+    /// and the decoys are only kept in memory, see `get_decoys()`. Without a target membership each worker opens a database connection,
+    /// looks up targets in the peptides-table and saves the decoys and their provenance.
+    pub fn set_target_membership(&mut self, target_membership: Arc<dyn TargetMembership>) {
+        self.target_membership = Some(target_membership);
+    }
+
+    /// Returns the minimal and maximal length a sequence can have to hit the precursor tolerance,
+    /// by considering the lightest and heaviest amino acid including their modifications.
+    fn get_feasible_length_range(&self) -> (usize, usize) {
+        let mut lightest_residue_mass: i64 = i64::max_value();
+        let mut heaviest_residue_mass: i64 = 0;
+        for aa_one_letter_code in AMINO_ACIDS_FOR_DECOY_GENERATION {
+            let mut residue_mass: i64 = AminoAcid::get(*aa_one_letter_code).get_mass(self.mass_type);
+            if let Some(modification) = self.fixed_modification_map.get(aa_one_letter_code) {
+                residue_mass += modification.get_mass(self.mass_type);
+            }
+            lightest_residue_mass = std::cmp::min(lightest_residue_mass, residue_mass);
+            heaviest_residue_mass = std::cmp::max(heaviest_residue_mass, residue_mass);
+            if self.max_modifications_per_decoy > 0 {
+                if let Some(modifications) = self.variable_modification_map.get(aa_one_letter_code) {
+                    for modification in modifications.iter() {
+                        lightest_residue_mass = std::cmp::min(lightest_residue_mass, residue_mass + modification.get_mass(self.mass_type));
+                        heaviest_residue_mass = std::cmp::max(heaviest_residue_mass, residue_mass + modification.get_mass(self.mass_type));
+                    }
+                }
+            }
         }
+        // every decoy carries the water and the fixed terminal modifications of any amino acid
+        let fixed_mass: i64 = NeutralLoss::get("H2O").get_mass(self.mass_type) + CompositionSolver::get_fixed_terminal_mass_shift(self.fixed_modification_map.as_ref(), self.mass_type);
+        let lower_residue_mass: i64 = std::cmp::max(self.lower_precursor_tolerance_limit - fixed_mass, 0);
+        let upper_residue_mass: i64 = std::cmp::max(self.upper_precursor_tolerance_limit - fixed_mass, 0);
+        return (
+            ((lower_residue_mass + heaviest_residue_mass - 1) / heaviest_residue_mass) as usize,
+            (upper_residue_mass / lightest_residue_mass) as usize
+        );
+    }
+
+    pub fn set_generation_strategy(&mut self, generation_strategy: GenerationStrategy) {
+        self.generation_strategy = generation_strategy;
+    }
+
+    pub fn get_generation_strategy(&self) -> GenerationStrategy {
+        return self.generation_strategy;
+    }
+
+    /// Sets the seed for the random number generators of the worker threads.
+    /// Each worker derives its own seed from this one, so the generated decoys are reproducible for the same seed, thread count and targets,
+    /// independent of decoys which are already in the database.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        return self.seed;
+    }
+
+    /// Derives a new seed from the given seed and a salt (e.g. a worker id), using the SplitMix64 finalizer
+    /// so seeds of neighbouring salts are not correlated.
+    pub fn derive_seed(seed: u64, salt: u64) -> u64 {
+        let mut derived_seed: u64 = seed.wrapping_add(salt.wrapping_add(1).wrapping_mul(0x9e3779b97f4a7c15));
+        derived_seed = (derived_seed ^ (derived_seed >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        derived_seed = (derived_seed ^ (derived_seed >> 27)).wrapping_mul(0x94d049bb133111eb);
+        return derived_seed ^ (derived_seed >> 31);
+    }
+
+    /// Derives a new seed from the given seed and a string (e.g. a spectrum id), using FNV-1a for hashing the string.
+    /// In contrast to the std hashers the FNV-1a hash is stable between Rust versions.
+    pub fn derive_seed_from_str(seed: u64, salt: &str) -> u64 {
+        let mut hash: u64 = FNV_OFFSET_BASIS;
+        for byte in salt.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+        return Self::derive_seed(seed, hash);
     }
 
     pub fn get_lower_precursor_tolerance_limit(&self) -> i64 {
@@ -71,6 +416,16 @@ impl DecoyGenerator {
         return &self.decoys;
     }
 
+    /// Returns the number of decoys generated by the last call of `generate_decoys()`, without locking the decoys
+    pub fn get_number_of_generated_decoys(&self) -> usize {
+        return self.counters.get_statistics().generated_decoys;
+    }
+
+    /// Returns the statistics of the last call of `generate_decoys()`
+    pub fn get_statistics(&self) -> GenerationStatistics {
+        return self.counters.get_statistics();
+    }
+
     // generate array which holds natural distribution of amino acids (published by UniProt)
     fn generate_amino_acid_distribution_array() -> Box<Vec<char>> {
         // create empty array
@@ -108,150 +463,311 @@ impl DecoyGenerator {
     pub fn generate_decoys(&self, number_of_decoys_to_generate: usize) -> GenerationResult {
         // set timeout to false before start
         self.timeout.store(false, Ordering::Relaxed);
-        // create threadpoll
-        let thread_pool = ThreadPool::new(self.thread_count);
-        // loop for starting threads
-        for thread_id in 0..self.thread_count {
-            // create copies of thread safe pointer of DecoyGenerator which can be move into thread
-            let fixed_modification_map_ptr = self.fixed_modification_map.clone();
-            let variable_modification_map_ptr = self.variable_modification_map.clone();
-            let one_amino_acid_substitute_map_ptr = self.one_amino_acid_substitute_map.clone();
-            let decoys_ptr = self.decoys.clone();
-            let timeout_ptr = self.timeout.clone();
-            // copy primitive attributes of DecoyGenerator which can be moved into thread
-            let precursor_mass = self.precursor_mass;
-            let upper_precursor_tolerance_limit = self.upper_precursor_tolerance_limit;
-            let lower_precursor_tolerance_limit = self.lower_precursor_tolerance_limit;
-            let max_modifications_per_decoy = self.max_modifications_per_decoy;
-            // start thread
-            thread_pool.execute(move||{
-                let conn: postgres::Connection = DatabaseConnection::get_database_connection();
-                // create random number generator
-                let mut rng = rand::thread_rng();
-                // endless loop with label 'decoy_loop
-                'decoy_loop: loop {
-                    match decoys_ptr.lock() {
-                        Ok(decoys) => if decoys.len() >= number_of_decoys_to_generate { break 'decoy_loop; },
-                        Err(_) => panic!("proteomic::utility::decoy_generator::DecoyGenerator.generate_decoys(): try to lock poisened mutex for decoys")
+        self.counters.reset();
+        // the composition solver is shared between all threads
+        let composition_solver: Option<Arc<CompositionSolver>> = match self.generation_strategy {
+            GenerationStrategy::Composition => {
+                let solver = CompositionSolver::new_weighted(
+                    self.lower_precursor_tolerance_limit,
+                    self.upper_precursor_tolerance_limit,
+                    self.fixed_modification_map.as_ref(),
+                    self.variable_modification_map.as_ref(),
+                    self.max_modifications_per_decoy,
+                    DEFAULT_MASS_RESOLUTION,
+                    self.mass_type,
+                    match self.amino_acid_composition {
+                        Some(ref amino_acid_composition) => Some(amino_acid_composition.as_ref()),
+                        None => None
+                    }
+                );
+                if !solver.has_solutions() {
+                    println!("proteomic::utility::decoy_generator::DecoyGenerator.generate_decoys(): No amino acid composition hits the precursor tolerance");
+                    return GenerationResult::new(GenerationStatus::NoSolution, self.get_statistics());
+                }
+                Some(Arc::new(solver))
+            },
+            GenerationStrategy::RandomWalk => None
+        };
+        // restrict the length distribution to lengths which can hit the precursor tolerance
+        let length_distribution: Option<LengthDistribution> = match self.length_distribution {
+            Some(ref length_distribution) => {
+                let feasible_length_range = self.get_feasible_length_range();
+                let restricted_length_distribution = length_distribution.restrict_to(feasible_length_range.0, feasible_length_range.1);
+                if restricted_length_distribution.is_empty() {
+                    println!("proteomic::utility::decoy_generator::DecoyGenerator.generate_decoys(): No length of the length distribution ({} - {}) can hit the precursor tolerance", length_distribution.get_min_length(), length_distribution.get_max_length());
+                    return GenerationResult::new(GenerationStatus::NoSolution, self.get_statistics());
+                }
+                Some(restricted_length_distribution)
+            },
+            None => None
+        };
+        // decoys saved in the database get a provenance with the id of this run
+        let run_id: i64 = match self.target_membership {
+            Some(_) => 0,
+            None => match DecoyProvenance::next_run_id(&DatabaseConnection::get_database_connection()) {
+                Ok(run_id) => run_id,
+                Err(err) => panic!("proteomic::utility::decoy_generator::DecoyGenerator.generate_decoys(): could not get a run id: {}", err)
+            }
+        };
+        let start_at = time::Instant::now();
+        let stop_at = start_at + time::Duration::from_secs(std::cmp::max(self.max_time_for_decoy_generation, 0) as u64);
+        let mut next_report_at = start_at + time::Duration::from_secs(REPORT_INTERVALL);
+        // a worker keeps a decoy which another worker found too, so the shared decoys can fall short of the requested number.
+        // The missing decoys are generated in further rounds, whose seeds depend only on the round, so the result stays reproducible.
+        let mut round: u64 = 0;
+        let mut number_of_missing_decoys: usize = number_of_decoys_to_generate;
+        while number_of_missing_decoys > 0 {
+            let round_seed: u64 = if round == 0 { self.seed } else { Self::derive_seed(self.seed, round) };
+            // the decoys of the previous rounds are known before the round starts, so rejecting them does not depend on the timing of the workers
+            let previous_decoys: Arc<HashSet<String>> = Arc::new(
+                if round == 0 {
+                    HashSet::new()
+                } else {
+                    match self.decoys.lock() {
+                        Ok(decoys) => decoys.iter().map(|decoy| decoy.get_aa_sequence().to_owned()).collect(),
+                        Err(_) => panic!("proteomic::utility::decoy_generator::DecoyGenerator.generate_decoys(): try to lock poisoned mutex for decoys")
+                    }
+                }
+            );
+            // create threadpoll
+            let thread_pool = ThreadPool::new(self.thread_count);
+            // each worker sends its thread id when it is finished
+            let (finished_sender, finished_receiver) = mpsc::channel::<usize>();
+            // the quotas are calculated for the whole run, so the combined decoys follow the distribution even if each worker generates only a few decoys
+            let worker_length_quotas: Option<Vec<Vec<(usize, usize)>>> = match length_distribution {
+                Some(ref length_distribution) => Some(LengthDistribution::split_quotas(&length_distribution.get_quotas(number_of_missing_decoys), self.thread_count)),
+                None => None
+            };
+            // loop for starting threads
+            for thread_id in 0..self.thread_count {
+                // every worker has its own share of decoys to generate, so the result does not depend on which worker is faster
+                let mut number_of_decoys_for_worker = number_of_missing_decoys / self.thread_count;
+                if thread_id < number_of_missing_decoys % self.thread_count {
+                    number_of_decoys_for_worker += 1;
+                }
+                let worker_seed = Self::derive_seed(round_seed, thread_id as u64);
+                let length_quotas: Option<Vec<(usize, usize)>> = match worker_length_quotas {
+                    Some(ref worker_length_quotas) => Some(worker_length_quotas[thread_id].clone()),
+                    None => None
+                };
+                // create copies of thread safe pointer of DecoyGenerator which can be move into thread
+                let fixed_modification_map_ptr = self.fixed_modification_map.clone();
+                let variable_modification_map_ptr = self.variable_modification_map.clone();
+                let one_amino_acid_substitute_map_ptr = self.one_amino_acid_substitute_map.clone();
+                let two_amino_acid_substitution_table_ptr = self.two_amino_acid_substitution_table.clone();
+                let mut mass_fitting_options = self.mass_fitting_options.clone();
+                // insertions and deletions would break the length quotas
+                if length_quotas.is_some() {
+                    mass_fitting_options.set_length_changes(false, 0, 0);
+                }
+                let decoys_ptr = self.decoys.clone();
+                let timeout_ptr = self.timeout.clone();
+                let composition_solver_ptr = composition_solver.clone();
+                let similarity_filter_ptr = self.similarity_filter.clone();
+                let property_filter_ptr = self.property_filter.clone();
+                let amino_acid_composition_ptr = self.amino_acid_composition.clone();
+                let target_index_ptr = self.target_index.clone();
+                let target_membership_ptr = self.target_membership.clone();
+                let previous_decoys_ptr = previous_decoys.clone();
+                let provenance = DecoyProvenance::new(
+                    run_id,
+                    self.generation_strategy.to_string().as_str(),
+                    self.seed,
+                    self.thread_count,
+                    self.precursor_mass,
+                    self.lower_precursor_tolerance_limit,
+                    self.upper_precursor_tolerance_limit
+                );
+                // copy primitive attributes of DecoyGenerator which can be moved into thread
+                let precursor_mass = self.precursor_mass;
+                let upper_precursor_tolerance_limit = self.upper_precursor_tolerance_limit;
+                let lower_precursor_tolerance_limit = self.lower_precursor_tolerance_limit;
+                let max_modifications_per_decoy = self.max_modifications_per_decoy;
+                let mass_type = self.mass_type;
+                let counters_ptr = self.counters.clone();
+                let finished_notifier = WorkerFinishedNotifier {
+                    thread_id: thread_id,
+                    sender: finished_sender.clone()
+                };
+                // start thread
+                thread_pool.execute(move||{
+                    let _finished_notifier = finished_notifier;
+                    let storage: WorkerStorage = match target_membership_ptr {
+                        Some(target_membership) => WorkerStorage::InMemory(target_membership),
+                        None => WorkerStorage::Database(DatabaseConnection::get_database_connection())
+                    };
+                    // create seeded random number generator
+                    let mut rng = StdRng::seed_from_u64(worker_seed);
+                    let amino_acid_composition: Option<&AminoAcidComposition> = match amino_acid_composition_ptr {
+                        Some(ref amino_acid_composition) => Some(amino_acid_composition.as_ref()),
+                        None => None
                     };
-                    if timeout_ptr.load(Ordering::Relaxed) { break 'decoy_loop; }
-                    // create new empty decoy
-                    let mut new_decoy: NewDecoy = NewDecoy::new_decoy(precursor_mass, lower_precursor_tolerance_limit, upper_precursor_tolerance_limit);
-                    // let distribution_array = *Self::generate_amino_acid_distribution_array();
-                    // repeat until new_decoy's weight greate then upper weight limit
-                    'amino_acid_loop: loop {
-                        // pick amino acids one letter code at index
-                        //let aa_one_letter_code: char = *distribution_array.choose(&mut rng).unwrap();
-                        let aa_one_letter_code: char = *AMINO_ACIDS_FOR_DECOY_GENERATION.choose(&mut rng).unwrap();
-                        // one letter code to amino acid
-                        let random_amino_acid: AminoAcid = AminoAcid::get(aa_one_letter_code);
-                        let modification_option = match fixed_modification_map_ptr.get(&random_amino_acid.get_one_letter_code()){
-                            Some(ref modification) => Some((*modification).clone()),
+                    let mut worker_state: WorkerState = WorkerState::new(thread_id, previous_decoys_ptr, length_quotas, similarity_filter_ptr, property_filter_ptr, target_index_ptr, storage, provenance, counters_ptr);
+                    // endless loop with label 'decoy_loop
+                    'decoy_loop: loop {
+                        if worker_state.decoys.len() >= number_of_decoys_for_worker { break 'decoy_loop; }
+                        if timeout_ptr.load(Ordering::Relaxed) { break 'decoy_loop; }
+                        // draw a decoy from the composition solver if this strategy is used
+                        if let Some(ref solver) = composition_solver_ptr {
+                            GenerationCounters::increment(&worker_state.counters.candidates_built);
+                            if let Some(new_decoy) = solver.draw_decoy(&mut rng, precursor_mass, lower_precursor_tolerance_limit, upper_precursor_tolerance_limit, fixed_modification_map_ptr.as_ref()) {
+                                worker_state.count_hit(&new_decoy, false);
+                                Self::accept_new_decoy(&new_decoy, &mut worker_state, &decoys_ptr);
+                            }
+                            continue 'decoy_loop;
+                        }
+                        // if the decoys should follow a length distribution, pick a length with remaining quota
+                        let target_length: Option<usize> = match worker_state.length_quotas {
+                            Some(ref quotas) => match LengthDistribution::pick_length_with_remaining_quota(&mut rng, quotas, &worker_state.length_counts) {
+                                Some(length) => Some(length),
+                                None => break 'decoy_loop
+                            },
                             None => None
                         };
-                        match new_decoy.push_amino_acid_and_fix_modification(&random_amino_acid, &modification_option) {
-                            Ok(push_ok) => match push_ok {
-                                PushAminoAcidOk::GreaterThenMassTolerance => break 'amino_acid_loop,
-                                _ => ()
-                            },
-                            Err(push_err) => panic!("proteomic::utility::decoy_generator::DecoyGenerator.generate_decoys(): Error at new_decoy.push_amino_acid_and_fix_modification: {}", push_err)
-                        }
-                    }
-                    if new_decoy.hits_mass_tolerance() {
-                        let mut decoy = new_decoy.to_decoy();
-                        decoy.set_modification_summary(new_decoy.get_modification_summary_for_header().as_str());
-                        if Self::save_new_decoy(&conn, &thread_id, &mut decoy) {
-                            match decoys_ptr.lock() {
-                                Ok(mut decoys) => {
-                                    decoys.insert(decoy);
-                                    continue 'decoy_loop;
-                                },
-                                Err(_) => panic!("proteomic::utility::decoy_generator::DecoyGenerator.generate_decoys(): try to lock poisoned mutex for decoys")
+                        // create new empty decoy
+                        let mut new_decoy: NewDecoy = NewDecoy::new_decoy(precursor_mass, lower_precursor_tolerance_limit, upper_precursor_tolerance_limit);
+                        new_decoy.set_mass_type(mass_type);
+                        // let distribution_array = *Self::generate_amino_acid_distribution_array();
+                        // repeat until new_decoy's weight greate then upper weight limit or the target length is reached
+                        'amino_acid_loop: loop {
+                            if let Some(length) = target_length {
+                                if new_decoy.get_length() as usize >= length { break 'amino_acid_loop; }
                             }
-                        }
-                    }
-                    if new_decoy.swap_amino_acids_to_hit_mass_tolerance(one_amino_acid_substitute_map_ptr.as_ref(), fixed_modification_map_ptr.as_ref(), max_modifications_per_decoy, variable_modification_map_ptr.as_ref())
-                    {
-                        let mut decoy = new_decoy.to_decoy();
-                        decoy.set_modification_summary(new_decoy.get_modification_summary_for_header().as_str());
-                        if Self::save_new_decoy(&conn, &thread_id, &mut decoy) {
-                            match decoys_ptr.lock() {
-                                Ok(mut decoys) => {
-                                    decoys.insert(decoy);
-                                    continue 'decoy_loop;
+                            // pick amino acids one letter code at index
+                            //let aa_one_letter_code: char = *distribution_array.choose(&mut rng).unwrap();
+                            let aa_one_letter_code: char = match amino_acid_composition {
+                                Some(amino_acid_composition) => amino_acid_composition.choose_amino_acid(&mut rng),
+                                None => *AMINO_ACIDS_FOR_DECOY_GENERATION.choose(&mut rng).unwrap()
+                            };
+                            // one letter code to amino acid
+                            let random_amino_acid: AminoAcid = AminoAcid::get(aa_one_letter_code);
+                            let modification_option = match fixed_modification_map_ptr.get(&random_amino_acid.get_one_letter_code()){
+                                Some(ref modification) => Some((*modification).clone()),
+                                None => None
+                            };
+                            match new_decoy.push_amino_acid_and_fix_modification(&random_amino_acid, &modification_option) {
+                                Ok(push_ok) => match push_ok {
+                                    // with a target length the mass is fitted afterwards by swapping amino acids
+                                    PushAminoAcidOk::GreaterThenMassTolerance if target_length.is_none() => break 'amino_acid_loop,
+                                    _ => ()
                                 },
-                                Err(_) => panic!("proteomic::utility::decoy_generator::DecoyGenerator.generate_decoys(): try to lock poisoned mutex for decoys")
+                                Err(push_err) => panic!("proteomic::utility::decoy_generator::DecoyGenerator.generate_decoys(): Error at new_decoy.push_amino_acid_and_fix_modification: {}", push_err)
                             }
                         }
+                        new_decoy.apply_fixed_terminal_modifications(fixed_modification_map_ptr.as_ref());
+                        GenerationCounters::increment(&worker_state.counters.candidates_built);
+                        // a rejected direct hit is not fitted again, swapping would only count the same candidate twice
+                        if new_decoy.hits_mass_tolerance() {
+                            worker_state.count_hit(&new_decoy, false);
+                            Self::accept_new_decoy(&new_decoy, &mut worker_state, &decoys_ptr);
+                            continue 'decoy_loop;
+                        }
+                        if new_decoy.swap_amino_acids_to_hit_mass_tolerance(one_amino_acid_substitute_map_ptr.as_ref(), two_amino_acid_substitution_table_ptr.as_ref(), &mass_fitting_options, fixed_modification_map_ptr.as_ref(), max_modifications_per_decoy, variable_modification_map_ptr.as_ref(), amino_acid_composition, &mut rng) {
+                            worker_state.count_hit(&new_decoy, true);
+                            Self::accept_new_decoy(&new_decoy, &mut worker_state, &decoys_ptr);
+                        }
                     }
+                });
+            }
+            // only the workers hold a sender now, so the channel disconnects when all workers are gone
+            drop(finished_sender);
+            let mut number_of_running_workers = self.thread_count;
+            while number_of_running_workers > 0 {
+                let now = time::Instant::now();
+                // signal threads to stop and break the loop if max generation time is reached
+                if now >= stop_at {
+                    self.timeout.store(true, Ordering::Relaxed);
+                    println!("proteomic::utility::decoy_generator::DecoyGenerator.generate_decoys(): Running out of time, stop threads");
+                    break;
                 }
-            });
-        }
-        let start_at_sec = better_time::now().to_timespec().sec;                // lock start sec
-        let stop_at_sec = start_at_sec + self.max_time_for_decoy_generation;    // lock stop sec
-        let mut next_report_at = start_at_sec + REPORT_INTERVALL;               // next second to report
-        thread::sleep(time::Duration::from_secs(2));                            // wait for threads to start
-        while thread_pool.active_count() > 0 {
-            let now_in_seconds = better_time::now().to_timespec().sec;          // current second
-            if now_in_seconds >= next_report_at {
-                next_report_at = now_in_seconds + REPORT_INTERVALL;
-                match self.decoys.lock() {
-                    Ok(decoys) => {
-                        println!("decoy generation progress: {} decoys generated", decoys.len());
-                    },
-                    Err(_) => panic!("proteomic::utility::decoy_generator::DecoyGenerator.generate_decoys(): Tried to lock poisoned mutex for decoys")
+                if now >= next_report_at {
+                    next_report_at = now + time::Duration::from_secs(REPORT_INTERVALL);
+                    println!("decoy generation progress: {} decoys generated", self.get_number_of_generated_decoys());
+                }
+                // sleep until a worker is finished, the next report is due or the time is up
+                match finished_receiver.recv_timeout(std::cmp::min(stop_at, next_report_at) - now) {
+                    Ok(_) => number_of_running_workers -= 1,
+                    Err(mpsc::RecvTimeoutError::Timeout) => (),
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break
                 }
             }
-            // signal threads to stop and break the loop if max generation time is reached
-            if now_in_seconds >= stop_at_sec {
-                self.timeout.store(true, Ordering::Relaxed);
-                println!("proteomic::utility::decoy_generator::DecoyGenerator.generate_decoys(): Running out of time, stop threads");
-                break;
+            // wait for generator threads to stop
+            thread_pool.join();
+            if self.timeout.load(Ordering::Relaxed) { break; }
+            number_of_missing_decoys = number_of_decoys_to_generate.saturating_sub(self.get_number_of_generated_decoys());
+            if number_of_missing_decoys > 0 {
+                println!("{} decoys were found by several workers, generate them in round {}", number_of_missing_decoys, round + 1);
             }
+            round += 1;
         }
-        // wait for generator threads to stop
-        thread_pool.join();
         if !self.timeout.load(Ordering::Relaxed) {
-            return GenerationResult::Success;
+            return GenerationResult::new(GenerationStatus::Success, self.get_statistics());
         } else {
-            return GenerationResult::Timeout
+            return GenerationResult::new(GenerationStatus::Timeout, self.get_statistics());
+        }
+    }
+
+    /// Saves the decoy if it is accepted by the worker and adds it to the generated decoys.
+    /// Returns true if the decoy is accepted.
+    fn accept_new_decoy(new_decoy: &NewDecoy, worker_state: &mut WorkerState, decoys_ptr: &Arc<Mutex<HashSet<Decoy>>>) -> bool {
+        if !worker_state.accepts(new_decoy.get_aa_sequence().as_str()) {
+            return false;
+        }
+        let mut decoy = new_decoy.to_decoy();
+        decoy.set_modification_summary(new_decoy.get_modification_summary_for_header().as_str());
+        decoy.set_proforma(new_decoy.to_proforma().as_str());
+        if worker_state.is_peptide(&decoy) {
+            GenerationCounters::increment(&worker_state.counters.is_peptide_rejections);
+            return false;
+        }
+        if let WorkerStorage::Database(ref conn) = worker_state.storage {
+            if !Self::save_new_decoy(conn, worker_state, &mut decoy) {
+                return false;
+            }
+        }
+        // the worker keeps the decoy even if another worker found it too, so each worker's decoys depend only on its seed and not on the timing of the other workers
+        worker_state.add_decoy(decoy.get_aa_sequence());
+        match decoys_ptr.lock() {
+            Ok(mut decoys) => if decoys.insert(decoy) {
+                GenerationCounters::increment(&worker_state.counters.generated_decoys);
+            } else {
+                GenerationCounters::increment(&worker_state.counters.cross_worker_duplicates);
+            },
+            Err(_) => panic!("proteomic::utility::decoy_generator::DecoyGenerator.generate_decoys(): try to lock poisoned mutex for decoys")
         }
+        return true;
     }
 
-    fn save_new_decoy(conn: &postgres::Connection, thread_id: &usize, decoy: &mut Decoy) -> bool {
+    /// Creates the decoy and its provenance in the database, the caller must make sure the decoy is not a target.
+    /// Decoys which already exist, e.g. from a previous run with the same seed or from another worker, are accepted too,
+    /// so the generated decoys do not depend on the state of the database.
+    fn save_new_decoy(conn: &postgres::Connection, worker_state: &WorkerState, decoy: &mut Decoy) -> bool {
+        let thread_id: usize = worker_state.thread_id;
         let mut error_occured = false;
-        if !decoy.is_peptide(&conn) {
-            // try 3 times to create the decoy
-            for _ in 0..3 {
-                match decoy.create(&conn) {
-                    Ok(query_ok) => {
-                        let result = match query_ok {
-                            // return true only if decoy is newly created. decoys which not newly created are already added from other threads or
-                            // add during previous target/decoy search
-                            QueryOk::Created => true,
-                            _ => false
-                        };
-                        // if previous
-                        if error_occured {
-                            println!("proteomic::utility::decoy_generator::DecoyGenerator.generate_decoys()_thread_{}: Previous error resolved", thread_id);
-                        }
-                        return result;
+        // try 3 times to create the decoy
+        for _ in 0..3 {
+            match worker_state.provenance.create_with_decoy(conn, decoy) {
+                Ok(query_ok) => {
+                    if let QueryOk::AlreadyExists = query_ok {
+                        GenerationCounters::increment(&worker_state.counters.database_duplicates);
                     }
-                    Err(query_err) => match query_err {
-                        // NoReturn is mostly a indication for a conflict between two or more threads which try to create the same decoy
-                        QueryError::NoReturn => {
-                            error_occured = true;
-                            // wait a random time between 1 and 6 seconds so hopefully one thread get the chance to create the decoy before the other threads
-                            let mut rng = rand::thread_rng();
-                            let wait_for: u64 = rng.gen_range(1, 6);
-                            println!("proteomic::utility::decoy_generator::DecoyGenerator.generate_decoys()_thread_{}: Conflict with other threads which try to create the same decoy. Try again in {} seconds", thread_id, wait_for);
-                            thread::sleep(time::Duration::from_secs(wait_for));
-                            continue;
-                        },
-                        _ => {
-                            println!("proteomic::utility::decoy_generator::DecoyGenerator.generate_decoys()_thread_{}: Unresoveable error occured: {}, continue with next decoy", thread_id, query_err);
-                            return false;
-                        }
+                    if error_occured {
+                        println!("proteomic::utility::decoy_generator::DecoyGenerator.generate_decoys()_thread_{}: Previous error resolved", thread_id);
+                    }
+                    return true;
+                }
+                Err(query_err) => match query_err {
+                    // NoReturn is mostly a indication for a conflict between two or more threads which try to create the same decoy,
+                    // the next try finds the decoy of the other thread
+                    QueryError::NoReturn => {
+                        GenerationCounters::increment(&worker_state.counters.database_conflicts);
+                        error_occured = true;
+                        thread::sleep(time::Duration::from_millis(100));
+                        continue;
+                    },
+                    _ => {
+                        println!("proteomic::utility::decoy_generator::DecoyGenerator.generate_decoys()_thread_{}: Unresoveable error occured: {}, continue with next decoy", thread_id, query_err);
+                        return false;
                     }
                 }
             }
@@ -265,7 +781,7 @@ impl DecoyGenerator {
     pub fn vary_targets(&self, targets: &Vec<Peptide>, number_of_decoys_to_generate: usize) -> Box<HashSet<Decoy>> {
         let mut decoys: Box<HashSet<Decoy>> = Box::new(HashSet::new());
         let conn: postgres::Connection = DatabaseConnection::get_database_connection();
-        let mut rng = rand::thread_rng();
+        let mut rng = StdRng::seed_from_u64(self.seed);
         'targets_loop: for target in targets.iter() {
             let mut aa_sequence: Vec<char> = target.get_aa_sequence().chars().collect();
             'shuffle_loop: for _ in 0..1000 {
@@ -279,6 +795,7 @@ impl DecoyGenerator {
                     }
                 }
                 let mut new_decoy: NewDecoy = NewDecoy::decoy_from_string(aa_sequence_as_string.as_str(), self.precursor_mass, self.get_lower_precursor_tolerance_limit(), self.get_upper_precursor_tolerance_limit(), self.fixed_modification_map.as_ref());
+                new_decoy.set_mass_type(self.mass_type);
                 if new_decoy.hits_mass_tolerance() {
                     let mut decoy = new_decoy.to_decoy();
                     if !decoy.is_peptide(&conn) {
@@ -298,22 +815,22 @@ impl DecoyGenerator {
     /// calculates a substitution map for swapping amino acids with each other.
     /// so one can lookup which difference in weight a substitution of amino acid x with y has.
     /// this function also considers fixed modifications
-    pub fn get_one_amino_acid_substitute_map(fixed_modification_map: &HashMap<char, Modification>) -> Box<HashMap<char, HashMap<char, i64>>> {
+    pub fn get_one_amino_acid_substitute_map(fixed_modification_map: &HashMap<char, Modification>, mass_type: MassType) -> Box<HashMap<char, HashMap<char, i64>>> {
         let mut substitution_map: HashMap<char, HashMap<char, i64>> = HashMap::new();
         for aa_origin in AMINO_ACIDS_FOR_DECOY_GENERATION.iter() {
             let mut differences_in_weight: HashMap<char, i64> = HashMap::new();
-            let mut aa_origin_weight: i64 = AminoAcid::get(*aa_origin).get_mono_mass();
+            let mut aa_origin_weight: i64 = AminoAcid::get(*aa_origin).get_mass(mass_type);
             aa_origin_weight += match fixed_modification_map.get(aa_origin){
-                Some(ref modification) => modification.get_mono_mass(),
+                Some(ref modification) => modification.get_mass(mass_type),
                 None => 0
             };
             for aa_replacement in AMINO_ACIDS_FOR_DECOY_GENERATION.iter() {
                 if *aa_replacement == *aa_origin {
                     continue;
                 }
-                let mut aa_replacement_weight: i64 = AminoAcid::get(*aa_replacement).get_mono_mass();
+                let mut aa_replacement_weight: i64 = AminoAcid::get(*aa_replacement).get_mass(mass_type);
                 aa_replacement_weight += match fixed_modification_map.get(aa_replacement){
-                    Some(ref modification) => modification.get_mono_mass(),
+                    Some(ref modification) => modification.get_mass(mass_type),
                     None => 0
                 };
                 differences_in_weight.insert(*aa_replacement, aa_replacement_weight - aa_origin_weight);
@@ -0,0 +1,148 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Column names of the statistics CSV, in the order of `GenerationStatistics.to_csv_row()`.
+/// New columns are appended, so existing readers of the CSV keep working.
+pub const CSV_HEADER: &'static str = "generated_decoys,candidates_built,direct_hits,hits_after_swaps,hits_after_variable_modifications,duplicates,is_peptide_rejections,similarity_rejections,length_quota_rejections,database_conflicts,cross_worker_duplicates,database_duplicates,property_rejections";
+
+/// Counters of a decoy generation run, to find out why a generation times out.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct GenerationStatistics {
+    pub generated_decoys: usize,                    // accepted and saved decoys
+    pub candidates_built: usize,                    // sequences built by random walk or drawn from the composition solver
+    pub direct_hits: usize,                         // candidates which hit the precursor tolerance without further changes
+    pub hits_after_swaps: usize,                    // candidates which hit the precursor tolerance after swapping amino acids
+    pub hits_after_variable_modifications: usize,   // candidates which hit the precursor tolerance with variable modifications
+    pub duplicates: usize,                          // hits which the same worker has already generated
+    pub is_peptide_rejections: usize,               // hits which are targets
+    pub similarity_rejections: usize,               // hits which are too similar to a target
+    pub property_rejections: usize,                 // hits whose physicochemical properties are outside the spread of the targets
+    pub length_quota_rejections: usize,             // hits whose length quota is already filled
+    pub database_conflicts: usize,                  // conflicts with other threads while saving (QueryError::NoReturn)
+    pub cross_worker_duplicates: usize,             // decoys which another worker of the run has already generated, the missing decoys are generated in further rounds
+    pub database_duplicates: usize                  // accepted decoys which were already stored in the database
+}
+
+impl GenerationStatistics {
+    pub fn to_csv_row(&self) -> String {
+        return format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.generated_decoys,
+            self.candidates_built,
+            self.direct_hits,
+            self.hits_after_swaps,
+            self.hits_after_variable_modifications,
+            self.duplicates,
+            self.is_peptide_rejections,
+            self.similarity_rejections,
+            self.length_quota_rejections,
+            self.database_conflicts,
+            self.cross_worker_duplicates,
+            self.database_duplicates,
+            self.property_rejections
+        );
+    }
+
+    pub fn to_string(&self) -> String {
+        return format!(
+            "generated decoys: {}, candidates built: {}, direct hits: {}, hits after swaps: {}, hits after variable modifications: {}, duplicates: {}, is_peptide rejections: {}, similarity rejections: {}, property rejections: {}, length quota rejections: {}, database conflicts: {}, cross worker duplicates: {}, database duplicates: {}",
+            self.generated_decoys,
+            self.candidates_built,
+            self.direct_hits,
+            self.hits_after_swaps,
+            self.hits_after_variable_modifications,
+            self.duplicates,
+            self.is_peptide_rejections,
+            self.similarity_rejections,
+            self.property_rejections,
+            self.length_quota_rejections,
+            self.database_conflicts,
+            self.cross_worker_duplicates,
+            self.database_duplicates
+        );
+    }
+}
+
+/// Thread safe counters which are shared by the workers of a generation run
+pub struct GenerationCounters {
+    pub generated_decoys: AtomicUsize,
+    pub candidates_built: AtomicUsize,
+    pub direct_hits: AtomicUsize,
+    pub hits_after_swaps: AtomicUsize,
+    pub hits_after_variable_modifications: AtomicUsize,
+    pub duplicates: AtomicUsize,
+    pub is_peptide_rejections: AtomicUsize,
+    pub similarity_rejections: AtomicUsize,
+    pub property_rejections: AtomicUsize,
+    pub length_quota_rejections: AtomicUsize,
+    pub database_conflicts: AtomicUsize,
+    pub cross_worker_duplicates: AtomicUsize,
+    pub database_duplicates: AtomicUsize
+}
+
+impl GenerationCounters {
+    pub fn new() -> Self {
+        return Self {
+            generated_decoys: AtomicUsize::new(0),
+            candidates_built: AtomicUsize::new(0),
+            direct_hits: AtomicUsize::new(0),
+            hits_after_swaps: AtomicUsize::new(0),
+            hits_after_variable_modifications: AtomicUsize::new(0),
+            duplicates: AtomicUsize::new(0),
+            is_peptide_rejections: AtomicUsize::new(0),
+            similarity_rejections: AtomicUsize::new(0),
+            property_rejections: AtomicUsize::new(0),
+            length_quota_rejections: AtomicUsize::new(0),
+            database_conflicts: AtomicUsize::new(0),
+            cross_worker_duplicates: AtomicUsize::new(0),
+            database_duplicates: AtomicUsize::new(0)
+        };
+    }
+
+    /// Increments the given counter by one
+    pub fn increment(counter: &AtomicUsize) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn reset(&self) {
+        for counter in self.get_counters().iter() {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn get_counters(&self) -> [&AtomicUsize; 13] {
+        return [
+            &self.generated_decoys,
+            &self.candidates_built,
+            &self.direct_hits,
+            &self.hits_after_swaps,
+            &self.hits_after_variable_modifications,
+            &self.duplicates,
+            &self.is_peptide_rejections,
+            &self.similarity_rejections,
+            &self.property_rejections,
+            &self.length_quota_rejections,
+            &self.database_conflicts,
+            &self.cross_worker_duplicates,
+            &self.database_duplicates
+        ];
+    }
+
+    /// Returns the current values of the counters
+    pub fn get_statistics(&self) -> GenerationStatistics {
+        return GenerationStatistics {
+            generated_decoys: self.generated_decoys.load(Ordering::Relaxed),
+            candidates_built: self.candidates_built.load(Ordering::Relaxed),
+            direct_hits: self.direct_hits.load(Ordering::Relaxed),
+            hits_after_swaps: self.hits_after_swaps.load(Ordering::Relaxed),
+            hits_after_variable_modifications: self.hits_after_variable_modifications.load(Ordering::Relaxed),
+            duplicates: self.duplicates.load(Ordering::Relaxed),
+            is_peptide_rejections: self.is_peptide_rejections.load(Ordering::Relaxed),
+            similarity_rejections: self.similarity_rejections.load(Ordering::Relaxed),
+            property_rejections: self.property_rejections.load(Ordering::Relaxed),
+            length_quota_rejections: self.length_quota_rejections.load(Ordering::Relaxed),
+            database_conflicts: self.database_conflicts.load(Ordering::Relaxed),
+            cross_worker_duplicates: self.cross_worker_duplicates.load(Ordering::Relaxed),
+            database_duplicates: self.database_duplicates.load(Ordering::Relaxed)
+        };
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+
+use rand::Rng;
+
+/// Distribution of peptide lengths, used to give decoys the same lengths as the targets.
+/// Lengths are kept sorted, so the quotas and random picks are reproducible.
+pub struct LengthDistribution {
+    weights: BTreeMap<usize, f64>
+}
+
+impl LengthDistribution {
+    /// Creates a uniform distribution from `min_length` to `max_length` (both inclusive)
+    pub fn uniform(min_length: usize, max_length: usize) -> Self {
+        let mut weights: BTreeMap<usize, f64> = BTreeMap::new();
+        for length in min_length..=max_length {
+            weights.insert(length, 1.0);
+        }
+        return Self {
+            weights: weights
+        };
+    }
+
+    /// Creates a distribution from the lengths of the given sequences, e.g. the targets of a spectrum.
+    /// Lengths outside of `min_length` and `max_length` are ignored.
+    pub fn from_lengths(lengths: &Vec<usize>, min_length: usize, max_length: usize) -> Self {
+        let mut weights: BTreeMap<usize, f64> = BTreeMap::new();
+        for length in lengths.iter() {
+            if (min_length <= *length) & (*length <= max_length) {
+                *weights.entry(*length).or_insert(0.0) += 1.0;
+            }
+        }
+        return Self {
+            weights: weights
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.weights.is_empty();
+    }
+
+    pub fn get_min_length(&self) -> usize {
+        return match self.weights.keys().next() {
+            Some(length) => *length,
+            None => 0
+        };
+    }
+
+    pub fn get_max_length(&self) -> usize {
+        return match self.weights.keys().last() {
+            Some(length) => *length,
+            None => 0
+        };
+    }
+
+    /// Returns a new distribution which contains only the lengths from `min_length` to `max_length` (both inclusive)
+    pub fn restrict_to(&self, min_length: usize, max_length: usize) -> Self {
+        return Self {
+            weights: self.weights.iter().filter(|&(length, _)| (min_length <= *length) & (*length <= max_length)).map(|(length, weight)| (*length, *weight)).collect()
+        };
+    }
+
+    /// Splits the given number of decoys into quotas per length, using the largest remainder method.
+    /// Returns a list of (length, quota) sorted by length.
+    pub fn get_quotas(&self, number_of_decoys: usize) -> Vec<(usize, usize)> {
+        let total_weight: f64 = self.weights.values().sum();
+        let mut quotas: Vec<(usize, usize)> = Vec::new();
+        let mut remainders: Vec<(usize, f64)> = Vec::new();
+        let mut distributed: usize = 0;
+        for (idx, (length, weight)) in self.weights.iter().enumerate() {
+            let exact_quota: f64 = number_of_decoys as f64 * weight / total_weight;
+            quotas.push((*length, exact_quota.floor() as usize));
+            remainders.push((idx, exact_quota - exact_quota.floor()));
+            distributed += exact_quota.floor() as usize;
+        }
+        // distribute the remaining decoys to the lengths with the largest remainders, shorter lengths first on ties
+        remainders.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then(a.0.cmp(&b.0)));
+        for remainder in remainders.iter().take(number_of_decoys - distributed) {
+            quotas[remainder.0].1 += 1;
+        }
+        return quotas;
+    }
+
+    /// Splits the quotas of a whole generation run on the given number of workers, by dealing the decoys of each length one by one to the workers in turn.
+    /// The workers' quotas add up to the given quotas and worker `i` gets `n / number_of_workers` decoys, plus one if `i < n % number_of_workers`.
+    pub fn split_quotas(quotas: &Vec<(usize, usize)>, number_of_workers: usize) -> Vec<Vec<(usize, usize)>> {
+        let mut worker_quotas: Vec<Vec<(usize, usize)>> = (0..number_of_workers).map(|_| quotas.iter().map(|&(length, _)| (length, 0)).collect()).collect();
+        let mut worker_idx: usize = 0;
+        for (length_idx, &(_, quota)) in quotas.iter().enumerate() {
+            for _ in 0..quota {
+                worker_quotas[worker_idx][length_idx].1 += 1;
+                worker_idx = (worker_idx + 1) % number_of_workers;
+            }
+        }
+        return worker_quotas;
+    }
+
+    /// Picks a length, weighted by the remaining quota of each length.
+    /// Returns None if all quotas are filled.
+    pub fn pick_length_with_remaining_quota<R: Rng>(rng: &mut R, quotas: &Vec<(usize, usize)>, length_counts: &BTreeMap<usize, usize>) -> Option<usize> {
+        let remaining_quotas: Vec<(usize, usize)> = quotas.iter().map(
+            |&(length, quota)| (length, quota - std::cmp::min(quota, *length_counts.get(&length).unwrap_or(&0)))
+        ).collect();
+        let total_remaining_quota: usize = remaining_quotas.iter().map(|remaining_quota| remaining_quota.1).sum();
+        if total_remaining_quota == 0 {
+            return None;
+        }
+        let mut threshold: usize = rng.gen_range(0, total_remaining_quota);
+        for remaining_quota in remaining_quotas.iter() {
+            if threshold < remaining_quota.1 {
+                return Some(remaining_quota.0);
+            }
+            threshold -= remaining_quota.1;
+        }
+        return None;
+    }
+
+    /// Returns true if the quota for the given length is not filled yet
+    pub fn has_remaining_quota(quotas: &Vec<(usize, usize)>, length_counts: &BTreeMap<usize, usize>, length: usize) -> bool {
+        return match quotas.iter().find(|quota| quota.0 == length) {
+            Some(quota) => *length_counts.get(&length).unwrap_or(&0) < quota.1,
+            None => false
+        };
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use proteomic::models::amino_acids::amino_acid::{AminoAcid, AMINO_ACIDS_FOR_DECOY_GENERATION};
+use proteomic::models::amino_acids::modification::Modification;
+use proteomic::models::mass::mass_type::MassType;
+
+/// Default number of iterations for fitting a decoy into the precursor tolerance
+pub const DEFAULT_MAX_ITERATIONS: usize = 100;
+
+/// Options of `ModifiedPeptide.swap_amino_acids_to_hit_mass_tolerance()`
+#[derive(Clone)]
+pub struct MassFittingOptions {
+    max_iterations: usize,
+    pair_substitutions: bool,
+    length_changes: bool,
+    min_length: usize,
+    max_length: usize
+}
+
+impl MassFittingOptions {
+    /// Creates options with single amino acid substitutions only, like before pair substitutions and length changes existed
+    pub fn new(max_iterations: usize) -> Self {
+        return Self {
+            max_iterations: max_iterations,
+            pair_substitutions: false,
+            length_changes: false,
+            min_length: 0,
+            max_length: std::usize::MAX
+        };
+    }
+
+    /// Reads the options MASS_FITTING_ITERATIONS, PAIR_SUBSTITUTIONS and LENGTH_CHANGES, which are shared by the decoy generating subcommands
+    pub fn from_cli_args(cli_args: &clap::ArgMatches, min_length: usize, max_length: usize) -> Self {
+        let max_iterations: usize = match cli_args.value_of("MASS_FITTING_ITERATIONS") {
+            Some(number_string) => match number_string.to_owned().parse::<usize>() {
+                Ok(number) => number,
+                Err(_) => panic!("proteomic::utility::mass_fitting::MassFittingOptions::from_cli_args(): could not cast mass-fitting-iterations to unsigned integer")
+            },
+            None => DEFAULT_MAX_ITERATIONS
+        };
+        let mut mass_fitting_options = Self::new(max_iterations);
+        mass_fitting_options.set_pair_substitutions(cli_args.is_present("PAIR_SUBSTITUTIONS"));
+        mass_fitting_options.set_length_changes(cli_args.is_present("LENGTH_CHANGES"), min_length, max_length);
+        return mass_fitting_options;
+    }
+
+    pub fn get_max_iterations(&self) -> usize {
+        return self.max_iterations;
+    }
+
+    pub fn set_pair_substitutions(&mut self, pair_substitutions: bool) {
+        self.pair_substitutions = pair_substitutions;
+    }
+
+    pub fn get_pair_substitutions(&self) -> bool {
+        return self.pair_substitutions;
+    }
+
+    /// Allows insertions and deletions of amino acids, as long as the length stays between the given lengths
+    pub fn set_length_changes(&mut self, length_changes: bool, min_length: usize, max_length: usize) {
+        self.length_changes = length_changes;
+        self.min_length = min_length;
+        self.max_length = max_length;
+    }
+
+    pub fn get_length_changes(&self) -> bool {
+        return self.length_changes;
+    }
+
+    pub fn get_min_length(&self) -> usize {
+        return self.min_length;
+    }
+
+    pub fn get_max_length(&self) -> usize {
+        return self.max_length;
+    }
+}
+
+/// Precomputed weights of single amino acids and of all pairs of amino acids (including fixed modifications), sorted by weight.
+/// Substituting two amino acids with the weight `w` by the pair closest to `w + distance` is looked up by binary search.
+pub struct TwoAminoAcidSubstitutionTable {
+    amino_acid_weights: Vec<(i64, char)>,
+    pair_weights: Vec<(i64, char, char)>
+}
+
+impl TwoAminoAcidSubstitutionTable {
+    pub fn new(fixed_modification_map: &HashMap<char, Modification>, mass_type: MassType) -> Self {
+        let mut amino_acid_weights: Vec<(i64, char)> = AMINO_ACIDS_FOR_DECOY_GENERATION.iter()
+            .map(|aa_one_letter_code| (Self::get_amino_acid_weight(*aa_one_letter_code, fixed_modification_map, mass_type), *aa_one_letter_code))
+            .collect();
+        let mut pair_weights: Vec<(i64, char, char)> = Vec::new();
+        for first in amino_acid_weights.iter() {
+            for second in amino_acid_weights.iter() {
+                pair_weights.push((first.0 + second.0, first.1, second.1));
+            }
+        }
+        // sort by weight and resolve ties by the one letter codes, so the lookups are deterministic
+        amino_acid_weights.sort();
+        pair_weights.sort();
+        return Self {
+            amino_acid_weights: amino_acid_weights,
+            pair_weights: pair_weights
+        };
+    }
+
+    /// Weight of the amino acid including its fixed modification
+    pub fn get_amino_acid_weight(aa_one_letter_code: char, fixed_modification_map: &HashMap<char, Modification>, mass_type: MassType) -> i64 {
+        return AminoAcid::get(aa_one_letter_code).get_mass(mass_type) + match fixed_modification_map.get(&aa_one_letter_code) {
+            Some(ref modification) => modification.get_mass(mass_type),
+            None => 0
+        };
+    }
+
+    /// Returns the amino acid whose weight is closest to the given weight
+    pub fn get_closest_amino_acid(&self, weight: i64) -> Option<(i64, char)> {
+        let idx = match self.amino_acid_weights.binary_search_by_key(&weight, |entry| entry.0) {
+            Ok(idx) => return Some(self.amino_acid_weights[idx]),
+            Err(idx) => idx
+        };
+        return Self::closest_neighbour(&self.amino_acid_weights, idx, weight, |entry| entry.0);
+    }
+
+    /// Returns the pair of amino acids whose weight is closest to the given weight
+    pub fn get_closest_pair(&self, weight: i64) -> Option<(i64, char, char)> {
+        let idx = match self.pair_weights.binary_search_by_key(&weight, |entry| entry.0) {
+            Ok(idx) => return Some(self.pair_weights[idx]),
+            Err(idx) => idx
+        };
+        return Self::closest_neighbour(&self.pair_weights, idx, weight, |entry| entry.0);
+    }
+
+    /// Returns the closer one of the entries before and at the insertion index
+    fn closest_neighbour<T: Copy, F: Fn(&T) -> i64>(entries: &Vec<T>, insertion_idx: usize, weight: i64, get_weight: F) -> Option<T> {
+        let before = if insertion_idx > 0 { entries.get(insertion_idx - 1) } else { None };
+        let after = entries.get(insertion_idx);
+        return match (before, after) {
+            (Some(before), Some(after)) => if (weight - get_weight(before)).abs() <= (get_weight(after) - weight).abs() { Some(*before) } else { Some(*after) },
+            (Some(before), None) => Some(*before),
+            (None, Some(after)) => Some(*after),
+            (None, None) => None
+        };
+    }
+}
@@ -3,8 +3,22 @@ pub mod database_connection;
 pub mod logger;
 pub mod combinations;
 pub mod decoy_generator;
+pub mod composition_solver;
+pub mod length_distribution;
+pub mod similarity_filter;
+pub mod amino_acid_composition;
+pub mod target_index;
+pub mod generation_statistics;
+pub mod precursor_reader;
+pub mod decoy_cache;
+pub mod mass_fitting;
+pub mod property_filter;
 pub mod mz_ml;
 pub mod comet_parameter;
+pub mod unimod_reader;
+pub mod peff;
+#[cfg(test)]
+mod tests;
 
 pub fn parts_per_million_of(value: f64, ppm: i64) -> f64 {
     return  value / 1000000.0 * ppm as f64;
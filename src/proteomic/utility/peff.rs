@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use proteomic::models::amino_acids::modification::Modification;
+use proteomic::models::amino_acids::modification_registry::ModificationRegistry;
+use proteomic::models::peptides::modified_peptide::ModifiedPeptide;
+
+/// Database prefix of the PEFF-entries
+pub const PEFF_PREFIX: &'static str = "mdg";
+
+/// Controlled vocabulary of the modifications which Comet reads from a PEFF-file
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PeffFormat {
+    PsiMod,             // \ModResPsi, comet peff_format = 1
+    Unimod              // \ModResUnimod, comet peff_format = 2
+}
+
+impl PeffFormat {
+    pub fn to_string(&self) -> String {
+        return match self {
+            PeffFormat::PsiMod => "psi-mod".to_string(),
+            PeffFormat::Unimod => "unimod".to_string()
+        }
+    }
+
+    pub fn from_str(format_name: &str) -> PeffFormat {
+        return match format_name.to_lowercase().as_str() {
+            "psi-mod" => PeffFormat::PsiMod,
+            "unimod" => PeffFormat::Unimod,
+            _ => panic!("proteomic::utility::peff::PeffFormat::from_str(): Unknown PEFF format '{}', use 'psi-mod' or 'unimod'", format_name)
+        }
+    }
+
+    /// Value of Comet's parameter `peff_format`
+    pub fn get_comet_peff_format(&self) -> u8 {
+        return match self {
+            PeffFormat::PsiMod => 1,
+            PeffFormat::Unimod => 2
+        }
+    }
+
+    /// PEFF key of the modifications, which is the only one Comet reads for this format
+    pub fn get_mod_res_key(&self) -> &'static str {
+        return match self {
+            PeffFormat::PsiMod => "ModResPsi",
+            PeffFormat::Unimod => "ModResUnimod"
+        }
+    }
+
+    /// Returns the accession of the modification in the controlled vocabulary, e.g. `UNIMOD:35` for `unimod:35`, or None if it has no accession of the vocabulary
+    pub fn get_accession(&self, modification: &Modification) -> Option<String> {
+        let accession: &str = modification.get_accession();
+        let prefix: &str = match self {
+            PeffFormat::PsiMod => "MOD:",
+            PeffFormat::Unimod => "UNIMOD:"
+        };
+        if (accession.len() > prefix.len()) && accession.is_char_boundary(prefix.len()) && accession[..prefix.len()].eq_ignore_ascii_case(prefix) {
+            return Some(format!("{}{}", prefix, &accession[prefix.len()..]));
+        }
+        return None;
+    }
+}
+
+/// Returns an error message if a variable modification of the registry has no accession of the PEFF format's vocabulary,
+/// because Comet could not read its annotation. Fixed modifications are static modifications and are not annotated.
+pub fn check_modifications(modification_registry: &ModificationRegistry, peff_format: PeffFormat) -> Result<(), String> {
+    for modification in modification_registry.get_sorted_variable_modifications() {
+        if peff_format.get_accession(modification).is_none() {
+            return Err(get_missing_accession_message(modification, peff_format));
+        }
+    }
+    return Ok(());
+}
+
+fn get_missing_accession_message(modification: &Modification, peff_format: PeffFormat) -> String {
+    return format!(
+        "modification '{}' with accession '{}' has no {} accession, which is required to annotate it in \\{} for --peff-format {}",
+        modification.get_name(),
+        modification.get_accession(),
+        peff_format.to_string(),
+        peff_format.get_mod_res_key(),
+        peff_format.to_string()
+    );
+}
+
+/// Returns the header of a PEFF-file
+pub fn get_file_header(database_name: &str, number_of_entries: usize) -> String {
+    return format!(
+        "# PEFF 1.0\n# //\n# DbName={}\n# Prefix={}\n# NumberOfEntries={}\n# SequenceType=AA\n# //\n",
+        database_name,
+        PEFF_PREFIX,
+        number_of_entries
+    );
+}
+
+/// Converts a FASTA-header, e.g. `>PEPTIDE_PEPMK ModRes=(1|unimod:35|Oxidation) ProForma=PEPM[UNIMOD:35]K`, into PEFF-headers
+/// with the modifications of the ProForma-tag at explicit positions, e.g. `>mdg:PEPTIDE_PEPMK \ModResUnimod=(4|UNIMOD:35|Oxidation) \ModSum=(1|unimod:35|Oxidation) \ProForma=PEPM[UNIMOD:35]K`.
+/// Only the key of `peff_format` is written, because Comet reads only this one. Comet treats the annotated modifications as optional variants,
+/// so the unmodified form is searched as well.
+/// If the header lists isoforms (`Isoforms=`), one header is returned per isoform, each with its ProForma-tag and an own identifier,
+/// otherwise one header for the ProForma-tag.
+/// The modifications are resolved with `modification_registry`, modifications which are in `static_modifications` are omitted, because Comet applies them anyway.
+/// Returns an error message if the ProForma-tag is invalid or a modification has no accession of the PEFF format's vocabulary.
+pub fn get_entry_headers(header: &str, modification_registry: &ModificationRegistry, static_modifications: &HashMap<char, Modification>, peff_format: PeffFormat) -> Result<Vec<String>, String> {
+    let mut tokens = header.trim_left_matches('>').split(' ');
+    let identifier: &str = tokens.next().unwrap_or("");
+    // key-value pairs of the FASTA-header, values may contain spaces
+    let mut key_values: Vec<(String, String)> = Vec::new();
+    for token in tokens {
+        match token.find('=') {
+            Some(separator_idx) => key_values.push((token[..separator_idx].to_owned(), token[(separator_idx + 1)..].to_owned())),
+            None => if let Some(key_value) = key_values.last_mut() {
+                key_value.1.push(' ');
+                key_value.1.push_str(token);
+            }
+        }
+    }
+    let proformas: Vec<String> = match key_values.iter().find(|key_value| key_value.0 == "Isoforms") {
+        Some(key_value) => key_value.1.split(',').map(|isoform| isoform.to_owned()).collect(),
+        None => match key_values.iter().find(|key_value| key_value.0 == "ProForma") {
+            Some(key_value) => vec![key_value.1.clone()],
+            None => Vec::new()
+        }
+    };
+    if proformas.is_empty() {
+        return Ok(vec![get_peff_header(identifier, "", &key_values, peff_format)]);
+    }
+    let mut peff_headers: Vec<String> = Vec::new();
+    for (isoform_idx, proforma) in proformas.iter().enumerate() {
+        let modified_peptide = match ModifiedPeptide::from_proforma("", proforma.as_str(), 0, 0, 0, modification_registry) {
+            Ok(modified_peptide) => modified_peptide,
+            Err(err) => return Err(format!("could not parse ProForma '{}' of '{}': {}", proforma, header, err))
+        };
+        let mut mod_res: String = String::new();
+        for (position, modification) in modified_peptide.get_modification_positions() {
+            if is_static_modification(modification, static_modifications) {
+                continue;
+            }
+            match peff_format.get_accession(modification) {
+                Some(accession) => mod_res.push_str(format!("({}|{}|{})", position, accession, modification.get_name()).as_str()),
+                None => return Err(format!("{} in '{}'", get_missing_accession_message(modification, peff_format), header))
+            }
+        }
+        // PEFF identifiers must be unique, so further isoforms get a suffix
+        let isoform_identifier: String = if isoform_idx == 0 { identifier.to_owned() } else { format!("{}_isoform{}", identifier, isoform_idx + 1) };
+        let mut isoform_key_values: Vec<(String, String)> = key_values.clone();
+        if let Some(key_value) = isoform_key_values.iter_mut().find(|key_value| key_value.0 == "ProForma") {
+            key_value.1 = proforma.clone();
+        }
+        peff_headers.push(get_peff_header(isoform_identifier.as_str(), mod_res.as_str(), &isoform_key_values, peff_format));
+    }
+    return Ok(peff_headers);
+}
+
+fn get_peff_header(identifier: &str, mod_res: &str, key_values: &Vec<(String, String)>, peff_format: PeffFormat) -> String {
+    let mut peff_header: String = format!(">{}:{}", PEFF_PREFIX, identifier);
+    if mod_res.len() > 0 {
+        peff_header.push_str(format!(" \\{}={}", peff_format.get_mod_res_key(), mod_res).as_str());
+    }
+    for key_value in key_values.iter() {
+        // the modification summary has no positions, so it must not be confused with PEFF's \ModRes
+        let key: &str = if key_value.0 == "ModRes" { "ModSum" } else { key_value.0.as_str() };
+        peff_header.push_str(format!(" \\{}={}", key, key_value.1).as_str());
+    }
+    return peff_header;
+}
+
+fn is_static_modification(modification: &Modification, static_modifications: &HashMap<char, Modification>) -> bool {
+    return match static_modifications.get(&modification.get_amino_acid_one_letter_code()) {
+        Some(static_modification) => (static_modification.get_accession() == modification.get_accession()) && (static_modification.get_position() == modification.get_position()),
+        None => false
+    };
+}
@@ -0,0 +1,98 @@
+use std::path::Path;
+
+use proteomic::models::mass;
+
+/// A precursor of the batch decoy generation
+pub struct Precursor {
+    id: String,
+    mass: i64   // neutral mass
+}
+
+impl Precursor {
+    pub fn new(id: &str, mass: i64) -> Self {
+        return Self {
+            id: id.to_owned(),
+            mass: mass
+        };
+    }
+
+    pub fn get_id(&self) -> &str {
+        return self.id.as_str();
+    }
+
+    pub fn get_mass(&self) -> i64 {
+        return self.mass;
+    }
+
+    /// Returns the id with all characters replaced which are not allowed in filenames
+    pub fn get_filename_safe_id(&self) -> String {
+        return self.id.chars().map(|character| if character.is_ascii_alphanumeric() | (character == '-') | (character == '.') { character } else { '_' }).collect();
+    }
+}
+
+/// Reads precursors from a CSV-file or a TSV-file (file extension `.tsv` or `.tab`).
+/// The file needs a header with either a column `mass` (neutral mass in Dalton) or the columns `mz` and `charge`.
+/// The optional column `id` names the precursor, otherwise the line number is used.
+pub fn read_precursors(precursor_file_path: &str) -> Vec<Precursor> {
+    let path = Path::new(precursor_file_path);
+    let delimiter: u8 = match path.extension() {
+        Some(extension) => match extension.to_str() {
+            Some("tsv") | Some("tab") => b'\t',
+            _ => b','
+        },
+        None => b','
+    };
+    let mut reader = match csv::ReaderBuilder::new().delimiter(delimiter).trim(csv::Trim::All).from_path(&path) {
+        Ok(reader) => reader,
+        Err(err) => panic!("proteomic::utility::precursor_reader::read_precursors(): could not open '{}': {}", precursor_file_path, err)
+    };
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(err) => panic!("proteomic::utility::precursor_reader::read_precursors(): could not read header of '{}': {}", precursor_file_path, err)
+    };
+    let id_column: Option<usize> = headers.iter().position(|header| header.to_lowercase() == "id");
+    let mass_column: Option<usize> = headers.iter().position(|header| header.to_lowercase() == "mass");
+    let mz_column: Option<usize> = headers.iter().position(|header| header.to_lowercase() == "mz");
+    let charge_column: Option<usize> = headers.iter().position(|header| header.to_lowercase() == "charge");
+    if mass_column.is_none() & (mz_column.is_none() | charge_column.is_none()) {
+        panic!("proteomic::utility::precursor_reader::read_precursors(): '{}' needs a column 'mass' or the columns 'mz' and 'charge'", precursor_file_path);
+    }
+    let mut precursors: Vec<Precursor> = Vec::new();
+    for (row_idx, row_result) in reader.records().enumerate() {
+        let row = match row_result {
+            Ok(row) => row,
+            Err(err) => panic!("proteomic::utility::precursor_reader::read_precursors(): error reading line {} of '{}': {}", row_idx + 2, precursor_file_path, err)
+        };
+        let line_number: usize = row_idx + 2;
+        let id: String = match id_column {
+            Some(column) => match row.get(column) {
+                Some(id) if id.len() > 0 => id.to_owned(),
+                _ => format!("precursor_{}", line_number)
+            },
+            None => format!("precursor_{}", line_number)
+        };
+        let neutral_mass: f64 = match mass_column {
+            Some(column) => parse_column::<f64>(&row, column, "mass", line_number, precursor_file_path),
+            None => {
+                let mz: f64 = parse_column::<f64>(&row, mz_column.unwrap(), "mz", line_number, precursor_file_path);
+                let charge: u8 = parse_column::<u8>(&row, charge_column.unwrap(), "charge", line_number, precursor_file_path);
+                if charge == 0 {
+                    panic!("proteomic::utility::precursor_reader::read_precursors(): charge in line {} of '{}' must be greater than 0", line_number, precursor_file_path);
+                }
+                mass::thomson_to_dalton(mz, charge)
+            }
+        };
+        precursors.push(Precursor::new(id.as_str(), mass::convert_mass_to_int(neutral_mass)));
+    }
+    return precursors;
+}
+
+fn parse_column<T: std::str::FromStr>(row: &csv::StringRecord, column: usize, column_name: &str, line_number: usize, precursor_file_path: &str) -> T {
+    return match row.get(column) {
+        Some(value) => match value.parse::<T>() {
+            Ok(value) => value,
+            Err(_) => panic!("proteomic::utility::precursor_reader::read_precursors(): could not parse {} '{}' in line {} of '{}'", column_name, value, line_number, precursor_file_path)
+        },
+        None => panic!("proteomic::utility::precursor_reader::read_precursors(): missing {} in line {} of '{}'", column_name, line_number, precursor_file_path)
+    };
+}
@@ -0,0 +1,82 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use proteomic::models::peptides::peptide_properties::PeptideProperty;
+
+/// Accepted range of one property
+struct PropertyRange {
+    property: PeptideProperty,
+    min: f64,
+    max: f64,
+    rejections: AtomicUsize
+}
+
+/// Rejects decoys whose physicochemical properties are outside the spread of the spectrum's targets,
+/// so RT-aware rescoring can not separate decoys from targets by their properties alone.
+/// The range of each property is spanned by the targets and widened on both sides by `margin` times its width,
+/// but at least by the minimal half width of the property, so a single target still leaves some room.
+pub struct PropertyFilter {
+    ranges: Vec<PropertyRange>
+}
+
+impl PropertyFilter {
+    /// Creates a new filter
+    ///
+    /// # Arguments
+    ///
+    /// * `target_sequences` - Amino acid sequences of the targets
+    /// * `properties` - Properties which are compared
+    /// * `margin` - Widens the range of each property by this fraction of its width on both sides
+    pub fn new(target_sequences: &Vec<String>, properties: &Vec<PeptideProperty>, margin: f64) -> Self {
+        let mut ranges: Vec<PropertyRange> = Vec::new();
+        if !target_sequences.is_empty() {
+            for property in properties.iter() {
+                let values: Vec<f64> = target_sequences.iter().map(|target_sequence| property.calculate(target_sequence.as_str())).collect();
+                let min: f64 = values.iter().cloned().fold(std::f64::INFINITY, f64::min);
+                let max: f64 = values.iter().cloned().fold(std::f64::NEG_INFINITY, f64::max);
+                let widening: f64 = f64::max((max - min) * margin, Self::get_min_half_width(property));
+                ranges.push(PropertyRange {
+                    property: *property,
+                    min: min - widening,
+                    max: max + widening,
+                    rejections: AtomicUsize::new(0)
+                });
+            }
+        }
+        return Self {
+            ranges: ranges
+        };
+    }
+
+    /// Minimal half width of the accepted range of the property
+    fn get_min_half_width(property: &PeptideProperty) -> f64 {
+        return match property {
+            PeptideProperty::Gravy => 0.1,
+            PeptideProperty::IsoelectricPoint => 0.2,
+            PeptideProperty::RetentionIndex => 2.0
+        };
+    }
+
+    /// Returns true if the filter has any property and any target
+    pub fn is_active(&self) -> bool {
+        return !self.ranges.is_empty();
+    }
+
+    /// Returns true if a property of the given sequence is outside its range and counts the rejection.
+    pub fn rejects(&self, aa_sequence: &str) -> bool {
+        for range in self.ranges.iter() {
+            let value: f64 = range.property.calculate(aa_sequence);
+            if (value < range.min) | (value > range.max) {
+                range.rejections.fetch_add(1, Ordering::Relaxed);
+                return true;
+            }
+        }
+        return false;
+    }
+
+    pub fn get_rejection_summary(&self) -> String {
+        return format!(
+            "property filter rejections: {}",
+            self.ranges.iter().map(|range| format!("{} by {} ({:.2} - {:.2})", range.rejections.load(Ordering::Relaxed), range.property.to_string(), range.min, range.max)).collect::<Vec<String>>().join(", ")
+        );
+    }
+}
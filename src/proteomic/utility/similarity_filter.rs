@@ -0,0 +1,195 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use proteomic::models::mass::neutral_loss::NeutralLoss;
+use proteomic::models::amino_acids::amino_acid::AminoAcid;
+use proteomic::models::amino_acids::modification::Modification;
+use proteomic::models::amino_acids::modification_registry::ModificationRegistry;
+use proteomic::models::peptides::modified_peptide::ModifiedPeptide;
+
+/// A target of the filter, with its precomputed fragment masses
+struct SimilarityTarget {
+    aa_sequence: String,    // generalized sequence
+    fragment_masses: Vec<i64>
+}
+
+/// Rejects decoys which are near-copies of the candidate targets of a spectrum.
+/// A decoy is rejected if it shares a k-mer with a target, if its edit distance to a target is small
+/// or if a large part of its b- and y-ions matches the b- and y-ions of a target.
+/// Each criterion is disabled if it is not set. Sequences are generalized (I and L are treated as J).
+/// The fragments contain the fixed modifications with their neutral losses and the general neutral losses of the registry,
+/// variable modifications are not considered.
+pub struct SimilarityFilter {
+    targets: Vec<SimilarityTarget>,
+    fixed_modifications: HashMap<char, Modification>,
+    general_neutral_losses: Vec<NeutralLoss>,
+    max_edit_distance: Option<usize>,
+    max_shared_fragment_ratio: Option<f64>,
+    fragment_tolerance: i64,
+    kmer_length: Option<usize>,
+    target_kmers: HashSet<String>,
+    edit_distance_rejections: AtomicUsize,
+    shared_fragment_rejections: AtomicUsize,
+    shared_kmer_rejections: AtomicUsize
+}
+
+impl SimilarityFilter {
+    /// Creates a new filter
+    ///
+    /// # Arguments
+    ///
+    /// * `target_sequences` - Amino acid sequences of the targets
+    /// * `modification_registry` - Registry with the fixed modifications and general neutral losses for the fragments
+    /// * `max_edit_distance` - Decoys with an edit distance less or equals to this value are rejected
+    /// * `max_shared_fragment_ratio` - Decoys whose ratio of b- and y-ions which matches the ions of a target is greater or equals to this value are rejected
+    /// * `fragment_tolerance` - Tolerance for matching b- and y-ions
+    /// * `kmer_length` - Decoys which share a k-mer of this length with a target are rejected
+    pub fn new(target_sequences: &Vec<String>, modification_registry: &ModificationRegistry, max_edit_distance: Option<usize>, max_shared_fragment_ratio: Option<f64>, fragment_tolerance: i64, kmer_length: Option<usize>) -> Self {
+        let fixed_modifications: HashMap<char, Modification> = modification_registry.get_fixed_modification_map().clone();
+        let general_neutral_losses: Vec<NeutralLoss> = modification_registry.get_general_neutral_losses().clone();
+        let mut targets: Vec<SimilarityTarget> = Vec::new();
+        let mut target_kmers: HashSet<String> = HashSet::new();
+        for target_sequence in target_sequences.iter() {
+            let aa_sequence: String = AminoAcid::gerneralize_sequence(target_sequence.as_str());
+            if let Some(length) = kmer_length {
+                target_kmers.extend(Self::get_kmers(aa_sequence.as_str(), length));
+            }
+            targets.push(SimilarityTarget {
+                fragment_masses: Self::get_fragment_masses(target_sequence.as_str(), &fixed_modifications, &general_neutral_losses),
+                aa_sequence: aa_sequence
+            });
+        }
+        return Self {
+            targets: targets,
+            fixed_modifications: fixed_modifications,
+            general_neutral_losses: general_neutral_losses,
+            max_edit_distance: max_edit_distance,
+            max_shared_fragment_ratio: max_shared_fragment_ratio,
+            fragment_tolerance: fragment_tolerance,
+            kmer_length: kmer_length,
+            target_kmers: target_kmers,
+            edit_distance_rejections: AtomicUsize::new(0),
+            shared_fragment_rejections: AtomicUsize::new(0),
+            shared_kmer_rejections: AtomicUsize::new(0)
+        };
+    }
+
+    /// Returns true if the filter has any criterion and any target
+    pub fn is_active(&self) -> bool {
+        return !self.targets.is_empty() & (self.max_edit_distance.is_some() | self.max_shared_fragment_ratio.is_some() | self.kmer_length.is_some());
+    }
+
+    /// Returns true if the given sequence is too similar to one of the targets and counts the rejection.
+    pub fn rejects(&self, aa_sequence: &str) -> bool {
+        if !self.is_active() {
+            return false;
+        }
+        let original_aa_sequence: &str = aa_sequence;
+        let aa_sequence: String = AminoAcid::gerneralize_sequence(aa_sequence);
+        if let Some(length) = self.kmer_length {
+            if Self::get_kmers(aa_sequence.as_str(), length).iter().any(|kmer| self.target_kmers.contains(kmer)) {
+                self.shared_kmer_rejections.fetch_add(1, Ordering::Relaxed);
+                return true;
+            }
+        }
+        if let Some(max_edit_distance) = self.max_edit_distance {
+            for target in self.targets.iter() {
+                if Self::edit_distance(aa_sequence.as_str(), target.aa_sequence.as_str(), max_edit_distance) <= max_edit_distance {
+                    self.edit_distance_rejections.fetch_add(1, Ordering::Relaxed);
+                    return true;
+                }
+            }
+        }
+        if let Some(max_shared_fragment_ratio) = self.max_shared_fragment_ratio {
+            let fragment_masses: Vec<i64> = Self::get_fragment_masses(original_aa_sequence, &self.fixed_modifications, &self.general_neutral_losses);
+            for target in self.targets.iter() {
+                if Self::shared_fragment_ratio(&fragment_masses, &target.fragment_masses, self.fragment_tolerance) >= max_shared_fragment_ratio {
+                    self.shared_fragment_rejections.fetch_add(1, Ordering::Relaxed);
+                    return true;
+                }
+            }
+        }
+        return false;
+    }
+
+    pub fn get_edit_distance_rejections(&self) -> usize {
+        return self.edit_distance_rejections.load(Ordering::Relaxed);
+    }
+
+    pub fn get_shared_fragment_rejections(&self) -> usize {
+        return self.shared_fragment_rejections.load(Ordering::Relaxed);
+    }
+
+    pub fn get_shared_kmer_rejections(&self) -> usize {
+        return self.shared_kmer_rejections.load(Ordering::Relaxed);
+    }
+
+    pub fn get_rejection_summary(&self) -> String {
+        return format!(
+            "similarity filter rejections: {} by edit distance, {} by shared fragments, {} by shared k-mers",
+            self.get_edit_distance_rejections(),
+            self.get_shared_fragment_rejections(),
+            self.get_shared_kmer_rejections()
+        );
+    }
+
+    fn get_kmers(aa_sequence: &str, length: usize) -> Vec<String> {
+        let amino_acids: Vec<char> = aa_sequence.chars().collect();
+        if (length == 0) | (amino_acids.len() < length) {
+            return Vec::new();
+        }
+        return amino_acids.windows(length).map(|kmer| kmer.iter().collect()).collect();
+    }
+
+    /// Returns the sorted masses of the b- and y-ions (without charge) of the given sequence, see ModifiedPeptide.get_fragment_masses()
+    fn get_fragment_masses(aa_sequence: &str, fixed_modifications: &HashMap<char, Modification>, general_neutral_losses: &Vec<NeutralLoss>) -> Vec<i64> {
+        return ModifiedPeptide::from_string("", aa_sequence, 0, 0, 0, fixed_modifications).get_fragment_masses(general_neutral_losses);
+    }
+
+    /// Returns the ratio of the fragments which matches a target fragment within the given tolerance
+    fn shared_fragment_ratio(fragment_masses: &Vec<i64>, target_fragment_masses: &Vec<i64>, fragment_tolerance: i64) -> f64 {
+        if fragment_masses.is_empty() {
+            return 0.0;
+        }
+        let mut shared_fragments: usize = 0;
+        let mut target_idx: usize = 0;
+        for fragment_mass in fragment_masses.iter() {
+            while (target_idx < target_fragment_masses.len()) && (target_fragment_masses[target_idx] < fragment_mass - fragment_tolerance) {
+                target_idx += 1;
+            }
+            if (target_idx < target_fragment_masses.len()) && (target_fragment_masses[target_idx] <= fragment_mass + fragment_tolerance) {
+                shared_fragments += 1;
+            }
+        }
+        return shared_fragments as f64 / fragment_masses.len() as f64;
+    }
+
+    /// Levenshtein distance of the given sequences. Stops early if the distance exceeds `limit` and returns `limit + 1` in this case.
+    pub fn edit_distance(sequence_a: &str, sequence_b: &str, limit: usize) -> usize {
+        let a: Vec<char> = sequence_a.chars().collect();
+        let b: Vec<char> = sequence_b.chars().collect();
+        let length_difference = if a.len() > b.len() { a.len() - b.len() } else { b.len() - a.len() };
+        if length_difference > limit {
+            return limit + 1;
+        }
+        let mut previous_row: Vec<usize> = (0..=b.len()).collect();
+        let mut current_row: Vec<usize> = vec![0; b.len() + 1];
+        for i in 1..=a.len() {
+            current_row[0] = i;
+            let mut row_minimum = current_row[0];
+            for j in 1..=b.len() {
+                let substitution_cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+                current_row[j] = std::cmp::min(
+                    std::cmp::min(previous_row[j] + 1, current_row[j - 1] + 1),
+                    previous_row[j - 1] + substitution_cost
+                );
+                row_minimum = std::cmp::min(row_minimum, current_row[j]);
+            }
+            if row_minimum > limit {
+                return limit + 1;
+            }
+            std::mem::swap(&mut previous_row, &mut current_row);
+        }
+        return std::cmp::min(previous_row[b.len()], limit + 1);
+    }
+}
@@ -0,0 +1,242 @@
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter};
+
+use proteomic::utility::decoy_generator::DecoyGenerator;
+
+/// Identifies a target index file
+const FILE_MAGIC: &[u8; 4] = b"MDTI";
+const FILE_VERSION: u32 = 2;
+/// Length of the file header in bytes
+const HEADER_LENGTH: usize = 36;
+/// Number of peptides which are fetched at once while building the index from the database
+const FETCH_SIZE: usize = 100000;
+
+/// Bloom filter over the amino acid sequences of all targets (peptides-table).
+/// It has no false negatives, so a sequence which is not in the index is definitely not a target
+/// and the database needs only to be asked if the index might contain the sequence.
+/// The index is built after the digestion, saved to a file and loaded once per run.
+/// An index built from the database is stamped with the number of peptides and the highest peptide id,
+/// so an index which does not match the peptides-table anymore is detected when it is loaded.
+pub struct TargetIndex {
+    bits: Vec<u64>,
+    number_of_bits: u64,
+    number_of_hashes: u32,
+    number_of_sequences: u64,
+    max_peptide_id: u64
+}
+
+impl TargetIndex {
+    /// Creates an empty index
+    ///
+    /// # Arguments
+    ///
+    /// * `expected_number_of_sequences` - Number of sequences which will be inserted
+    /// * `false_positive_rate` - Expected rate of sequences which are not in the index but reported as contained
+    pub fn new(expected_number_of_sequences: u64, false_positive_rate: f64) -> Self {
+        let expected_number_of_sequences: f64 = std::cmp::max(expected_number_of_sequences, 1) as f64;
+        let ln_2: f64 = std::f64::consts::LN_2;
+        let number_of_bits: u64 = std::cmp::max((-expected_number_of_sequences * false_positive_rate.ln() / (ln_2 * ln_2)).ceil() as u64, 64);
+        let number_of_hashes: u32 = std::cmp::max((number_of_bits as f64 / expected_number_of_sequences * ln_2).round() as u32, 1);
+        return Self {
+            bits: vec![0; ((number_of_bits + 63) / 64) as usize],
+            number_of_bits: number_of_bits,
+            number_of_hashes: number_of_hashes,
+            number_of_sequences: 0,
+            max_peptide_id: 0
+        };
+    }
+
+    pub fn get_number_of_sequences(&self) -> u64 {
+        return self.number_of_sequences;
+    }
+
+    pub fn get_max_peptide_id(&self) -> u64 {
+        return self.max_peptide_id;
+    }
+
+    /// Returns two independent hashes for double hashing. The second one must be odd to reach all bits.
+    fn get_hashes(aa_sequence: &str) -> (u64, u64) {
+        let hash: u64 = DecoyGenerator::derive_seed_from_str(0, aa_sequence);
+        return (hash, DecoyGenerator::derive_seed(hash, 0) | 1);
+    }
+
+    /// Returns the number of peptides and the highest peptide id, which change whenever peptides are added or removed.
+    fn get_database_stamp(conn: &postgres::GenericConnection) -> (u64, u64) {
+        return match conn.query("SELECT count(*), COALESCE(max(id), 0) FROM peptides;", &[]) {
+            Ok(rows) => {
+                let number_of_peptides: i64 = rows.get(0).get(0);
+                let max_peptide_id: i64 = rows.get(0).get(1);
+                (number_of_peptides as u64, max_peptide_id as u64)
+            },
+            Err(err) => panic!("proteomic::utility::target_index::TargetIndex::get_database_stamp(): could not count peptides: {}", err)
+        };
+    }
+
+    pub fn insert(&mut self, aa_sequence: &str) {
+        let hashes = Self::get_hashes(aa_sequence);
+        for hash_idx in 0..self.number_of_hashes as u64 {
+            let bit: u64 = hashes.0.wrapping_add(hash_idx.wrapping_mul(hashes.1)) % self.number_of_bits;
+            self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+        self.number_of_sequences += 1;
+    }
+
+    /// Returns false if the sequence is definitely not a target, true if it might be a target.
+    pub fn might_contain(&self, aa_sequence: &str) -> bool {
+        let hashes = Self::get_hashes(aa_sequence);
+        for hash_idx in 0..self.number_of_hashes as u64 {
+            let bit: u64 = hashes.0.wrapping_add(hash_idx.wrapping_mul(hashes.1)) % self.number_of_bits;
+            if self.bits[(bit / 64) as usize] & (1 << (bit % 64)) == 0 {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    /// Builds the index from the peptides-table. The peptides are fetched with a cursor, so they are not loaded into memory at once.
+    pub fn build_from_database(conn: &postgres::Connection, false_positive_rate: f64) -> Self {
+        let transaction = match conn.transaction() {
+            Ok(transaction) => transaction,
+            Err(err) => panic!("proteomic::utility::target_index::TargetIndex::build_from_database(): could not start transaction: {}", err)
+        };
+        // the stamp is taken in the same transaction as the cursor, so it matches the indexed peptides
+        match transaction.execute("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ;", &[]) {
+            Ok(_) => (),
+            Err(err) => panic!("proteomic::utility::target_index::TargetIndex::build_from_database(): could not set isolation level: {}", err)
+        }
+        let (number_of_peptides, max_peptide_id) = Self::get_database_stamp(&transaction);
+        let mut target_index = Self::new(number_of_peptides, false_positive_rate);
+        target_index.max_peptide_id = max_peptide_id;
+        match transaction.execute("DECLARE target_index_cursor CURSOR FOR SELECT aa_sequence FROM peptides;", &[]) {
+            Ok(_) => (),
+            Err(err) => panic!("proteomic::utility::target_index::TargetIndex::build_from_database(): could not declare cursor: {}", err)
+        }
+        let fetch_statement: String = format!("FETCH {} FROM target_index_cursor;", FETCH_SIZE);
+        loop {
+            let rows = match transaction.query(fetch_statement.as_str(), &[]) {
+                Ok(rows) => rows,
+                Err(err) => panic!("proteomic::utility::target_index::TargetIndex::build_from_database(): could not fetch peptides: {}", err)
+            };
+            if rows.is_empty() {
+                break;
+            }
+            for row in rows.iter() {
+                let aa_sequence: String = row.get(0);
+                target_index.insert(aa_sequence.as_str());
+            }
+        }
+        match transaction.finish() {
+            Ok(_) => (),
+            Err(err) => panic!("proteomic::utility::target_index::TargetIndex::build_from_database(): could not finish transaction: {}", err)
+        }
+        return target_index;
+    }
+
+    /// Writes the index to the given file
+    pub fn save(&self, path: &str) {
+        let file = match File::create(path) {
+            Ok(file) => file,
+            Err(err) => panic!("proteomic::utility::target_index::TargetIndex.save(): could not create '{}': {}", path, err)
+        };
+        let mut writer = BufWriter::new(file);
+        let mut header: Vec<u8> = Vec::new();
+        header.extend_from_slice(FILE_MAGIC);
+        header.extend_from_slice(&Self::u64_to_bytes(FILE_VERSION as u64)[0..4]);
+        header.extend_from_slice(&Self::u64_to_bytes(self.number_of_bits));
+        header.extend_from_slice(&Self::u64_to_bytes(self.number_of_hashes as u64)[0..4]);
+        header.extend_from_slice(&Self::u64_to_bytes(self.number_of_sequences));
+        header.extend_from_slice(&Self::u64_to_bytes(self.max_peptide_id));
+        match writer.write_all(header.as_slice()) {
+            Ok(_) => (),
+            Err(err) => panic!("proteomic::utility::target_index::TargetIndex.save(): could not write to '{}': {}", path, err)
+        }
+        for word in self.bits.iter() {
+            match writer.write_all(&Self::u64_to_bytes(*word)) {
+                Ok(_) => (),
+                Err(err) => panic!("proteomic::utility::target_index::TargetIndex.save(): could not write to '{}': {}", path, err)
+            }
+        }
+        match writer.flush() {
+            Ok(_) => (),
+            Err(err) => panic!("proteomic::utility::target_index::TargetIndex.save(): could not write to '{}': {}", path, err)
+        }
+    }
+
+    /// Reads an index from the given file
+    pub fn load(path: &str) -> Self {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) => panic!("proteomic::utility::target_index::TargetIndex::load(): could not open '{}': {}", path, err)
+        };
+        let mut reader = BufReader::new(file);
+        let mut header: [u8; HEADER_LENGTH] = [0; HEADER_LENGTH];
+        match reader.read_exact(&mut header) {
+            Ok(_) => (),
+            Err(err) => panic!("proteomic::utility::target_index::TargetIndex::load(): could not read header of '{}': {}", path, err)
+        }
+        if &header[0..4] != FILE_MAGIC {
+            panic!("proteomic::utility::target_index::TargetIndex::load(): '{}' is not a target index", path);
+        }
+        let version: u32 = Self::bytes_to_u64(&header[4..8]) as u32;
+        if version != FILE_VERSION {
+            panic!("proteomic::utility::target_index::TargetIndex::load(): '{}' has version {}, expected {}", path, version, FILE_VERSION);
+        }
+        let number_of_bits: u64 = Self::bytes_to_u64(&header[8..16]);
+        let number_of_hashes: u32 = Self::bytes_to_u64(&header[16..20]) as u32;
+        let number_of_sequences: u64 = Self::bytes_to_u64(&header[20..28]);
+        let max_peptide_id: u64 = Self::bytes_to_u64(&header[28..36]);
+        let mut bits: Vec<u64> = Vec::with_capacity(((number_of_bits + 63) / 64) as usize);
+        let mut word: [u8; 8] = [0; 8];
+        for _ in 0..(number_of_bits + 63) / 64 {
+            match reader.read_exact(&mut word) {
+                Ok(_) => bits.push(Self::bytes_to_u64(&word)),
+                Err(err) => panic!("proteomic::utility::target_index::TargetIndex::load(): '{}' is truncated: {}", path, err)
+            }
+        }
+        return Self {
+            bits: bits,
+            number_of_bits: number_of_bits,
+            number_of_hashes: number_of_hashes,
+            number_of_sequences: number_of_sequences,
+            max_peptide_id: max_peptide_id
+        };
+    }
+
+    /// Returns true if the index was built from the current state of the peptides-table.
+    pub fn matches_database(&self, conn: &postgres::Connection) -> bool {
+        return Self::get_database_stamp(conn) == (self.number_of_sequences, self.max_peptide_id);
+    }
+
+    /// Reads an index from the given file and refuses it if the peptides-table changed since the index was built.
+    /// A stale index may miss new targets, so decoys could be identical to targets.
+    pub fn load_for_database(path: &str, conn: &postgres::Connection) -> Self {
+        let target_index = Self::load(path);
+        if !target_index.matches_database(conn) {
+            let (number_of_peptides, max_peptide_id) = Self::get_database_stamp(conn);
+            panic!(
+                "proteomic::utility::target_index::TargetIndex::load_for_database(): '{}' was built for {} peptides (max. id {}) but the database contains {} peptides (max. id {}), rebuild it with target-index",
+                path, target_index.number_of_sequences, target_index.max_peptide_id, number_of_peptides, max_peptide_id
+            );
+        }
+        return target_index;
+    }
+
+    /// Little endian bytes of the given value
+    fn u64_to_bytes(value: u64) -> [u8; 8] {
+        let mut bytes: [u8; 8] = [0; 8];
+        for idx in 0..8 {
+            bytes[idx] = (value >> (idx * 8)) as u8;
+        }
+        return bytes;
+    }
+
+    /// Value of the given little endian bytes (at most 8)
+    fn bytes_to_u64(bytes: &[u8]) -> u64 {
+        let mut value: u64 = 0;
+        for (idx, byte) in bytes.iter().enumerate() {
+            value |= (*byte as u64) << (idx * 8);
+        }
+        return value;
+    }
+}
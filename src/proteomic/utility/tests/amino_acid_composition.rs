@@ -0,0 +1,33 @@
+use std::collections::HashMap;
+
+use rand::prelude::*;
+
+use proteomic::models::peptides::peptide::Peptide;
+use proteomic::utility::amino_acid_composition::AminoAcidComposition;
+
+#[test]
+/// Adds a target twice and checks that it is counted once.
+pub fn test_same_sequence_is_counted_once() {
+    let mut amino_acid_composition = AminoAcidComposition::new();
+    amino_acid_composition.add_peptide(&Peptide::new("WWWWK", 0));
+    amino_acid_composition.add_peptide(&Peptide::new("WWWWK", 0));
+    amino_acid_composition.add_peptide(&Peptide::new("AAK", 0));
+    assert_eq!(amino_acid_composition.get_number_of_amino_acids(), 8);
+}
+
+#[test]
+/// Draws 100000 amino acids from a tryptophan rich composition and checks that the drawn frequencies track the frequencies of the composition.
+pub fn test_drawn_frequencies_track_composition() {
+    let mut amino_acid_composition = AminoAcidComposition::new();
+    amino_acid_composition.add_peptide(&Peptide::new("WWWWWWWWWWWWWWWWWWWWGGGGGGGGGGK", 0));
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    let number_of_draws: usize = 100000;
+    for _ in 0..number_of_draws {
+        *counts.entry(amino_acid_composition.choose_amino_acid(&mut rng)).or_insert(0) += 1;
+    }
+    for aa_one_letter_code in ['W', 'G', 'K', 'A'].iter() {
+        let drawn_frequency: f64 = *counts.get(aa_one_letter_code).unwrap_or(&0) as f64 / number_of_draws as f64;
+        assert!((drawn_frequency - amino_acid_composition.get_frequency(*aa_one_letter_code)).abs() < 0.01, "{}: {}", aa_one_letter_code, drawn_frequency);
+    }
+}
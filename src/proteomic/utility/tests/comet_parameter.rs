@@ -0,0 +1,37 @@
+use std::path::Path;
+
+use proteomic::models::amino_acids::label_channel::LabelChannel;
+use proteomic::models::amino_acids::modification::{Modification, ModificationPosition};
+use proteomic::models::amino_acids::modification_registry::ModificationRegistry;
+use proteomic::models::mass::mass_type::MassType;
+use proteomic::utility::comet_parameter;
+use proteomic::utility::peff::{self, PeffFormat};
+
+#[test]
+/// Checks that the label of a channel is a static modification in the parameters of the channel and not annotated in its headers,
+/// while the other channel is searched without it
+pub fn test_channel_parameters_and_headers() {
+    let heavy_label = Modification::new("unimod:259", "Label:13C(6)15N(2)", ModificationPosition::Anywhere, true, 'K', 8.014199);
+    let mut registry = ModificationRegistry::from_modifications(&vec![
+        Modification::new("unimod:35", "Oxidation", ModificationPosition::Anywhere, false, 'M', 15.994915)
+    ]);
+    let mut heavy_channel = LabelChannel::new("heavy");
+    heavy_channel.add_label(heavy_label.clone()).unwrap();
+    registry.add_label_channel(LabelChannel::new("light")).unwrap();
+    registry.add_label_channel(heavy_channel).unwrap();
+    let light_registry = registry.get_channel_registry(&registry.get_label_channels()[0]);
+    let heavy_registry = registry.get_channel_registry(&registry.get_label_channels()[1]);
+    let static_label_param: String = heavy_label.to_comet_static_modification_param(MassType::Monoisotopic);
+    let light_params: String = comet_parameter::new("# comet_version 2019.01 rev. 4", &light_registry, Path::new("spectrum.light.fasta"), 10, 3, 0.02, 5, 5, None, "", MassType::Monoisotopic);
+    let heavy_params: String = comet_parameter::new("# comet_version 2019.01 rev. 4", &heavy_registry, Path::new("spectrum.heavy.fasta"), 10, 3, 0.02, 5, 5, None, "", MassType::Monoisotopic);
+    assert!(!light_params.contains(static_label_param.as_str()));
+    assert!(heavy_params.contains(static_label_param.as_str()));
+    assert!(heavy_params.contains("database_name = spectrum.heavy.fasta\n"));
+    // only the oxidation is a variable modification
+    assert_eq!(light_params.matches("variable_mod0").count(), 1);
+    assert_eq!(heavy_params.matches("variable_mod0").count(), 1);
+    let header = ">PEPTIDE_1 ModRes=(1|unimod:35|Oxidation) ProForma=PEPM[UNIMOD:35]K Channel=heavy 2";
+    let peff_headers: Vec<String> = peff::get_entry_headers(header, &heavy_registry, heavy_registry.get_fixed_modification_map(), PeffFormat::Unimod).unwrap();
+    assert!(peff_headers[0].contains("\\ModResUnimod=(4|UNIMOD:35|Oxidation) "));
+    assert!(!peff_headers[0].contains("UNIMOD:259"));
+}
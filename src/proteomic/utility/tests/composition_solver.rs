@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use rand::prelude::*;
+
+use proteomic::models::mass;
+use proteomic::models::mass::mass_type::MassType;
+use proteomic::models::amino_acids::amino_acid::AminoAcid;
+use proteomic::models::amino_acids::modification::{Modification, ModificationPosition};
+use proteomic::utility::composition_solver::{CompositionSolver, DEFAULT_MASS_RESOLUTION};
+
+#[test]
+/// Draws decoys for the mass of a glycine rich 40-mer with a tolerance of 5 ppm, where the rounding errors of the binned glycine masses add up to several bins,
+/// and checks the masses of the drawn sequences independently of the solver.
+pub fn test_drawn_decoys_hit_mass_tolerance() {
+    let precursor_mass: i64 = AminoAcid::get_sequence_weight("GGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGK");
+    let precursor_tolerance = mass::calculate_precursor_tolerance(precursor_mass, 5, 5);
+    let modifications: HashMap<char, Modification> = HashMap::new();
+    let variable_modifications: HashMap<char, Vec<Modification>> = HashMap::new();
+    let solver = CompositionSolver::new(precursor_tolerance.0, precursor_tolerance.1, &modifications, &variable_modifications, 0, DEFAULT_MASS_RESOLUTION, MassType::Monoisotopic);
+    assert!(solver.has_solutions());
+    let mut rng = StdRng::seed_from_u64(42);
+    for _ in 0..10 {
+        match solver.draw_decoy(&mut rng, precursor_mass, precursor_tolerance.0, precursor_tolerance.1, &modifications) {
+            Some(decoy) => {
+                let weight: i64 = AminoAcid::get_sequence_weight(decoy.get_aa_sequence().as_str());
+                assert!((precursor_tolerance.0 <= weight) & (weight <= precursor_tolerance.1));
+            },
+            None => panic!("proteomic::utility::tests::composition_solver.test_drawn_decoys_hit_mass_tolerance(): no decoy drawn")
+        }
+    }
+}
+
+#[test]
+/// Checks that a mass lighter than the lightest amino acid has no solution
+pub fn test_no_solution_below_lightest_amino_acid() {
+    let precursor_mass: i64 = mass::convert_mass_to_int(50.0);
+    let precursor_tolerance = mass::calculate_precursor_tolerance(precursor_mass, 5, 5);
+    let modifications: HashMap<char, Modification> = HashMap::new();
+    let variable_modifications: HashMap<char, Vec<Modification>> = HashMap::new();
+    let solver = CompositionSolver::new(precursor_tolerance.0, precursor_tolerance.1, &modifications, &variable_modifications, 0, DEFAULT_MASS_RESOLUTION, MassType::Monoisotopic);
+    assert!(!solver.has_solutions());
+}
+
+#[test]
+/// Draws decoys for the average mass of "DHWVHVLVPMGFVIGCYLDR" and checks that their weight is the average mass of their sequence.
+pub fn test_drawn_decoys_hit_average_mass_tolerance() {
+    let precursor_mass: i64 = AminoAcid::get_sequence_mass(AminoAcid::gerneralize_sequence("DHWVHVLVPMGFVIGCYLDR").as_str(), MassType::Average);
+    let precursor_tolerance = mass::calculate_precursor_tolerance(precursor_mass, 5, 5);
+    let modifications: HashMap<char, Modification> = HashMap::new();
+    let variable_modifications: HashMap<char, Vec<Modification>> = HashMap::new();
+    let solver = CompositionSolver::new(precursor_tolerance.0, precursor_tolerance.1, &modifications, &variable_modifications, 0, DEFAULT_MASS_RESOLUTION, MassType::Average);
+    let mut rng = StdRng::seed_from_u64(42);
+    match solver.draw_decoy(&mut rng, precursor_mass, precursor_tolerance.0, precursor_tolerance.1, &modifications) {
+        Some(decoy) => {
+            assert!(decoy.hits_mass_tolerance());
+            assert_eq!(decoy.get_weight(), AminoAcid::get_sequence_mass(decoy.get_aa_sequence().as_str(), MassType::Average));
+        },
+        None => panic!("proteomic::utility::tests::composition_solver.test_drawn_decoys_hit_average_mass_tolerance(): no decoy drawn")
+    }
+}
+
+#[test]
+/// Draws decoys with a fixed TMT modification at the N-terminus of any amino acid, which every decoy carries,
+/// and checks that the solver finds sequences whose mass including TMT hits the precursor tolerance.
+pub fn test_drawn_decoys_with_fixed_n_terminal_modification() {
+    let tmt: Modification = Modification::new("unimod:737", "TMT6plex", ModificationPosition::NTerminus, true, '*', 229.162932);
+    let mut modifications: HashMap<char, Modification> = HashMap::new();
+    modifications.insert('*', tmt.clone());
+    let variable_modifications: HashMap<char, Vec<Modification>> = HashMap::new();
+    let precursor_mass: i64 = AminoAcid::get_sequence_weight(AminoAcid::gerneralize_sequence("DHWVHVLVPMGFVIGCYLDR").as_str()) + tmt.get_mono_mass();
+    let precursor_tolerance = mass::calculate_precursor_tolerance(precursor_mass, 5, 5);
+    let solver = CompositionSolver::new(precursor_tolerance.0, precursor_tolerance.1, &modifications, &variable_modifications, 0, DEFAULT_MASS_RESOLUTION, MassType::Monoisotopic);
+    assert_eq!(CompositionSolver::get_fixed_terminal_mass_shift(&modifications, MassType::Monoisotopic), tmt.get_mono_mass());
+    let mut rng = StdRng::seed_from_u64(42);
+    for _ in 0..10 {
+        match solver.draw_decoy(&mut rng, precursor_mass, precursor_tolerance.0, precursor_tolerance.1, &modifications) {
+            Some(decoy) => {
+                assert!(decoy.hits_mass_tolerance());
+                assert_eq!(decoy.get_weight(), AminoAcid::get_sequence_weight(decoy.get_aa_sequence().as_str()) + tmt.get_mono_mass());
+            },
+            None => panic!("proteomic::utility::tests::composition_solver.test_drawn_decoys_with_fixed_n_terminal_modification(): no decoy drawn")
+        }
+    }
+}
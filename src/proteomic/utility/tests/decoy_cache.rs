@@ -0,0 +1,21 @@
+use proteomic::utility::decoy_cache::DecoyCache;
+
+#[test]
+/// Checks that only decoys within the window are returned and that a decoy is not returned after it reached its reuse limit.
+pub fn test_decoys_are_reused_up_to_the_limit() {
+    let mut decoy_cache = DecoyCache::new(1);
+    decoy_cache.insert(1000000000, "DECOY_AAAK", "AAAK");
+    decoy_cache.insert(1000002000, "DECOY_GGGR", "GGGR");
+    decoy_cache.insert(1000100000, "DECOY_MMMK", "MMMK");
+    let decoys = decoy_cache.get_decoys_in_window(999999000, 1000003000, 10);
+    assert_eq!(decoys.len(), 2);
+    assert!(decoys.iter().all(|decoy| decoy.get_aa_sequence() != "MMMK"));
+    // first usage by the spectrum which found the decoy, second usage by a neighbouring spectrum
+    decoy_cache.record_usage("AAAK");
+    assert!(decoy_cache.can_use("AAAK"));
+    decoy_cache.record_usage("AAAK");
+    assert!(!decoy_cache.can_use("AAAK"));
+    let decoys = decoy_cache.get_decoys_in_window(999999000, 1000003000, 10);
+    assert_eq!(decoys.len(), 1);
+    assert_eq!(decoys[0].get_aa_sequence(), "GGGR");
+}
@@ -0,0 +1,101 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use proteomic::models::mass;
+use proteomic::models::amino_acids::amino_acid::AminoAcid;
+use proteomic::models::amino_acids::modification::Modification;
+use proteomic::models::peptides::peptide_interface::PeptideInterface;
+use proteomic::utility::decoy_generator::{DecoyGenerator, GenerationStrategy};
+
+#[test]
+/// Generates decoys without a database, rejecting every sequence which starts with 'A' as target,
+/// and checks that all decoys hit the precursor tolerance and none of them is a target.
+pub fn test_in_memory_generation_respects_target_membership() {
+    let modifications: HashMap<char, Modification> = HashMap::new();
+    let variable_modifications: HashMap<char, Vec<Modification>> = HashMap::new();
+    let precursor_mass: i64 = AminoAcid::get_sequence_weight(AminoAcid::gerneralize_sequence("DHWVHVLVPMGFVIGCYLDR").as_str());
+    let precursor_tolerance = mass::calculate_precursor_tolerance(precursor_mass, 5, 5);
+    let mut generator = DecoyGenerator::new(precursor_mass, precursor_tolerance.0, precursor_tolerance.1, 2, 0, &modifications, &variable_modifications, 60);
+    generator.set_seed(42);
+    generator.set_generation_strategy(GenerationStrategy::Composition);
+    generator.set_target_membership(Arc::new(|aa_sequence: &str| aa_sequence.starts_with('A')));
+    let generation_result = generator.generate_decoys(10);
+    assert!(generation_result.is_success());
+    assert_eq!(generation_result.get_statistics().generated_decoys, 10);
+    let decoys = generator.get_decoys().lock().unwrap();
+    assert_eq!(decoys.len(), 10);
+    for decoy in decoys.iter() {
+        assert!(!decoy.get_aa_sequence().starts_with('A'));
+        let weight: i64 = AminoAcid::get_sequence_weight(decoy.get_aa_sequence());
+        assert!((precursor_tolerance.0 <= weight) & (weight <= precursor_tolerance.1));
+    }
+}
+
+#[test]
+/// Generates decoys twice with the same seed and several workers and checks that both runs generate the same decoys.
+pub fn test_generation_is_reproducible_with_seed() {
+    let modifications: HashMap<char, Modification> = HashMap::new();
+    let variable_modifications: HashMap<char, Vec<Modification>> = HashMap::new();
+    let precursor_mass: i64 = AminoAcid::get_sequence_weight(AminoAcid::gerneralize_sequence("DHWVHVLVPMGFVIGCYLDR").as_str());
+    let precursor_tolerance = mass::calculate_precursor_tolerance(precursor_mass, 5, 5);
+    let mut decoy_sets: Vec<HashSet<String>> = Vec::new();
+    for _ in 0..2 {
+        let mut generator = DecoyGenerator::new(precursor_mass, precursor_tolerance.0, precursor_tolerance.1, 4, 0, &modifications, &variable_modifications, 60);
+        generator.set_seed(42);
+        generator.set_generation_strategy(GenerationStrategy::Composition);
+        generator.set_target_membership(Arc::new(|_aa_sequence: &str| false));
+        assert!(generator.generate_decoys(20).is_success());
+        let decoys = generator.get_decoys().lock().unwrap();
+        decoy_sets.push(decoys.iter().map(|decoy| decoy.get_aa_sequence().to_owned()).collect());
+    }
+    assert!(!decoy_sets[0].is_empty());
+    assert_eq!(decoy_sets[0], decoy_sets[1]);
+}
+
+#[test]
+/// Generates decoys with a wide precursor tolerance while every sequence is a target, so nearly every candidate is a rejected direct hit,
+/// and checks that each candidate is counted as at most one hit and each hit as exactly one outcome.
+pub fn test_each_hit_is_counted_once() {
+    let modifications: HashMap<char, Modification> = HashMap::new();
+    let variable_modifications: HashMap<char, Vec<Modification>> = HashMap::new();
+    let precursor_mass: i64 = AminoAcid::get_sequence_weight(AminoAcid::gerneralize_sequence("DHWVHVLVPMGFVIGCYLDR").as_str());
+    let precursor_tolerance = mass::calculate_precursor_tolerance(precursor_mass, 50, 50);
+    let mut generator = DecoyGenerator::new(precursor_mass, precursor_tolerance.0, precursor_tolerance.1, 1, 0, &modifications, &variable_modifications, 1);
+    generator.set_seed(42);
+    generator.set_generation_strategy(GenerationStrategy::RandomWalk);
+    generator.set_target_membership(Arc::new(|_aa_sequence: &str| true));
+    let generation_result = generator.generate_decoys(10);
+    assert!(!generation_result.is_success());
+    let statistics = generation_result.get_statistics();
+    let hits: usize = statistics.direct_hits + statistics.hits_after_swaps + statistics.hits_after_variable_modifications;
+    assert!(statistics.direct_hits > 0);
+    assert!(hits <= statistics.candidates_built);
+    assert_eq!(statistics.generated_decoys, 0);
+    assert_eq!(statistics.is_peptide_rejections, hits);
+    assert_eq!(statistics.duplicates + statistics.cross_worker_duplicates + statistics.database_duplicates, 0);
+}
+
+#[test]
+/// Generates decoys for the mass of "GAGA", which has only 16 sequences, with several workers, so the workers find the same decoys,
+/// and checks that the missing decoys are generated in further rounds and the result is still reproducible.
+pub fn test_colliding_workers_reach_number_of_decoys() {
+    let modifications: HashMap<char, Modification> = HashMap::new();
+    let variable_modifications: HashMap<char, Vec<Modification>> = HashMap::new();
+    let precursor_mass: i64 = AminoAcid::get_sequence_weight("GAGA");
+    let precursor_tolerance = mass::calculate_precursor_tolerance(precursor_mass, 5, 5);
+    let mut decoy_sets: Vec<HashSet<String>> = Vec::new();
+    for _ in 0..2 {
+        let mut generator = DecoyGenerator::new(precursor_mass, precursor_tolerance.0, precursor_tolerance.1, 4, 0, &modifications, &variable_modifications, 60);
+        generator.set_seed(42);
+        generator.set_generation_strategy(GenerationStrategy::Composition);
+        generator.set_target_membership(Arc::new(|_aa_sequence: &str| false));
+        let generation_result = generator.generate_decoys(12);
+        assert!(generation_result.is_success());
+        assert!(generation_result.get_statistics().cross_worker_duplicates > 0);
+        assert_eq!(generation_result.get_statistics().generated_decoys, 12);
+        let decoys = generator.get_decoys().lock().unwrap();
+        assert_eq!(decoys.len(), 12);
+        decoy_sets.push(decoys.iter().map(|decoy| decoy.get_aa_sequence().to_owned()).collect());
+    }
+    assert_eq!(decoy_sets[0], decoy_sets[1]);
+}
@@ -0,0 +1,21 @@
+use proteomic::utility::length_distribution::LengthDistribution;
+
+#[test]
+/// Splits 10 decoys on the target lengths 8, 8, 8, 12 and checks if the quotas follow the target lengths.
+pub fn test_quotas_follow_target_lengths() {
+    let length_distribution = LengthDistribution::from_lengths(&vec![8, 8, 8, 12, 70], 5, 50);
+    let quotas = length_distribution.get_quotas(10);
+    assert_eq!(quotas, vec![(8, 8), (12, 2)]);
+    assert_eq!(quotas.iter().map(|quota| quota.1).sum::<usize>(), 10);
+}
+
+#[test]
+/// Splits the quotas of 5 decoys on 4 workers and checks that the workers together follow the quotas, although each worker generates at most 2 decoys.
+pub fn test_split_quotas_on_workers() {
+    let quotas: Vec<(usize, usize)> = vec![(8, 2), (12, 2), (15, 1)];
+    let worker_quotas = LengthDistribution::split_quotas(&quotas, 4);
+    assert_eq!(worker_quotas, vec![vec![(8, 1), (12, 0), (15, 1)], vec![(8, 1), (12, 0), (15, 0)], vec![(8, 0), (12, 1), (15, 0)], vec![(8, 0), (12, 1), (15, 0)]]);
+    for (idx, &(length, quota)) in quotas.iter().enumerate() {
+        assert_eq!(worker_quotas.iter().map(|worker_quota| worker_quota[idx].1).sum::<usize>(), quota, "length {}", length);
+    }
+}
@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+use rand::prelude::*;
+
+use proteomic::models::mass;
+use proteomic::models::mass::mass_type::MassType;
+use proteomic::models::amino_acids::amino_acid::AminoAcid;
+use proteomic::models::amino_acids::modification::Modification;
+use proteomic::models::peptides::modified_peptide::ModifiedPeptide;
+use proteomic::utility::decoy_generator::DecoyGenerator;
+use proteomic::utility::mass_fitting::{MassFittingOptions, TwoAminoAcidSubstitutionTable};
+
+#[test]
+/// Checks that the table finds a pair with exactly the weight of two amino acids
+pub fn test_closest_pair_has_exact_weight() {
+    let modifications: HashMap<char, Modification> = HashMap::new();
+    let substitution_table = TwoAminoAcidSubstitutionTable::new(&modifications, MassType::Monoisotopic);
+    let weight: i64 = AminoAcid::get('W').get_mono_mass() + AminoAcid::get('G').get_mono_mass();
+    match substitution_table.get_closest_pair(weight) {
+        Some(pair) => assert_eq!(pair.0, weight),
+        None => panic!("proteomic::utility::tests::mass_fitting.test_closest_pair_has_exact_weight(): no pair found")
+    }
+}
+
+#[test]
+/// Fits a sequence into the 5 ppm tolerance of another sequence with pair substitutions and length changes and checks the length limits.
+pub fn test_fitting_with_pair_substitutions_and_length_changes() {
+    let modifications: HashMap<char, Modification> = HashMap::new();
+    let precursor_mass: i64 = AminoAcid::get_sequence_weight(AminoAcid::gerneralize_sequence("DHWVHVLVPMGFVIGCYLDR").as_str());
+    let precursor_tolerance = mass::calculate_precursor_tolerance(precursor_mass, 5, 5);
+    let mut mass_fitting_options = MassFittingOptions::new(1000);
+    mass_fitting_options.set_pair_substitutions(true);
+    mass_fitting_options.set_length_changes(true, 15, 25);
+    let variable_modifications: HashMap<char, Vec<Modification>> = HashMap::new();
+    let substitution_table = TwoAminoAcidSubstitutionTable::new(&modifications, MassType::Monoisotopic);
+    let substitute_map = *DecoyGenerator::get_one_amino_acid_substitute_map(&modifications, MassType::Monoisotopic);
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut decoy = ModifiedPeptide::decoy_from_string("AAAAAAAAAAAAAAAAAAAK", precursor_mass, precursor_tolerance.0, precursor_tolerance.1, &modifications);
+    assert!(decoy.swap_amino_acids_to_hit_mass_tolerance(&substitute_map, &substitution_table, &mass_fitting_options, &modifications, 0, &variable_modifications, None, &mut rng));
+    assert!(decoy.hits_mass_tolerance());
+    assert_eq!(decoy.get_weight(), AminoAcid::get_sequence_weight(decoy.get_aa_sequence().as_str()));
+    assert!((15 <= decoy.get_length()) & (decoy.get_length() <= 25));
+}
@@ -0,0 +1,13 @@
+mod amino_acid_composition;
+mod comet_parameter;
+mod composition_solver;
+mod decoy_cache;
+mod decoy_generator;
+mod length_distribution;
+mod mass_fitting;
+mod peff;
+mod precursor_reader;
+mod property_filter;
+mod similarity_filter;
+mod target_index;
+mod unimod_reader;
@@ -0,0 +1,47 @@
+use proteomic::models::amino_acids::modification::{Modification, ModificationPosition};
+use proteomic::models::amino_acids::modification_registry::ModificationRegistry;
+use proteomic::utility::peff::{self, PeffFormat};
+
+#[test]
+/// Checks that the variable modification of the ProForma-tag is annotated at its position, but not the static modification
+pub fn test_entry_header() {
+    let registry = ModificationRegistry::from_modifications(&vec![
+        Modification::new("unimod:4", "Carbamidomethyl", ModificationPosition::Anywhere, true, 'C', 57.021464),
+        Modification::new("unimod:35", "Oxidation", ModificationPosition::Anywhere, false, 'M', 15.994915)
+    ]);
+    let header = ">PEPTIDE_1 ModRes=(1|unimod:35|Oxidation)(1|unimod:4|Carbamidomethyl) ProForma=PEPC[UNIMOD:4]M[UNIMOD:35]K Channel=heavy 2";
+    assert_eq!(
+        vec![">mdg:PEPTIDE_1 \\ModResUnimod=(5|UNIMOD:35|Oxidation) \\ModSum=(1|unimod:35|Oxidation)(1|unimod:4|Carbamidomethyl) \\ProForma=PEPC[UNIMOD:4]M[UNIMOD:35]K \\Channel=heavy 2".to_owned()],
+        peff::get_entry_headers(header, &registry, registry.get_fixed_modification_map(), PeffFormat::Unimod).unwrap()
+    );
+}
+
+#[test]
+/// Checks that each isoform is written as an own entry with its ProForma-tag and a unique identifier
+pub fn test_entry_header_per_isoform() {
+    let registry = ModificationRegistry::from_modifications(&vec![
+        Modification::new("unimod:35", "Oxidation", ModificationPosition::Anywhere, false, 'M', 15.994915)
+    ]);
+    let header = ">PEPTIDE_1 ProForma=PM[UNIMOD:35]EPMK Isoforms=PM[UNIMOD:35]EPMK,PMEPM[UNIMOD:35]K";
+    let peff_headers: Vec<String> = peff::get_entry_headers(header, &registry, registry.get_fixed_modification_map(), PeffFormat::Unimod).unwrap();
+    assert_eq!(2, peff_headers.len());
+    assert!(peff_headers[0].starts_with(">mdg:PEPTIDE_1 \\ModResUnimod=(2|UNIMOD:35|Oxidation) \\ProForma=PM[UNIMOD:35]EPMK "));
+    assert!(peff_headers[1].starts_with(">mdg:PEPTIDE_1_isoform2 \\ModResUnimod=(5|UNIMOD:35|Oxidation) \\ProForma=PMEPM[UNIMOD:35]K "));
+}
+
+#[test]
+/// Checks that modifications without accession of the selected vocabulary are rejected instead of being written to a key Comet ignores
+pub fn test_missing_accession() {
+    let registry = ModificationRegistry::from_modifications(&vec![
+        Modification::new("unimod:35", "Oxidation", ModificationPosition::Anywhere, false, 'M', 15.994915)
+    ]);
+    assert!(peff::check_modifications(&registry, PeffFormat::Unimod).is_ok());
+    assert!(peff::check_modifications(&registry, PeffFormat::PsiMod).is_err());
+    assert!(peff::get_entry_headers(">PEPTIDE_1 ProForma=PEPM[UNIMOD:35]K", &registry, registry.get_fixed_modification_map(), PeffFormat::PsiMod).is_err());
+    // a mass delta without matching modification has no accession
+    assert!(peff::get_entry_headers(">PEPTIDE_1 ProForma=PEPM[+42.0106]K", &registry, registry.get_fixed_modification_map(), PeffFormat::Unimod).is_err());
+    let registry = ModificationRegistry::from_modifications(&vec![
+        Modification::new("custom:1", "Custom", ModificationPosition::Anywhere, false, 'M', 42.0)
+    ]);
+    assert!(peff::check_modifications(&registry, PeffFormat::Unimod).is_err());
+}
@@ -0,0 +1,22 @@
+use std::env;
+use std::fs::File;
+use std::io::prelude::*;
+
+use proteomic::models::mass;
+use proteomic::utility::precursor_reader;
+
+#[test]
+/// Reads a TSV-file with m/z and charge and checks the neutral masses and the fallback ids.
+pub fn test_read_precursors_from_mz_and_charge() {
+    let mut precursor_file_path = env::temp_dir();
+    precursor_file_path.push("max_decoy_test_precursors.tsv");
+    let mut precursor_file = File::create(&precursor_file_path).unwrap();
+    precursor_file.write_all(b"id\tmz\tcharge\nscan=1\t500.5\t2\n\t1001.007276\t1\n").unwrap();
+    let precursors = precursor_reader::read_precursors(precursor_file_path.to_str().unwrap());
+    assert_eq!(precursors.len(), 2);
+    assert_eq!(precursors[0].get_id(), "scan=1");
+    assert_eq!(precursors[0].get_filename_safe_id(), "scan_1");
+    assert_eq!(precursors[0].get_mass(), mass::convert_mass_to_int(mass::thomson_to_dalton(500.5, 2)));
+    assert_eq!(precursors[1].get_id(), "precursor_3");
+    assert_eq!(precursors[1].get_mass(), mass::convert_mass_to_int(mass::thomson_to_dalton(1001.007276, 1)));
+}
@@ -0,0 +1,22 @@
+use proteomic::models::peptides::peptide_properties::PeptideProperty;
+use proteomic::utility::property_filter::PropertyFilter;
+
+#[test]
+/// Checks that a shuffled target passes, because the properties depend only on the composition, and a hydrophobic decoy is rejected.
+pub fn test_rejects_decoys_outside_target_properties() {
+    let targets: Vec<String> = vec!["PEPTIDEK".to_owned(), "DHKRSEGK".to_owned()];
+    let properties: Vec<PeptideProperty> = vec![PeptideProperty::Gravy, PeptideProperty::IsoelectricPoint, PeptideProperty::RetentionIndex];
+    let property_filter = PropertyFilter::new(&targets, &properties, 0.1);
+    assert!(property_filter.is_active());
+    assert!(!property_filter.rejects("KEDITPEP"));
+    assert!(property_filter.rejects("IIVVLLFW"));
+    assert!(property_filter.get_rejection_summary().starts_with("property filter rejections: 1 by "));
+}
+
+#[test]
+/// Checks that the filter is inactive without targets or properties.
+pub fn test_filter_without_targets_is_inactive() {
+    let properties: Vec<PeptideProperty> = vec![PeptideProperty::Gravy];
+    assert!(!PropertyFilter::new(&Vec::new(), &properties, 0.1).is_active());
+    assert!(!PropertyFilter::new(&vec!["PEPTIDEK".to_owned()], &Vec::new(), 0.1).is_active());
+}
@@ -0,0 +1,30 @@
+use proteomic::models::amino_acids::modification::{Modification, ModificationPosition};
+use proteomic::models::amino_acids::modification_registry::ModificationRegistry;
+use proteomic::utility::similarity_filter::SimilarityFilter;
+
+#[test]
+/// Checks if a decoy with one substitution is rejected by edit distance and an unrelated decoy passes.
+pub fn test_rejects_decoys_close_to_targets() {
+    let targets: Vec<String> = vec!["PEPTIDEK".to_owned()];
+    let similarity_filter = SimilarityFilter::new(&targets, &ModificationRegistry::new(), Some(1), None, 20000, None);
+    assert!(similarity_filter.rejects("PEPTADEK"));
+    assert!(!similarity_filter.rejects("WGHYRNSC"));
+    // I and L have the same mass, so they are treated as equal by the filter
+    assert!(similarity_filter.rejects("PEPTLDEK"));
+    assert_eq!(similarity_filter.get_edit_distance_rejections(), 2);
+    assert_eq!(SimilarityFilter::edit_distance("PEPTIDEK", "PEPTLDEK", 3), 1);
+}
+
+#[test]
+/// Checks that the shared fragments include fixed modifications, an oxidized M has almost the mass of F.
+pub fn test_shared_fragments_with_fixed_modifications() {
+    let targets: Vec<String> = vec!["PEPMK".to_owned()];
+    let similarity_filter = SimilarityFilter::new(&targets, &ModificationRegistry::new(), None, Some(0.9), 50000, None);
+    assert!(!similarity_filter.rejects("PEPFK"));
+    let registry = ModificationRegistry::from_modifications(&vec![
+        Modification::new("unimod:35", "Oxidation", ModificationPosition::Anywhere, true, 'M', 15.994915)
+    ]);
+    let similarity_filter = SimilarityFilter::new(&targets, &registry, None, Some(0.9), 50000, None);
+    assert!(similarity_filter.rejects("PEPFK"));
+    assert_eq!(similarity_filter.get_shared_fragment_rejections(), 1);
+}
@@ -0,0 +1,22 @@
+use std::env;
+
+use proteomic::utility::target_index::TargetIndex;
+
+#[test]
+/// Inserts some targets, writes the index to a file, reads it again and checks that no inserted target is missing.
+pub fn test_saved_index_contains_all_targets() {
+    let targets: Vec<&str> = vec!["PEPTJDEK", "DHWVHVJVPMGFVJGCYJDR", "AAAAK", "MGR"];
+    let mut target_index = TargetIndex::new(targets.len() as u64, 0.01);
+    for target in targets.iter() {
+        target_index.insert(target);
+    }
+    let mut index_file = env::temp_dir();
+    index_file.push("max_decoy_test_target_index.bin");
+    target_index.save(index_file.to_str().unwrap());
+    let loaded_target_index = TargetIndex::load(index_file.to_str().unwrap());
+    assert_eq!(loaded_target_index.get_number_of_sequences(), targets.len() as u64);
+    assert_eq!(loaded_target_index.get_max_peptide_id(), target_index.get_max_peptide_id());
+    for target in targets.iter() {
+        assert!(loaded_target_index.might_contain(target));
+    }
+}
@@ -0,0 +1,62 @@
+use std::env;
+use std::fs::File;
+use std::io::prelude::*;
+
+use proteomic::models::mass;
+use proteomic::models::amino_acids::modification::{ModificationPosition, ANY_AMINO_ACID};
+use proteomic::utility::unimod_reader::UnimodReader;
+
+const UNIMOD_XML: &[u8] = br#"<?xml version="1.0" encoding="UTF-8"?>
+<umod:unimod xmlns:umod="http://www.unimod.org/xmlns/schema/unimod_2">
+  <umod:modifications>
+    <umod:mod title="Acetyl" full_name="Acetylation" record_id="1">
+      <umod:specificity hidden="0" site="N-term" position="Protein N-term" classification="Post-translational" spec_group="5"/>
+      <umod:specificity hidden="0" site="N-term" position="Any N-term" classification="Multiple" spec_group="2"/>
+      <umod:delta mono_mass="42.010565" avge_mass="42.0367" composition="H(2) C(2) O">
+        <umod:element symbol="H" number="2"/>
+      </umod:delta>
+    </umod:mod>
+    <umod:mod title="Oxidation" full_name="Oxidation or Hydroxylation" record_id="35">
+      <umod:specificity hidden="0" site="M" position="Anywhere" classification="Post-translational" spec_group="1">
+        <umod:NeutralLoss mono_mass="63.998285" avge_mass="64.1069" flag="false" composition="H(4) C O S">
+          <umod:element symbol="H" number="4"/>
+        </umod:NeutralLoss>
+        <umod:NeutralLoss mono_mass="0" avge_mass="0" flag="false" composition="0"/>
+      </umod:specificity>
+      <umod:delta mono_mass="15.994915" avge_mass="15.9994" composition="O"/>
+    </umod:mod>
+  </umod:modifications>
+</umod:unimod>
+"#;
+
+#[test]
+/// Reads a small Unimod-file and checks masses, positions and neutral losses of the referenced modifications
+pub fn test_create_modifications_from_unimod_references() {
+    let mut unimod_file_path = env::temp_dir();
+    unimod_file_path.push("max_decoy_test_unimod.xml");
+    File::create(&unimod_file_path).unwrap().write_all(UNIMOD_XML).unwrap();
+    let reader = UnimodReader::new(unimod_file_path.to_str().unwrap());
+    let modifications = reader.create_modifications(&vec![
+        "UNIMOD:35@M variable".to_owned(),
+        "UNIMOD:1@N-term fixed".to_owned(),
+        "unimod:1@Protein N-term variable".to_owned()
+    ]);
+    assert_eq!(modifications[0].get_accession(), "unimod:35");
+    assert_eq!(modifications[0].get_name(), "Oxidation");
+    assert!(!modifications[0].is_fix());
+    assert_eq!(modifications[0].get_mono_mass(), mass::convert_mass_to_int(15.994915));
+    assert_eq!(modifications[0].get_average_mass(), mass::convert_mass_to_int(15.9994));
+    assert_eq!(modifications[0].get_neutral_losses().len(), 1);
+    assert_eq!(modifications[0].get_neutral_losses()[0].get_mono_mass(), mass::convert_mass_to_int(63.998285));
+    assert_eq!(modifications[1].get_amino_acid_one_letter_code(), ANY_AMINO_ACID);
+    assert_eq!(modifications[1].get_position(), ModificationPosition::NTerminus);
+    assert_eq!(modifications[1].get_composition(), "H(2) C(2) O");
+    assert_eq!(modifications[2].get_position(), ModificationPosition::ProteinNTerminus);
+}
+
+#[test]
+#[should_panic]
+/// Checks that references without modification type are rejected
+pub fn test_reference_without_type() {
+    UnimodReader::parse_reference("UNIMOD:35@M");
+}
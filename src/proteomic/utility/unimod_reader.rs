@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::Path;
+
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use quick_xml::events::BytesStart;
+
+use proteomic::models::amino_acids::modification::{Modification, ModificationPosition, ANY_AMINO_ACID};
+use proteomic::models::mass::neutral_loss::NeutralLoss;
+
+const UNIMOD_ACCESSION_PREFIX: &str = "UNIMOD:";
+
+/// Site and position where a Unimod modification may occure, including its neutral losses
+pub struct UnimodSpecificity {
+    site: String,
+    position: String,
+    neutral_losses: Vec<NeutralLoss>
+}
+
+impl UnimodSpecificity {
+    pub fn get_site(&self) -> &str {
+        return self.site.as_str();
+    }
+
+    pub fn get_position(&self) -> &str {
+        return self.position.as_str();
+    }
+
+    pub fn get_neutral_losses(&self) -> &Vec<NeutralLoss> {
+        return &self.neutral_losses;
+    }
+
+    /// Converts the Unimod position, e.g. "Anywhere", "Any N-term" or "Protein C-term", to the ModificationPosition
+    fn get_modification_position(&self) -> Option<ModificationPosition> {
+        return match self.position.as_str() {
+            "Anywhere" => Some(ModificationPosition::Anywhere),
+            "Any N-term" => Some(ModificationPosition::NTerminus),
+            "Any C-term" => Some(ModificationPosition::CTerminus),
+            "Protein N-term" => Some(ModificationPosition::ProteinNTerminus),
+            "Protein C-term" => Some(ModificationPosition::ProteinCTerminus),
+            _ => None
+        };
+    }
+
+    /// Returns the amino acid one letter code of the site. Sites "N-term" and "C-term" apply to any amino acid.
+    /// Because sequences are generalized, I and L are returned as J.
+    fn get_amino_acid_one_letter_code(&self) -> Option<char> {
+        return match self.site.as_str() {
+            "N-term" | "C-term" => Some(ANY_AMINO_ACID),
+            "I" | "L" => Some('J'),
+            site if site.len() == 1 => site.chars().next().map(|site| site.to_ascii_uppercase()),
+            _ => None
+        };
+    }
+}
+
+/// Modification definition of the Unimod database
+pub struct UnimodDefinition {
+    record_id: u32,
+    title: String,
+    mono_mass: f64,
+    average_mass: f64,
+    composition: String,
+    specificities: Vec<UnimodSpecificity>
+}
+
+impl UnimodDefinition {
+    pub fn get_record_id(&self) -> u32 {
+        return self.record_id;
+    }
+
+    pub fn get_title(&self) -> &str {
+        return self.title.as_str();
+    }
+
+    pub fn get_mono_mass(&self) -> f64 {
+        return self.mono_mass;
+    }
+
+    pub fn get_average_mass(&self) -> f64 {
+        return self.average_mass;
+    }
+
+    pub fn get_composition(&self) -> &str {
+        return self.composition.as_str();
+    }
+
+    pub fn get_specificities(&self) -> &Vec<UnimodSpecificity> {
+        return &self.specificities;
+    }
+
+    /// Creates the modification for the given site, which is an amino acid one letter code, "N-term", "C-term", "Protein N-term" or "Protein C-term".
+    /// If the site has several specificities, the one which is not restricted to protein termini is used.
+    pub fn to_modification(&self, site: &str, is_fix: bool) -> Modification {
+        return match self.try_to_modification(site, is_fix) {
+            Ok(modification) => modification,
+            Err(err) => panic!("proteomic::utility::unimod_reader::UnimodDefinition.to_modification(): {}", err)
+        };
+    }
+
+    /// Same as to_modification() but returns an error message instead of panicking
+    pub fn try_to_modification(&self, site: &str, is_fix: bool) -> Result<Modification, String> {
+        let (site, protein_position): (&str, Option<&str>) = match site {
+            "Protein N-term" => ("N-term", Some("Protein N-term")),
+            "Protein C-term" => ("C-term", Some("Protein C-term")),
+            _ => (site, None)
+        };
+        let mut matching_specificities: Vec<&UnimodSpecificity> = self.specificities.iter().filter(|specificity| {
+            (specificity.get_site().eq_ignore_ascii_case(site)) & match protein_position {
+                Some(position) => specificity.get_position() == position,
+                None => true
+            }
+        }).collect();
+        // prefer specificities which are not restricted to protein termini
+        matching_specificities.sort_by_key(|specificity| specificity.get_modification_position().map(|position| position.is_protein_terminal()));
+        let specificity = match matching_specificities.first() {
+            Some(specificity) => specificity,
+            None => return Err(format!("{}{} ({}) has no specificity for site '{}'", UNIMOD_ACCESSION_PREFIX, self.record_id, self.title, site))
+        };
+        let position: ModificationPosition = match specificity.get_modification_position() {
+            Some(position) => position,
+            None => return Err(format!("{}{} ({}) has the unknown position '{}'", UNIMOD_ACCESSION_PREFIX, self.record_id, self.title, specificity.get_position()))
+        };
+        let amino_acid_one_letter_code: char = match specificity.get_amino_acid_one_letter_code() {
+            Some(amino_acid_one_letter_code) => amino_acid_one_letter_code,
+            None => return Err(format!("{}{} ({}) has the unknown site '{}'", UNIMOD_ACCESSION_PREFIX, self.record_id, self.title, specificity.get_site()))
+        };
+        let mut modification = Modification::new(
+            format!("{}{}", UNIMOD_ACCESSION_PREFIX, self.record_id).as_str(),
+            self.title.as_str(),
+            position,
+            is_fix,
+            amino_acid_one_letter_code,
+            self.mono_mass
+        );
+        modification.set_average_mass(self.average_mass);
+        modification.set_composition(self.composition.as_str());
+        for neutral_loss in specificity.get_neutral_losses().iter() {
+            modification.add_neutral_loss(neutral_loss.clone());
+        }
+        return Ok(modification);
+    }
+}
+
+/// Reads modification definitions from a local copy of unimod.xml (http://www.unimod.org/xml/unimod.xml)
+pub struct UnimodReader {
+    file_path: String
+}
+
+impl UnimodReader {
+    pub fn new(file_path: &str) -> Self {
+        return Self {
+            file_path: file_path.to_owned()
+        };
+    }
+
+    /// Returns the definitions mapped by their record id
+    pub fn get_definitions(&self) -> Box<HashMap<u32, UnimodDefinition>> {
+        let mut definitions: HashMap<u32, UnimodDefinition> = HashMap::new();
+        let mut reader = match Reader::from_file(Path::new(self.file_path.as_str())) {
+            Ok(reader) => reader,
+            Err(err) => panic!("proteomic::utility::unimod_reader::UnimodReader.get_definitions(): error when reading Unimod-file, original error: {:?}", err)
+        };
+        let mut buf = Vec::new();
+        let mut definition: Option<UnimodDefinition> = None;
+        let mut specificity: Option<UnimodSpecificity> = None;
+        loop {
+            match reader.read_event(&mut buf) {
+                // entering tags: <tag (attr1="" attr2="" ...)>
+                Ok(Event::Start(ref tag)) => {
+                    let attributes: HashMap<String, String> = Self::get_attributes(&reader, tag);
+                    Self::handle_start_tag(Self::get_local_name(tag.name()), &attributes, &mut definition, &mut specificity, reader.buffer_position());
+                },
+                // empty tags: <tag (attr1="" attr2="" ...)/>, which are entered and left at once
+                Ok(Event::Empty(ref tag)) => {
+                    let attributes: HashMap<String, String> = Self::get_attributes(&reader, tag);
+                    Self::handle_start_tag(Self::get_local_name(tag.name()), &attributes, &mut definition, &mut specificity, reader.buffer_position());
+                    Self::handle_end_tag(Self::get_local_name(tag.name()), &mut definition, &mut specificity, &mut definitions);
+                },
+                // leaving of tags: </tag>
+                Ok(Event::End(ref tag)) => Self::handle_end_tag(Self::get_local_name(tag.name()), &mut definition, &mut specificity, &mut definitions),
+                Ok(Event::Eof) => break, // exits the loop when reaching end of file
+                Err(e) => panic!("proteomic::utility::unimod_reader::UnimodReader.get_definitions(): Error at position {}: {:?}", reader.buffer_position(), e),
+                _ => (), // There are several other `Event`s we do not consider here
+            }
+            // if we don't keep a borrow elsewhere, we can clear the buffer to keep memory usage low
+            buf.clear();
+        }
+        return Box::new(definitions);
+    }
+
+    fn handle_start_tag(local_name: &[u8], attributes: &HashMap<String, String>, definition: &mut Option<UnimodDefinition>, specificity: &mut Option<UnimodSpecificity>, buffer_position: usize) {
+        match local_name {
+            b"mod" => *definition = Some(UnimodDefinition {
+                record_id: match attributes.get("record_id").and_then(|record_id| record_id.parse::<u32>().ok()) {
+                    Some(record_id) => record_id,
+                    None => panic!("proteomic::utility::unimod_reader::UnimodReader::handle_start_tag(): modification at position {} has no valid record_id", buffer_position)
+                },
+                title: attributes.get("title").cloned().unwrap_or(String::new()),
+                mono_mass: 0.0,
+                average_mass: 0.0,
+                composition: String::new(),
+                specificities: Vec::new()
+            }),
+            b"specificity" => *specificity = Some(UnimodSpecificity {
+                site: attributes.get("site").cloned().unwrap_or(String::new()),
+                position: attributes.get("position").cloned().unwrap_or(String::new()),
+                neutral_losses: Vec::new()
+            }),
+            b"NeutralLoss" => if let Some(ref mut specificity) = specificity {
+                let mono_mass: f64 = Self::parse_mass_attribute(attributes, "mono_mass");
+                // Unimod lists a zero loss, if the modification may also be observed without loss
+                if mono_mass != 0.0 {
+                    specificity.neutral_losses.push(NeutralLoss::new_custom(
+                        attributes.get("composition").map(|composition| composition.as_str()).unwrap_or(""),
+                        mono_mass,
+                        Self::parse_mass_attribute(attributes, "avge_mass")
+                    ));
+                }
+            },
+            b"delta" => if let Some(ref mut definition) = definition {
+                definition.mono_mass = Self::parse_mass_attribute(attributes, "mono_mass");
+                definition.average_mass = Self::parse_mass_attribute(attributes, "avge_mass");
+                definition.composition = attributes.get("composition").cloned().unwrap_or(String::new());
+            },
+            _ => ()
+        }
+    }
+
+    fn handle_end_tag(local_name: &[u8], definition: &mut Option<UnimodDefinition>, specificity: &mut Option<UnimodSpecificity>, definitions: &mut HashMap<u32, UnimodDefinition>) {
+        match local_name {
+            b"specificity" => if let (Some(definition), Some(specificity)) = (definition.as_mut(), specificity.take()) {
+                definition.specificities.push(specificity);
+            },
+            b"mod" => if let Some(definition) = definition.take() {
+                definitions.insert(definition.get_record_id(), definition);
+            },
+            _ => ()
+        }
+    }
+
+    /// Creates modifications from references like `UNIMOD:35@M variable` or `UNIMOD:1@Protein N-term fixed`.
+    /// The site is an amino acid one letter code, "N-term", "C-term", "Protein N-term" or "Protein C-term".
+    pub fn create_modifications(&self, references: &Vec<String>) -> Box<Vec<Modification>> {
+        let definitions = self.get_definitions();
+        let mut modifications: Vec<Modification> = Vec::new();
+        for reference in references.iter() {
+            match Self::try_create_modification(&definitions, reference) {
+                Ok(modification) => modifications.push(modification),
+                Err(err) => panic!("proteomic::utility::unimod_reader::UnimodReader.create_modifications(): {} (Unimod-file: '{}')", err, self.file_path)
+            }
+        }
+        return Box::new(modifications);
+    }
+
+    /// Creates the modification for a reference like `UNIMOD:35@M variable` from the given definitions
+    pub fn try_create_modification(definitions: &HashMap<u32, UnimodDefinition>, reference: &str) -> Result<Modification, String> {
+        let (record_id, site, is_fix) = Self::try_parse_reference(reference)?;
+        return match definitions.get(&record_id) {
+            Some(definition) => definition.try_to_modification(site.as_str(), is_fix),
+            None => Err(format!("{}{} not found", UNIMOD_ACCESSION_PREFIX, record_id))
+        };
+    }
+
+    /// Parses a reference like `UNIMOD:35@M variable` to (record id, site, is fix)
+    pub fn parse_reference(reference: &str) -> (u32, String, bool) {
+        return match Self::try_parse_reference(reference) {
+            Ok(parsed_reference) => parsed_reference,
+            Err(err) => panic!("proteomic::utility::unimod_reader::UnimodReader::parse_reference(): {}", err)
+        };
+    }
+
+    /// Same as parse_reference() but returns an error message instead of panicking
+    pub fn try_parse_reference(reference: &str) -> Result<(u32, String, bool), String> {
+        let reference = reference.trim();
+        let (accession, site_and_type) = match reference.find('@') {
+            Some(idx) => (&reference[..idx], &reference[idx + 1..]),
+            None => return Err(format!("'{}' has no site, use e.g. 'UNIMOD:35@M variable'", reference))
+        };
+        if !accession.to_ascii_uppercase().starts_with(UNIMOD_ACCESSION_PREFIX) {
+            return Err(format!("'{}' does not start with '{}'", reference, UNIMOD_ACCESSION_PREFIX));
+        }
+        let record_id: u32 = match accession[UNIMOD_ACCESSION_PREFIX.len()..].trim().parse::<u32>() {
+            Ok(record_id) => record_id,
+            Err(_) => return Err(format!("'{}' has no valid record id", reference))
+        };
+        let (site, modification_type) = match site_and_type.trim().rfind(char::is_whitespace) {
+            Some(idx) => (site_and_type.trim()[..idx].trim(), site_and_type.trim()[idx + 1..].to_lowercase()),
+            None => return Err(format!("'{}' has no modification type, use 'fixed' or 'variable'", reference))
+        };
+        let is_fix: bool = match modification_type.as_str() {
+            "fixed" => true,
+            "variable" => false,
+            _ => return Err(format!("'{}' has an unknown modification type, use 'fixed' or 'variable'", reference))
+        };
+        return Ok((record_id, site.to_owned(), is_fix));
+    }
+
+    /// Returns the tag name without namespace prefix, e.g. `umod:mod` => `mod`
+    fn get_local_name(name: &[u8]) -> &[u8] {
+        return match name.iter().position(|byte| *byte == b':') {
+            Some(idx) => &name[idx + 1..],
+            None => name
+        };
+    }
+
+    fn get_attributes<B: BufRead>(reader: &Reader<B>, tag: &BytesStart) -> HashMap<String, String> {
+        let mut attributes: HashMap<String, String> = HashMap::new();
+        for attribute_result in tag.attributes() {
+            let attribute = match attribute_result {
+                Ok(attribute) => attribute,
+                Err(err) => panic!("proteomic::utility::unimod_reader::UnimodReader::get_attributes(): Error at position {}: {:?}", reader.buffer_position(), err)
+            };
+            let key: String = String::from_utf8_lossy(attribute.key).into_owned();
+            match attribute.unescape_and_decode_value(reader) {
+                Ok(value) => attributes.insert(key, value),
+                Err(err) => panic!("proteomic::utility::unimod_reader::UnimodReader::get_attributes(): Error at position {}: {:?}", reader.buffer_position(), err)
+            };
+        }
+        return attributes;
+    }
+
+    fn parse_mass_attribute(attributes: &HashMap<String, String>, name: &str) -> f64 {
+        return match attributes.get(name) {
+            Some(mass) => match mass.trim().parse::<f64>() {
+                Ok(mass) => mass,
+                Err(_) => panic!("proteomic::utility::unimod_reader::UnimodReader::parse_mass_attribute(): can not parse {} '{}' to float", name, mass)
+            },
+            None => 0.0
+        };
+    }
+}